@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 
 use rayon::prelude::*;
-use shared::{Board, Level, Mage, Team};
+use shared::{Board, Level, Mage, Simulation, Team};
 
 fn generate_levels() -> Vec<Level> {
     (0..25)
@@ -58,16 +58,36 @@ fn main() {
     generate_levels()
         .par_iter()
         .map(|level| {
-            let simulations = Level::simulate(level, N, seed);
-            let result = simulations
-                .par_iter()
+            let code = match level.as_code() {
+                Ok(code) => code,
+                Err(error) => {
+                    eprintln!("skipping level: {error}");
+                    return;
+                }
+            };
+
+            let report = (0..N as u64)
+                .into_par_iter()
                 .map(|game| {
-                    // println!("  {:?}", (game.turns(), game.evaluate()));
-                    (game.turns(), game.evaluate())
+                    let mut simulation = Simulation::new(level, 1, game.wrapping_add(seed));
+                    simulation.play_out();
+                    simulation
+                })
+                .reduce_with(|mut simulation, other| {
+                    simulation.merge(other);
+                    simulation
                 })
-                .reduce(|| (0, 0), |(aa, ba), (ab, bb)| (aa + ab, ba + bb));
+                .map(|simulation| simulation.report())
+                .unwrap_or_default();
 
-            println!("{}, {}, {}", level.as_code(), result.0, result.1);
+            println!(
+                "{}, {:.1}, {:.2}, {:.2}, {:.2}",
+                code,
+                report.average_length,
+                report.red_rate(),
+                report.blue_rate(),
+                report.first_move_advantage,
+            );
         })
         .count();
 }
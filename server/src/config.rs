@@ -0,0 +1,246 @@
+use std::{fmt::Display, fs, net::SocketAddr, path::PathBuf, time::Duration};
+
+use clap::Parser;
+use serde::Deserialize;
+
+/// Longest timeout, interval or lifetime in seconds, which keeps deadlines computed from them far from overflowing.
+const MAX_SECONDS: u64 = 100 * 365 * 24 * 60 * 60;
+
+/// An error in reading the configuration file, or a configured value out of range.
+#[derive(Debug)]
+pub struct ConfigError(String);
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Configuration of the server.
+/// Values are taken from command line arguments, then environment variables, then a TOML file, then the defaults.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Address the server listens on.
+    pub bind: SocketAddr,
+    /// Directory served under `/static`.
+    pub static_dir: PathBuf,
+    /// Page served under `/`.
+    pub index: PathBuf,
+    /// Directory of the lobby snapshots and event logs.
+    pub data_dir: PathBuf,
+    /// Where lobbies are stored, `sqlite:<path>` for an SQLite database or else a directory. The data directory by default.
+    pub storage: Option<String>,
+    /// Seconds without a heartbeat after which a player is no longer considered connected.
+    pub heartbeat_timeout: u64,
+    /// Seconds a player may be disconnected from a game in progress before forfeiting it.
+    pub forfeit_timeout: u64,
+    /// Seconds a finished game is kept without a heartbeat from any of its players.
+    pub finished_timeout: u64,
+    /// Seconds a lobby is kept if nobody joins it.
+    pub unjoined_timeout: u64,
+    /// Seconds between checks for lobbies to reap.
+    pub reap_interval: u64,
+    /// Number of lobbies after which no more can be created.
+    pub max_lobbies: usize,
+    /// Origins allowed to make cross-origin requests. None are by default.
+    pub cors_origins: Vec<String>,
+    /// Secret that session tokens are signed with. A random one is generated by default, which invalidates all sessions on restart.
+    pub session_secret: Option<String>,
+    /// Seconds a session token is valid for before it has to be refreshed.
+    pub session_lifetime: u64,
+    /// Requests per second allowed from one address, and separately from one session. Zero disables the limit.
+    pub rate_limit: u32,
+    /// Requests allowed in a burst beyond the rate limit.
+    pub rate_burst: u32,
+    /// Lobbies and sessions one address may create per minute. Zero disables the limit.
+    pub create_limit: u32,
+    /// Whether to take client addresses from the `X-Forwarded-For` header, when running behind a reverse proxy.
+    /// The last address in the header is taken, which is the one appended by the proxy in front of the server.
+    pub trust_forwarded_for: bool,
+    /// Bytes a request body may have.
+    pub max_body_size: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            bind: SocketAddr::from(([127, 0, 0, 1], 8000)),
+            static_dir: PathBuf::from("static"),
+            index: PathBuf::from("html/game.html"),
+            data_dir: PathBuf::from("lobbies"),
+            storage: None,
+            heartbeat_timeout: 15,
+            forfeit_timeout: 60,
+            finished_timeout: 5 * 60,
+            unjoined_timeout: 2 * 60,
+            reap_interval: 5,
+            max_lobbies: 1024,
+            cors_origins: Vec::new(),
+            session_secret: None,
+            session_lifetime: 7 * 24 * 60 * 60,
+            rate_limit: 10,
+            rate_burst: 30,
+            create_limit: 10,
+            trust_forwarded_for: false,
+            max_body_size: 64 * 1024,
+        }
+    }
+}
+
+/// Command line arguments, each of which can also be given as an environment variable.
+#[derive(Debug, Parser)]
+#[command(about = "Serves maginet lobbies")]
+struct Args {
+    /// TOML file to read the configuration from
+    #[arg(long, env = "MAGINET_CONFIG")]
+    config: Option<PathBuf>,
+    /// Address to listen on
+    #[arg(long, env = "MAGINET_BIND")]
+    bind: Option<SocketAddr>,
+    /// Directory served under /static
+    #[arg(long, env = "MAGINET_STATIC_DIR")]
+    static_dir: Option<PathBuf>,
+    /// Page served under /
+    #[arg(long, env = "MAGINET_INDEX")]
+    index: Option<PathBuf>,
+    /// Directory of the lobby snapshots and event logs
+    #[arg(long, env = "MAGINET_DATA_DIR")]
+    data_dir: Option<PathBuf>,
+    /// Where lobbies are stored, sqlite:<path> for an SQLite database or else a directory
+    #[arg(long, env = "MAGINET_STORAGE")]
+    storage: Option<String>,
+    /// Seconds without a heartbeat after which a player is disconnected
+    #[arg(long, env = "MAGINET_HEARTBEAT_TIMEOUT")]
+    heartbeat_timeout: Option<u64>,
+    /// Seconds a disconnected player has to return before forfeiting
+    #[arg(long, env = "MAGINET_FORFEIT_TIMEOUT")]
+    forfeit_timeout: Option<u64>,
+    /// Seconds a finished game is kept without a heartbeat
+    #[arg(long, env = "MAGINET_FINISHED_TIMEOUT")]
+    finished_timeout: Option<u64>,
+    /// Seconds a lobby is kept if nobody joins it
+    #[arg(long, env = "MAGINET_UNJOINED_TIMEOUT")]
+    unjoined_timeout: Option<u64>,
+    /// Seconds between checks for lobbies to reap
+    #[arg(long, env = "MAGINET_REAP_INTERVAL")]
+    reap_interval: Option<u64>,
+    /// Number of lobbies after which no more can be created
+    #[arg(long, env = "MAGINET_MAX_LOBBIES")]
+    max_lobbies: Option<usize>,
+    /// Origins allowed to make cross-origin requests, separated by commas
+    #[arg(long, env = "MAGINET_CORS_ORIGINS", value_delimiter = ',')]
+    cors_origins: Option<Vec<String>>,
+    /// Secret that session tokens are signed with
+    #[arg(long, env = "MAGINET_SESSION_SECRET", hide_env_values = true)]
+    session_secret: Option<String>,
+    /// Seconds a session token is valid for
+    #[arg(long, env = "MAGINET_SESSION_LIFETIME")]
+    session_lifetime: Option<u64>,
+    /// Requests per second allowed from one address or session
+    #[arg(long, env = "MAGINET_RATE_LIMIT")]
+    rate_limit: Option<u32>,
+    /// Requests allowed in a burst beyond the rate limit
+    #[arg(long, env = "MAGINET_RATE_BURST")]
+    rate_burst: Option<u32>,
+    /// Lobbies and sessions one address may create per minute
+    #[arg(long, env = "MAGINET_CREATE_LIMIT")]
+    create_limit: Option<u32>,
+    /// Whether to take client addresses from the X-Forwarded-For header
+    #[arg(long, env = "MAGINET_TRUST_FORWARDED_FOR")]
+    trust_forwarded_for: Option<bool>,
+    /// Bytes a request body may have
+    #[arg(long, env = "MAGINET_MAX_BODY_SIZE")]
+    max_body_size: Option<usize>,
+}
+
+impl Config {
+    /// Loads the configuration from the command line, the environment and the configuration file they name.
+    pub fn load() -> Result<Config, ConfigError> {
+        let args = Args::parse();
+
+        let config = match &args.config {
+            Some(path) => {
+                let text = fs::read_to_string(path).map_err(|error| {
+                    ConfigError(format!("could not read {}: {error}", path.display()))
+                })?;
+
+                toml::from_str(&text).map_err(|error| {
+                    ConfigError(format!("could not parse {}: {error}", path.display()))
+                })?
+            }
+            None => Config::default(),
+        };
+
+        let config = config.overridden_by(args);
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /// Rejects values the server cannot run with, such as a zero interval or a lifetime that overflows.
+    fn validate(&self) -> Result<(), ConfigError> {
+        let seconds = [
+            ("heartbeat_timeout", self.heartbeat_timeout),
+            ("forfeit_timeout", self.forfeit_timeout),
+            ("finished_timeout", self.finished_timeout),
+            ("unjoined_timeout", self.unjoined_timeout),
+            ("reap_interval", self.reap_interval),
+            ("session_lifetime", self.session_lifetime),
+        ];
+
+        if let Some((name, _)) = seconds.iter().find(|(_, value)| *value > MAX_SECONDS) {
+            return Err(ConfigError(format!(
+                "{name} must be at most {MAX_SECONDS} seconds"
+            )));
+        }
+
+        let positive = [
+            ("heartbeat_timeout", self.heartbeat_timeout),
+            ("reap_interval", self.reap_interval),
+            ("session_lifetime", self.session_lifetime),
+            ("rate_burst", u64::from(self.rate_burst)),
+            ("max_body_size", self.max_body_size as u64),
+        ];
+
+        if let Some((name, _)) = positive.iter().find(|(_, value)| *value == 0) {
+            return Err(ConfigError(format!("{name} must be greater than zero")));
+        }
+
+        Ok(())
+    }
+
+    fn overridden_by(self, args: Args) -> Config {
+        Config {
+            bind: args.bind.unwrap_or(self.bind),
+            static_dir: args.static_dir.unwrap_or(self.static_dir),
+            index: args.index.unwrap_or(self.index),
+            data_dir: args.data_dir.unwrap_or(self.data_dir),
+            storage: args.storage.or(self.storage),
+            heartbeat_timeout: args.heartbeat_timeout.unwrap_or(self.heartbeat_timeout),
+            forfeit_timeout: args.forfeit_timeout.unwrap_or(self.forfeit_timeout),
+            finished_timeout: args.finished_timeout.unwrap_or(self.finished_timeout),
+            unjoined_timeout: args.unjoined_timeout.unwrap_or(self.unjoined_timeout),
+            reap_interval: args.reap_interval.unwrap_or(self.reap_interval),
+            max_lobbies: args.max_lobbies.unwrap_or(self.max_lobbies),
+            cors_origins: args.cors_origins.unwrap_or(self.cors_origins),
+            session_secret: args.session_secret.or(self.session_secret),
+            session_lifetime: args.session_lifetime.unwrap_or(self.session_lifetime),
+            rate_limit: args.rate_limit.unwrap_or(self.rate_limit),
+            rate_burst: args.rate_burst.unwrap_or(self.rate_burst),
+            create_limit: args.create_limit.unwrap_or(self.create_limit),
+            trust_forwarded_for: args.trust_forwarded_for.unwrap_or(self.trust_forwarded_for),
+            max_body_size: args.max_body_size.unwrap_or(self.max_body_size),
+        }
+    }
+
+    /// Returns how long a player is considered connected after a heartbeat.
+    pub fn heartbeat_timeout(&self) -> Duration {
+        Duration::from_secs(self.heartbeat_timeout)
+    }
+
+    /// Returns how long a session token is valid for.
+    pub fn session_lifetime(&self) -> Duration {
+        Duration::from_secs(self.session_lifetime)
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{
+        header::{AUTHORIZATION, RETRY_AFTER},
+        HeaderMap, Request, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use shared::{timestamp, LobbyError, Message};
+
+use crate::{config::Config, AppState};
+
+/// Interval at which buckets that have refilled completely are forgotten.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A token bucket per key, refilled continuously at a fixed rate up to its burst size.
+/// A rate of zero disables the limiter.
+pub struct RateLimiter {
+    per_second: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+impl RateLimiter {
+    pub fn new(per_second: f64, burst: u32) -> RateLimiter {
+        RateLimiter {
+            per_second,
+            burst: f64::from(burst.max(1)),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token from the bucket of a key, or returns how long it takes until one is available.
+    pub fn check(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        if self.per_second <= 0.0 {
+            return Ok(());
+        }
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let (tokens, updated) = buckets.entry(key.to_string()).or_insert((self.burst, now));
+
+        *tokens = (*tokens + now.duration_since(*updated).as_secs_f64() * self.per_second)
+            .min(self.burst);
+        *updated = now;
+
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - *tokens) / self.per_second))
+        }
+    }
+
+    /// Forgets the buckets that have refilled completely, as they are no different from new ones.
+    pub fn prune(&self, now: Instant) {
+        if self.per_second <= 0.0 {
+            return;
+        }
+
+        // Slow rates with large bursts take longer to refill than a `Duration` can hold.
+        let refill =
+            Duration::try_from_secs_f64(self.burst / self.per_second).unwrap_or(Duration::MAX);
+
+        self.buckets
+            .lock()
+            .unwrap()
+            .retain(|_, (_, updated)| now.duration_since(*updated) < refill);
+    }
+}
+
+/// The rate limits applied to requests, by address and by session.
+pub struct RateLimits {
+    /// Limits all requests from one address.
+    addresses: RateLimiter,
+    /// Limits all requests made with one session token.
+    sessions: RateLimiter,
+    /// Limits the lobbies and sessions created from one address, which are far more costly than other requests.
+    creations: RateLimiter,
+    /// Whether to take the address of a client from the `X-Forwarded-For` header of a reverse proxy.
+    trust_forwarded_for: bool,
+}
+
+impl RateLimits {
+    /// Takes the limits from the [`Config`].
+    pub fn from_config(config: &Config) -> RateLimits {
+        RateLimits {
+            addresses: RateLimiter::new(f64::from(config.rate_limit), config.rate_burst),
+            sessions: RateLimiter::new(f64::from(config.rate_limit), config.rate_burst),
+            creations: RateLimiter::new(f64::from(config.create_limit) / 60.0, config.create_limit),
+            trust_forwarded_for: config.trust_forwarded_for,
+        }
+    }
+
+    fn prune(&self, now: Instant) {
+        self.addresses.prune(now);
+        self.sessions.prune(now);
+        self.creations.prune(now);
+    }
+
+    /// Determines the address a request came from, which is that of the proxy unless it is trusted.
+    /// A trusted proxy appends the address it was reached from to `X-Forwarded-For`, so the last entry is taken;
+    /// earlier entries are sent by the client and can be forged.
+    fn address(&self, headers: &HeaderMap, peer: SocketAddr) -> IpAddr {
+        self.trust_forwarded_for
+            .then(|| headers.get("x-forwarded-for"))
+            .flatten()
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|address| address.trim().parse().ok())
+            .unwrap_or(peer.ip())
+    }
+}
+
+/// Returns the session token sent with a request, in the same places the [`crate::session::Session`] extractor looks.
+fn session_token<B>(request: &Request<B>) -> Option<&str> {
+    request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .or_else(|| {
+            request
+                .uri()
+                .query()?
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("token="))
+        })
+}
+
+/// Rounds the wait for a token up to the whole seconds of a `Retry-After` header, which is never zero.
+fn retry_after(wait: Duration) -> u64 {
+    (wait.as_secs_f64().ceil() as u64).max(1)
+}
+
+/// Rejects requests beyond the rate limits of their address or session with `429 Too Many Requests`.
+/// Session IDs are public, so only verified tokens are charged to a session; others only count against their address.
+pub async fn limit_rate<B>(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let limits = &state.rate_limits;
+    let now = Instant::now();
+    let address = limits.address(request.headers(), peer).to_string();
+    let creating = matches!(request.uri().path(), "/lobby/create" | "/session");
+    let session_id =
+        session_token(&request).and_then(|token| state.sessions.verify(token, timestamp()).ok());
+
+    let checked = limits
+        .addresses
+        .check(&address, now)
+        .and_then(|_| match &session_id {
+            Some(session_id) => limits.sessions.check(session_id, now),
+            None => Ok(()),
+        })
+        .and_then(|_| {
+            if creating {
+                limits.creations.check(&address, now)
+            } else {
+                Ok(())
+            }
+        });
+
+    match checked {
+        Ok(()) => next.run(request).await,
+        Err(wait) => (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(RETRY_AFTER, retry_after(wait).to_string())],
+            Json(Message::LobbyError(LobbyError(
+                "too many requests, try again later".to_string(),
+            ))),
+        )
+            .into_response(),
+    }
+}
+
+pub async fn prune_periodically(state: AppState) {
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        state.rate_limits.prune(Instant::now());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn refills_buckets_over_time() {
+        let limiter = RateLimiter::new(2.0, 3);
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert_eq!(limiter.check("a", now), Ok(()));
+        }
+
+        assert_eq!(limiter.check("a", now), Err(Duration::from_millis(500)));
+        assert_eq!(limiter.check("b", now), Ok(()));
+
+        let later = now + Duration::from_millis(250);
+
+        assert_eq!(limiter.check("a", later), Err(Duration::from_millis(250)));
+        assert_eq!(
+            limiter.check("a", later + Duration::from_millis(250)),
+            Ok(())
+        );
+
+        // Refilling stops at the burst size.
+        let much_later = now + Duration::from_secs(60);
+
+        for _ in 0..3 {
+            assert_eq!(limiter.check("a", much_later), Ok(()));
+        }
+
+        assert!(limiter.check("a", much_later).is_err());
+    }
+
+    #[test]
+    fn disables_zero_rates() {
+        let limiter = RateLimiter::new(0.0, 1);
+        let now = Instant::now();
+
+        for _ in 0..100 {
+            assert_eq!(limiter.check("a", now), Ok(()));
+        }
+    }
+
+    #[test]
+    fn rounds_retry_after_up() {
+        assert_eq!(retry_after(Duration::ZERO), 1);
+        assert_eq!(retry_after(Duration::from_millis(100)), 1);
+        assert_eq!(retry_after(Duration::from_secs(1)), 1);
+        assert_eq!(retry_after(Duration::from_millis(1001)), 2);
+        assert_eq!(retry_after(Duration::from_secs(90)), 90);
+    }
+
+    #[test]
+    fn prunes_refilled_buckets() {
+        let limiter = RateLimiter::new(1.0, 2);
+        let now = Instant::now();
+
+        limiter.check("a", now).unwrap();
+        limiter.check("b", now + Duration::from_secs(1)).unwrap();
+
+        limiter.prune(now + Duration::from_millis(1500));
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 2);
+
+        limiter.prune(now + Duration::from_millis(2500));
+        assert!(!limiter.buckets.lock().unwrap().contains_key("a"));
+        assert!(limiter.buckets.lock().unwrap().contains_key("b"));
+
+        limiter.prune(now + Duration::from_secs(3));
+        assert!(limiter.buckets.lock().unwrap().is_empty());
+
+        // Buckets that would take longer to refill than a `Duration` can hold are kept.
+        let slow = RateLimiter::new(f64::MIN_POSITIVE, u32::MAX);
+        slow.check("a", now).unwrap();
+        slow.prune(now + Duration::from_secs(60));
+        assert_eq!(slow.buckets.lock().unwrap().len(), 1);
+
+        RateLimiter::new(0.0, u32::MAX).prune(now);
+    }
+
+    #[test]
+    fn takes_address_appended_by_proxy() {
+        let peer = SocketAddr::from(([127, 0, 0, 1], 4000));
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "1.2.3.4, 5.6.7.8".parse().unwrap());
+
+        let trusting = RateLimits::from_config(&Config {
+            trust_forwarded_for: true,
+            ..Default::default()
+        });
+
+        assert_eq!(trusting.address(&headers, peer), IpAddr::from([5, 6, 7, 8]));
+        assert_eq!(
+            trusting.address(&HeaderMap::new(), peer),
+            IpAddr::from([127, 0, 0, 1])
+        );
+
+        let distrusting = RateLimits::from_config(&Config::default());
+
+        assert_eq!(
+            distrusting.address(&headers, peer),
+            IpAddr::from([127, 0, 0, 1])
+        );
+    }
+}
@@ -1,56 +1,296 @@
+mod config;
+mod limit;
+mod reaper;
+mod session;
+mod storage;
+
 use std::{
-    collections::HashMap,
-    fs::{self, File},
+    collections::{HashMap, HashSet},
+    convert::Infallible,
+    fs::{self, OpenOptions},
+    io::Write,
     net::SocketAddr,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use axum::{
-    extract::{Json, Path, State},
+    extract::{
+        ws::{self, WebSocket, WebSocketUpgrade},
+        DefaultBodyLimit, Json, Path, State,
+    },
+    http::HeaderValue,
+    middleware,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Response,
+    },
     routing::{get, post},
     Router,
 };
+use config::Config;
+use futures::{stream, Stream};
+use limit::{limit_rate, prune_periodically, RateLimits};
 use rand::Rng;
+use reaper::{reap_periodically, ReapMetrics, ReapRules};
+use session::{Session, SessionKeys};
 use shared::{
-    timestamp, Lobby, LobbyError, LobbySettings, LobbySort, Message, SessionMessage,
-    SessionNewLobby, SessionRequest, Turn,
+    timestamp, GameEvent, Lobby, LobbyError, LobbySettings, LobbySort, Message, Presence,
+    SessionNewLobby, SessionRejoin, SessionToken, Turn,
+};
+use storage::{FileStorage, SqliteStorage, Storage, StorageError};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tower_http::{
+    cors::{Any, CorsLayer},
+    services::{ServeDir, ServeFile},
 };
-use tower_http::services::{ServeDir, ServeFile};
+
+/// Interval at which changed lobbies are written to [`Storage`].
+const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Interval at which open WebSocket connections keep their player's heartbeat alive.
+const SOCKET_HEARTBEAT: Duration = Duration::from_secs(5);
+
+/// A change to a lobby, announced to its WebSocket connections.
+#[derive(Debug, Clone, PartialEq)]
+enum LobbyUpdate {
+    /// The session with this ID acted, and its client has already taken any resulting turn.
+    Acted(String),
+    /// A player joined or left, which is announced before the lobby itself is sent again.
+    Presence(Presence),
+    /// Anything else changed, such as readiness, rematch requests or connections.
+    Changed,
+}
+
+/// The events of a move, with the ID of its lobby and the number of turns taken after it.
+type QueuedEvents = (u16, usize, Vec<GameEvent>);
 
 #[derive(Clone)]
 struct AppState {
+    config: Arc<Config>,
     lobbies: Arc<Mutex<HashMap<u16, Lobby>>>,
+    updates: Arc<Mutex<HashMap<u16, broadcast::Sender<LobbyUpdate>>>>,
+    storage: Arc<dyn Storage>,
+    /// IDs of the lobbies changed or removed since they were last written to storage.
+    dirty: Arc<Mutex<HashSet<u16>>>,
+    /// Events of the moves taken since the last snapshot, with their lobby and turn, to be appended to the event logs.
+    events: Arc<Mutex<Vec<QueuedEvents>>>,
+    reap_rules: ReapRules,
+    reap_metrics: Arc<ReapMetrics>,
+    sessions: Arc<SessionKeys>,
+    rate_limits: Arc<RateLimits>,
+}
+
+impl AppState {
+    /// Subscribes to the [`LobbyUpdate`]s of a lobby.
+    fn subscribe(&self, id: u16) -> broadcast::Receiver<LobbyUpdate> {
+        self.updates
+            .lock()
+            .unwrap()
+            .entry(id)
+            .or_insert_with(|| broadcast::channel(16).0)
+            .subscribe()
+    }
+
+    /// Marks a lobby to be written to storage with the next snapshot.
+    fn mark_dirty(&self, id: u16) {
+        self.dirty.lock().unwrap().insert(id);
+    }
+
+    /// Queues the events of a move to be appended to the event log of a lobby with the next snapshot.
+    fn queue_events(&self, id: u16, turns: usize, events: &[GameEvent]) {
+        if !events.is_empty() {
+            self.events
+                .lock()
+                .unwrap()
+                .push((id, turns, events.to_vec()));
+        }
+    }
+
+    /// Announces a [`LobbyUpdate`] to the WebSocket connections of a lobby, if there are any.
+    fn notify(&self, id: u16, update: LobbyUpdate) {
+        if let Some(sender) = self.updates.lock().unwrap().get(&id) {
+            let _ = sender.send(update);
+        }
+    }
+
+    /// Announces a [`Presence`] change to the connections of a lobby, followed by the changed lobby.
+    fn announce(&self, id: u16, presence: Presence) {
+        self.notify(id, LobbyUpdate::Presence(presence));
+        self.notify(id, LobbyUpdate::Changed);
+    }
 }
 
 #[tokio::main]
 async fn main() {
+    let config = Config::load().unwrap_or_else(|error| {
+        eprintln!("{error}");
+        std::process::exit(1)
+    });
+    let storage = open_storage(&config).unwrap();
+
+    // Restored lobbies which are no longer needed are left to the reaper, which archives them.
+    let mut lobbies = storage.load().unwrap_or_else(|error| {
+        eprintln!("could not restore lobbies: {error}");
+        HashMap::new()
+    });
+
+    for lobby in lobbies.values_mut() {
+        lobby.restore(timestamp());
+    }
+
+    let sessions = match &config.session_secret {
+        Some(secret) => SessionKeys::new(secret.as_bytes(), config.session_lifetime()),
+        None => {
+            eprintln!("no session secret configured, sessions will not survive a restart");
+            SessionKeys::random(config.session_lifetime())
+        }
+    };
+
     let state = AppState {
-        lobbies: Arc::new(Mutex::new(HashMap::new())),
+        config: Arc::new(config.clone()),
+        lobbies: Arc::new(Mutex::new(lobbies)),
+        updates: Arc::new(Mutex::new(HashMap::new())),
+        storage,
+        dirty: Arc::new(Mutex::new(HashSet::new())),
+        events: Arc::new(Mutex::new(Vec::new())),
+        reap_rules: ReapRules::from_config(&config),
+        reap_metrics: Arc::new(ReapMetrics::default()),
+        sessions: Arc::new(sessions),
+        rate_limits: Arc::new(RateLimits::from_config(&config)),
     };
 
-    let app = Router::new()
-        .nest_service("/static", ServeDir::new("static"))
-        .route_service("/", ServeFile::new("html/game.html"))
+    tokio::spawn(snapshot_periodically(state.clone()));
+    tokio::spawn(reap_periodically(state.clone()));
+    tokio::spawn(prune_periodically(state.clone()));
+
+    // Static files are left out of the rate limits, as loading the client takes a burst of them.
+    let api = Router::new()
         .route("/lobbies", get(get_lobbies))
         .route("/lobby/create", post(create_lobby))
         .route("/lobby/:id/turns/:since", post(get_turns_since))
         .route("/lobby/:id/act", post(process_inbound))
         .route("/lobby/:id/ready", post(post_ready))
         .route("/lobby/:id/rematch", post(post_rematch))
-        .route("/lobby/:id/state", get(get_state))
+        .route("/lobby/:id/rejoin", post(post_rejoin))
+        .route("/lobby/:id/leave", post(post_leave))
+        .route("/lobby/:id/state", post(get_state))
+        .route("/lobby/:id/ws", get(lobby_socket))
+        .route("/lobby/:id/watch", get(watch_lobby))
         .route("/session", get(obtain_session))
-        .with_state(state);
+        .route("/session/refresh", post(refresh_session))
+        .route("/metrics", get(get_metrics))
+        .route_layer(middleware::from_fn_with_state(state.clone(), limit_rate))
+        .layer(DefaultBodyLimit::max(config.max_body_size));
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 8000));
+    let app = Router::new()
+        .nest_service("/static", ServeDir::new(&config.static_dir))
+        .route_service("/", ServeFile::new(&config.index))
+        .merge(api)
+        .with_state(state.clone());
 
-    axum::Server::bind(&addr)
-        .serve(app.into_make_service())
+    let app = if config.cors_origins.is_empty() {
+        app
+    } else {
+        let origins: Vec<HeaderValue> = config
+            .cors_origins
+            .iter()
+            .filter_map(|origin| origin.parse().ok())
+            .collect();
+
+        app.layer(
+            CorsLayer::new()
+                .allow_origin(origins)
+                .allow_methods(Any)
+                .allow_headers(Any),
+        )
+    };
+
+    axum::Server::bind(&config.bind)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
         .await
         .unwrap();
+
+    snapshot(&state).await;
+}
+
+/// Opens the [`Storage`] named by the configuration.
+/// `sqlite:<path>` selects an SQLite database, and anything else a directory of JSON files, the data directory by default.
+fn open_storage(config: &Config) -> Result<Arc<dyn Storage>, StorageError> {
+    Ok(match &config.storage {
+        Some(location) => match location.strip_prefix("sqlite:") {
+            Some(path) => Arc::new(SqliteStorage::new(path)?),
+            None => Arc::new(FileStorage::new(location)?),
+        },
+        None => Arc::new(FileStorage::new(&config.data_dir)?),
+    })
+}
+
+async fn snapshot_periodically(state: AppState) {
+    let mut interval = tokio::time::interval(SNAPSHOT_INTERVAL);
+
+    loop {
+        interval.tick().await;
+        snapshot(&state).await;
+    }
+}
+
+/// Writes the lobbies changed since the last snapshot to storage, removes those that are gone and appends the queued events to the event logs.
+/// The lobbies are cloned under the lock and written outside of it, so requests are not held up by the writes.
+async fn snapshot(state: &AppState) {
+    let changes: Vec<(u16, Option<Lobby>)> = {
+        let lobbies = state.lobbies.lock().unwrap();
+        let mut dirty = state.dirty.lock().unwrap();
+
+        dirty
+            .drain()
+            .map(|id| (id, lobbies.get(&id).cloned()))
+            .collect()
+    };
+    let events = std::mem::take(&mut *state.events.lock().unwrap());
+
+    if changes.is_empty() && events.is_empty() {
+        return;
+    }
+
+    let storage = state.storage.clone();
+    let data_dir = state.config.data_dir.clone();
+
+    let failed = tokio::task::spawn_blocking(move || {
+        // Events that could not be written are dropped, as retrying could log part of them twice.
+        for (id, turns, events) in events {
+            if let Err(error) = record_events(&data_dir, id, turns, &events) {
+                eprintln!("could not log events of lobby {id}: {error}");
+            }
+        }
+
+        changes
+            .into_iter()
+            .filter_map(|(id, lobby)| {
+                let result = match lobby {
+                    Some(lobby) => storage.save(id, &lobby),
+                    None => storage.remove(id),
+                };
+
+                result
+                    .map_err(|error| eprintln!("could not store lobby {id}: {error}"))
+                    .err()
+                    .map(|_| id)
+            })
+            .collect::<Vec<u16>>()
+    })
+    .await
+    .unwrap_or_default();
+
+    // Lobbies that could not be written are tried again with the next snapshot.
+    state.dirty.lock().unwrap().extend(failed);
 }
 
 async fn get_lobbies(State(state): State<AppState>) -> Json<Message> {
-    let mut lobbies = state.lobbies.lock().unwrap();
+    let lobbies = state.lobbies.lock().unwrap();
 
     // let lobby_settings = LobbySettings {
     //     lobby_sort: shared::LobbySort::Local,
@@ -64,22 +304,58 @@ async fn get_lobbies(State(state): State<AppState>) -> Json<Message> {
     //     lobbies.insert(len, lobby.clone());
     // }
 
-    lobbies.retain(|_, v| v.any_connected(timestamp()) && !v.finished());
+    // Lobbies without anyone around or with a finished game are left to the reaper, but not listed.
+    Json(Message::Lobbies(
+        lobbies
+            .iter()
+            .filter(|(_, lobby)| {
+                lobby.any_connected(timestamp(), state.config.heartbeat_timeout())
+                    && !lobby.finished()
+            })
+            .map(|(id, lobby)| (*id, lobby.view_for(None)))
+            .collect(),
+    ))
+}
+
+async fn get_metrics(State(state): State<AppState>) -> String {
+    let active_lobbies = state.lobbies.lock().unwrap().len();
 
-    Json(Message::Lobbies(lobbies.clone()))
+    state.reap_metrics.render(active_lobbies)
 }
 
 async fn create_lobby(
     State(state): State<AppState>,
-    Json(mut session_message): Json<SessionNewLobby>,
+    // Like every other lobby action, creating a lobby takes a verified session.
+    _: Session,
+    Json(session_message): Json<SessionNewLobby>,
 ) -> Json<Message> {
-    let lobby_id = generate_lobby_id();
+    // The seed is chosen here, so clients cannot pick one that favours them.
+    let lobby_settings = match session_message.lobby_settings.normalized(rand::random()) {
+        Ok(lobby_settings) => lobby_settings,
+        Err(error) => return Json(Message::LobbyError(error.into())),
+    };
+
     let mut lobbies = state.lobbies.lock().unwrap();
 
-    session_message.lobby_settings.lobby_sort = LobbySort::Online(lobby_id);
-    let lobby = Lobby::new(session_message.lobby_settings, timestamp());
+    let lobby_id = match generate_lobby_id(&lobbies) {
+        Some(lobby_id) if lobbies.len() < state.config.max_lobbies => lobby_id,
+        _ => {
+            return Json(Message::LobbyError(LobbyError(
+                "too many lobbies, try again later".to_string(),
+            )))
+        }
+    };
+
+    let lobby = Lobby::new(
+        LobbySettings {
+            lobby_sort: LobbySort::Online(lobby_id),
+            ..lobby_settings
+        },
+        timestamp(),
+    );
 
     lobbies.insert(lobby_id, lobby.clone());
+    state.mark_dirty(lobby_id);
 
     Json(Message::Lobby(Box::new(lobby)))
 }
@@ -87,19 +363,34 @@ async fn create_lobby(
 async fn get_turns_since(
     State(state): State<AppState>,
     Path((id, since)): Path<(u16, usize)>,
-    Json(session_request): Json<SessionRequest>,
+    Session(session_id): Session,
 ) -> Json<Message> {
     let mut lobbies = state.lobbies.lock().unwrap();
 
     if let Some(lobby) = lobbies.get_mut(&id) {
-        lobby.beat_heart(session_request.session_id);
-
-        if lobby.all_ready() {
-            let turns_since: Vec<Turn> =
-                lobby.game.turns_since(since).into_iter().cloned().collect();
-            Json(Message::Turns(turns_since))
+        lobby.beat_heart(session_id.clone());
+
+        // Drafting and deploying do not produce turns, so lobbies in setup are delivered whole.
+        if lobby.all_ready() && !lobby.in_setup() {
+            if since == lobby.game.turns()
+                && (lobby.draw_offer().is_some() || lobby.game.is_drawn())
+            {
+                // Draw offers and agreements do not produce turns, so they are delivered with the whole lobby.
+                Json(Message::Lobby(Box::new(lobby.view_for(Some(&session_id)))))
+            } else if lobby.settings.fog_of_war {
+                // Hidden turns cannot be replayed by the client, so it is resynchronised with a fogged view instead.
+                if since != lobby.game.turns() {
+                    Json(Message::Lobby(Box::new(lobby.view_for(Some(&session_id)))))
+                } else {
+                    Json(Message::Turns(Vec::new()))
+                }
+            } else {
+                let turns_since: Vec<Turn> =
+                    lobby.game.turns_since(since).into_iter().cloned().collect();
+                Json(Message::Turns(turns_since))
+            }
         } else {
-            Json(Message::Lobby(Box::new(lobby.clone())))
+            Json(Message::Lobby(Box::new(lobby.view_for(Some(&session_id)))))
         }
     } else {
         Json(Message::LobbyError(LobbyError(
@@ -108,11 +399,15 @@ async fn get_turns_since(
     }
 }
 
-async fn get_state(State(state): State<AppState>, Path(id): Path<u16>) -> Json<Message> {
+async fn get_state(
+    State(state): State<AppState>,
+    Path(id): Path<u16>,
+    Session(session_id): Session,
+) -> Json<Message> {
     let lobbies = state.lobbies.lock().unwrap();
 
     match lobbies.get(&id) {
-        Some(lobby) => Json(Message::Lobby(Box::new(lobby.clone()))),
+        Some(lobby) => Json(Message::Lobby(Box::new(lobby.view_for(Some(&session_id))))),
         None => Json(Message::LobbyError(LobbyError(
             "lobby does not exist".to_string(),
         ))),
@@ -122,17 +417,22 @@ async fn get_state(State(state): State<AppState>, Path(id): Path<u16>) -> Json<M
 async fn process_inbound(
     State(state): State<AppState>,
     Path(id): Path<u16>,
-    Json(session_message): Json<SessionMessage>,
+    Session(session_id): Session,
+    Json(message): Json<Message>,
 ) -> Json<Message> {
     let mut lobbies = state.lobbies.lock().unwrap();
 
     Json(match lobbies.get_mut(&id) {
         Some(lobby) => {
-            let result: Message = lobby
-                .act_player(session_message.session_id, session_message.message)
-                .into();
-            record_lobby(id, lobby);
-            result
+            let result = lobby.act_player(session_id.clone(), message);
+
+            if let Ok(events) = &result {
+                state.queue_events(id, lobby.game.turns(), events);
+                state.notify(id, LobbyUpdate::Acted(session_id));
+            }
+
+            state.mark_dirty(id);
+            result.into()
         }
         None => Message::LobbyError(LobbyError("lobby does not exist".to_string())),
     })
@@ -141,16 +441,72 @@ async fn process_inbound(
 async fn post_ready(
     State(state): State<AppState>,
     Path(id): Path<u16>,
-    Json(session_request): Json<SessionRequest>,
+    Session(session_id): Session,
 ) -> Json<Message> {
     let mut lobbies = state.lobbies.lock().unwrap();
 
     Json(match lobbies.get_mut(&id) {
         Some(lobby) => {
-            let result = lobby.join_player(session_request.session_id.clone()).into();
-            lobby.beat_heart(session_request.session_id);
+            let rejoin_token = generate_session_id();
+            let result = lobby.join_player(session_id.clone(), rejoin_token.clone());
+            lobby.beat_heart(session_id);
+
+            match result {
+                Ok(team) => {
+                    state.mark_dirty(id);
+                    state.announce(id, Presence::Joined(team));
+
+                    Message::Joined(rejoin_token)
+                }
+                Err(error) => Message::LobbyError(error),
+            }
+        }
+        None => Message::LobbyError(LobbyError("lobby does not exist".to_string())),
+    })
+}
+
+async fn post_leave(
+    State(state): State<AppState>,
+    Path(id): Path<u16>,
+    Session(session_id): Session,
+) -> Json<Message> {
+    let mut lobbies = state.lobbies.lock().unwrap();
+
+    Json(match lobbies.get_mut(&id) {
+        Some(lobby) => {
+            let result = lobby.leave_player(&session_id);
+
+            if let Ok(team) = result {
+                state.mark_dirty(id);
+                state.announce(id, Presence::Left(team));
+            }
+
+            result.into()
+        }
+        None => Message::LobbyError(LobbyError("lobby does not exist".to_string())),
+    })
+}
 
-            result
+async fn post_rejoin(
+    State(state): State<AppState>,
+    Path(id): Path<u16>,
+    Session(session_id): Session,
+    Json(session_rejoin): Json<SessionRejoin>,
+) -> Json<Message> {
+    let mut lobbies = state.lobbies.lock().unwrap();
+
+    Json(match lobbies.get_mut(&id) {
+        Some(lobby) => {
+            match lobby.rejoin_player(session_id.clone(), &session_rejoin.rejoin_token) {
+                Ok(team) => {
+                    lobby.beat_heart(session_id.clone());
+                    state.mark_dirty(id);
+                    state.announce(id, Presence::Joined(team));
+
+                    Message::Lobby(Box::new(lobby.view_for(Some(&session_id))))
+                }
+                Err(error) => Message::LobbyError(error),
+            }
         }
         None => Message::LobbyError(LobbyError("lobby does not exist".to_string())),
     })
@@ -159,34 +515,229 @@ async fn post_ready(
 async fn post_rematch(
     State(state): State<AppState>,
     Path(id): Path<u16>,
-    Json(session_request): Json<SessionRequest>,
+    Session(session_id): Session,
 ) -> Json<Message> {
     let mut lobbies = state.lobbies.lock().unwrap();
 
     Json(match lobbies.get_mut(&id) {
         Some(lobby) => {
-            let result = lobby.request_rematch(session_request.session_id);
+            let result = lobby.request_rematch(session_id);
 
             if let Ok(true) = result {
                 lobby.remake(timestamp());
             }
 
+            if result.is_ok() {
+                state.mark_dirty(id);
+                state.notify(id, LobbyUpdate::Changed);
+            }
+
             result.into()
         }
         None => Message::LobbyError(LobbyError("lobby does not exist".to_string())),
     })
 }
 
-async fn obtain_session() -> Json<SessionRequest> {
-    Json(SessionRequest {
-        session_id: generate_session_id(),
-    })
+async fn lobby_socket(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path(id): Path<u16>,
+    Session(session_id): Session,
+) -> Response {
+    ws.on_upgrade(move |socket| serve_socket(socket, state, id, session_id))
+}
+
+/// Pushes the lobby to a WebSocket connection whenever it changes, until either side closes it or the lobby is gone.
+/// Connecting and disconnecting are announced to the other connections as presence changes.
+async fn serve_socket(mut socket: WebSocket, state: AppState, id: u16, session_id: String) {
+    let mut updates = state.subscribe(id);
+    let mut heartbeat = tokio::time::interval(SOCKET_HEARTBEAT);
+    let mut pending = Some(LobbyUpdate::Changed);
+    let mut since = None;
+
+    state.notify(id, LobbyUpdate::Changed);
+
+    loop {
+        if let Some(update) = pending.take() {
+            let message = match state.lobbies.lock().unwrap().get(&id) {
+                Some(lobby) => socket_message(lobby, Some(&session_id), &mut since, &update),
+                None => Some(Message::LobbyError(LobbyError(
+                    "lobby does not exist".to_string(),
+                ))),
+            };
+
+            if let Some(message) = message {
+                let closing = matches!(message, Message::LobbyError(_));
+                let text = serde_json::to_string(&message).unwrap();
+
+                if socket.send(ws::Message::Text(text)).await.is_err() || closing {
+                    break;
+                }
+            }
+        }
+
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(update) => pending = Some(update),
+                Err(RecvError::Lagged(_)) => pending = Some(LobbyUpdate::Changed),
+                Err(RecvError::Closed) => break,
+            },
+            _ = heartbeat.tick() => beat_heart(&state, id, &session_id),
+            received = socket.recv() => match received {
+                Some(Ok(ws::Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => (),
+            },
+        }
+    }
+
+    state.notify(id, LobbyUpdate::Changed);
+}
+
+/// Chooses what to push to a WebSocket connection or spectator after a [`LobbyUpdate`], given the number of turns it was last sent.
+/// Plain turns are sent on their own, except to the session that took them, and anything else resynchronises the whole lobby.
+/// Presence changes are passed on as they are, and leave the number of turns sent untouched.
+fn socket_message(
+    lobby: &Lobby,
+    session_id: Option<&String>,
+    since: &mut Option<usize>,
+    update: &LobbyUpdate,
+) -> Option<Message> {
+    if let LobbyUpdate::Presence(presence) = update {
+        return Some(Message::Presence(*presence));
+    }
+
+    let since = since.replace(lobby.game.turns()).filter(|since| {
+        lobby.all_ready()
+            && !lobby.in_setup()
+            && !lobby.settings.fog_of_war
+            && *since < lobby.game.turns()
+    });
+
+    match since {
+        Some(_)
+            if session_id
+                .is_some_and(|session_id| *update == LobbyUpdate::Acted(session_id.clone())) =>
+        {
+            None
+        }
+        Some(since) => Some(Message::Turns(
+            lobby.game.turns_since(since).into_iter().cloned().collect(),
+        )),
+        None => Some(Message::Lobby(Box::new(lobby.view_for(session_id)))),
+    }
+}
+
+/// Counts a spectator of a lobby for as long as its event stream is open.
+struct Spectator {
+    state: AppState,
+    id: u16,
+}
+
+impl Spectator {
+    fn new(state: AppState, id: u16) -> Spectator {
+        if let Some(lobby) = state.lobbies.lock().unwrap().get_mut(&id) {
+            lobby.add_spectator();
+        }
+
+        state.notify(id, LobbyUpdate::Changed);
+
+        Spectator { state, id }
+    }
+}
+
+impl Drop for Spectator {
+    fn drop(&mut self) {
+        if let Some(lobby) = self.state.lobbies.lock().unwrap().get_mut(&self.id) {
+            lobby.remove_spectator();
+        }
+
+        self.state.notify(self.id, LobbyUpdate::Changed);
+    }
+}
+
+/// Streams a lobby to a read-only spectator as server-sent events, each carrying a [`Message`] like those of [`lobby_socket`].
+/// Spectators see the lobby as it is shown to those outside of it, so hidden information stays hidden.
+async fn watch_lobby(
+    State(state): State<AppState>,
+    Path(id): Path<u16>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let updates = state.subscribe(id);
+    let spectator = Spectator::new(state, id);
+
+    let events = stream::unfold(
+        (spectator, updates, None, Some(LobbyUpdate::Changed), true),
+        |(spectator, mut updates, mut since, mut pending, open)| async move {
+            if !open {
+                return None;
+            }
+
+            loop {
+                if let Some(update) = pending.take() {
+                    let message = match spectator.state.lobbies.lock().unwrap().get(&spectator.id) {
+                        Some(lobby) => socket_message(lobby, None, &mut since, &update),
+                        None => Some(Message::LobbyError(LobbyError(
+                            "lobby does not exist".to_string(),
+                        ))),
+                    };
+
+                    if let Some(message) = message {
+                        // The stream ends after announcing that the lobby is gone.
+                        let open = !matches!(message, Message::LobbyError(_));
+                        let event = Event::default().data(serde_json::to_string(&message).unwrap());
+
+                        return Some((Ok(event), (spectator, updates, since, None, open)));
+                    }
+                }
+
+                pending = match updates.recv().await {
+                    Ok(update) => Some(update),
+                    Err(RecvError::Lagged(_)) => Some(LobbyUpdate::Changed),
+                    Err(RecvError::Closed) => return None,
+                };
+            }
+        },
+    );
+
+    Sse::new(events).keep_alive(KeepAlive::default())
 }
 
-fn record_lobby(id: u16, lobby: &Lobby) {
-    fs::create_dir_all("lobbies").unwrap();
-    let file = File::create(format!("lobbies/{}.json", id)).unwrap();
-    serde_json::to_writer(&file, lobby).unwrap();
+fn beat_heart(state: &AppState, id: u16, session_id: &str) {
+    if let Some(lobby) = state.lobbies.lock().unwrap().get_mut(&id) {
+        lobby.beat_heart(session_id.to_string());
+    }
+}
+
+async fn obtain_session(State(state): State<AppState>) -> Json<SessionToken> {
+    Json(state.sessions.issue(generate_session_id()))
+}
+
+/// Issues a fresh token for the session of a token that is still valid, so a client keeps its session and slots.
+async fn refresh_session(
+    State(state): State<AppState>,
+    Session(session_id): Session,
+) -> Json<SessionToken> {
+    Json(state.sessions.issue(session_id))
+}
+
+/// Appends the events of a move to the event log of a lobby, one line of JSON per event.
+fn record_events(
+    data_dir: &std::path::Path,
+    id: u16,
+    turns: usize,
+    events: &[GameEvent],
+) -> std::io::Result<()> {
+    fs::create_dir_all(data_dir)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(data_dir.join(format!("{}.events.jsonl", id)))?;
+
+    for event in events {
+        serde_json::to_writer(&file, &(turns, event))?;
+        writeln!(file)?;
+    }
+
+    Ok(())
 }
 
 fn generate_session_id() -> String {
@@ -197,12 +748,109 @@ fn generate_session_id() -> String {
         .collect()
 }
 
-fn generate_lobby_id() -> u16 {
-    loop {
-        let res = rand::thread_rng().gen_range(u16::MIN..=u16::MAX);
+/// Generates an ID that no lobby has yet, or none if they are all taken.
+/// IDs are picked at random to be hard to guess, falling back to the first free one when random picks keep colliding.
+fn generate_lobby_id<T>(lobbies: &HashMap<u16, T>) -> Option<u16> {
+    let available = |id: &u16| id.count_ones() >= 4 && !lobbies.contains_key(id);
+    let mut rng = rand::thread_rng();
 
-        if res.count_ones() >= 4 {
-            return res;
+    (0..64)
+        .map(|_| rng.gen_range(u16::MIN..=u16::MAX))
+        .find(available)
+        .or_else(|| (u16::MIN..=u16::MAX).find(available))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        body::Body,
+        http::{
+            header::{AUTHORIZATION, CONTENT_TYPE},
+            Request, StatusCode,
+        },
+    };
+    use tower::ServiceExt;
+
+    use super::*;
+
+    /// Builds the state of a server without lobbies, backed by an in-memory database.
+    fn state() -> AppState {
+        let config = Config::default();
+
+        AppState {
+            config: Arc::new(config.clone()),
+            lobbies: Arc::new(Mutex::new(HashMap::new())),
+            updates: Arc::new(Mutex::new(HashMap::new())),
+            storage: Arc::new(SqliteStorage::new(":memory:").unwrap()),
+            dirty: Arc::new(Mutex::new(HashSet::new())),
+            events: Arc::new(Mutex::new(Vec::new())),
+            reap_rules: ReapRules::from_config(&config),
+            reap_metrics: Arc::new(ReapMetrics::default()),
+            sessions: Arc::new(SessionKeys::new("secret", config.session_lifetime())),
+            rate_limits: Arc::new(RateLimits::from_config(&config)),
         }
     }
+
+    /// Maps every ID that [`generate_lobby_id`] may return, except those given, to a lobby.
+    fn lobbies_except(free: &[u16]) -> HashMap<u16, ()> {
+        (u16::MIN..=u16::MAX)
+            .filter(|id| id.count_ones() >= 4 && !free.contains(id))
+            .map(|id| (id, ()))
+            .collect()
+    }
+
+    #[test]
+    fn generates_unused_ids() {
+        let lobbies: HashMap<u16, ()> = (0..1000).map(|id| (id, ())).collect();
+
+        for _ in 0..100 {
+            let id = generate_lobby_id(&lobbies).unwrap();
+
+            assert!(id.count_ones() >= 4);
+            assert!(!lobbies.contains_key(&id));
+        }
+    }
+
+    #[test]
+    fn finds_last_free_id() {
+        for free in [0b1111, 0x1248, u16::MAX] {
+            assert_eq!(generate_lobby_id(&lobbies_except(&[free])), Some(free));
+        }
+
+        assert_eq!(generate_lobby_id(&lobbies_except(&[])), None);
+    }
+
+    #[tokio::test]
+    async fn rejects_unauthenticated_lobby_creation() {
+        let state = state();
+        let app = Router::new()
+            .route("/lobby/create", post(create_lobby))
+            .with_state(state.clone());
+
+        let request = |token: Option<&str>| {
+            let body = serde_json::to_string(&SessionNewLobby {
+                lobby_settings: LobbySettings::default(),
+            })
+            .unwrap();
+            let request = Request::post("/lobby/create").header(CONTENT_TYPE, "application/json");
+
+            match token {
+                Some(token) => request.header(AUTHORIZATION, format!("Bearer {token}")),
+                None => request,
+            }
+            .body(Body::from(body))
+            .unwrap()
+        };
+
+        let response = app.clone().oneshot(request(None)).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert!(state.lobbies.lock().unwrap().is_empty());
+
+        let token = state.sessions.issue("abcdefgh".to_string()).token;
+        let response = app.oneshot(request(Some(&token))).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.lobbies.lock().unwrap().len(), 1);
+    }
 }
@@ -0,0 +1,199 @@
+use std::{
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use shared::{timestamp, Lobby, Presence};
+
+use crate::{config::Config, AppState, LobbyUpdate};
+
+/// Why a lobby was reaped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReapReason {
+    /// An unfinished game whose players all stopped sending heartbeats.
+    Abandoned,
+    /// A finished game that nobody has been looking at, for example to request a rematch.
+    Finished,
+    /// A lobby that nobody ever joined.
+    Unjoined,
+}
+
+impl ReapReason {
+    const ALL: [ReapReason; 3] = [
+        ReapReason::Abandoned,
+        ReapReason::Finished,
+        ReapReason::Unjoined,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            ReapReason::Abandoned => "abandoned",
+            ReapReason::Finished => "finished",
+            ReapReason::Unjoined => "unjoined",
+        }
+    }
+}
+
+/// The rules by which the reaper removes lobbies.
+#[derive(Debug, Clone)]
+pub struct ReapRules {
+    /// How often lobbies are checked.
+    pub interval: Duration,
+    /// How long an unfinished game is kept without a heartbeat from any of its players.
+    pub heartbeat_timeout: Duration,
+    /// How long a player may go without a heartbeat in a game in progress before forfeiting it.
+    pub forfeit_timeout: Duration,
+    /// How long a finished game is kept without a heartbeat from any of its players.
+    pub finished_timeout: Duration,
+    /// How long a lobby is kept if nobody joins it.
+    pub unjoined_timeout: Duration,
+}
+
+impl ReapRules {
+    /// Takes the rules from the timeouts of the [`Config`].
+    pub fn from_config(config: &Config) -> ReapRules {
+        ReapRules {
+            interval: Duration::from_secs(config.reap_interval),
+            heartbeat_timeout: config.heartbeat_timeout(),
+            forfeit_timeout: Duration::from_secs(config.forfeit_timeout),
+            finished_timeout: Duration::from_secs(config.finished_timeout),
+            unjoined_timeout: Duration::from_secs(config.unjoined_timeout),
+        }
+    }
+
+    /// Determines if, and why, a lobby should be reaped at the given time.
+    pub fn reason(&self, lobby: &Lobby, now: Duration) -> Option<ReapReason> {
+        let idle = now.saturating_sub(lobby.latest_heartbeat());
+
+        if lobby.players().is_empty() {
+            (idle >= self.unjoined_timeout).then_some(ReapReason::Unjoined)
+        } else if lobby.finished() {
+            (idle >= self.finished_timeout).then_some(ReapReason::Finished)
+        } else {
+            (idle >= self.heartbeat_timeout).then_some(ReapReason::Abandoned)
+        }
+    }
+}
+
+/// Counts of the lobbies reaped since the server started.
+#[derive(Debug, Default)]
+pub struct ReapMetrics {
+    reaped: [AtomicU64; 3],
+}
+
+impl ReapMetrics {
+    fn count(&self, reason: ReapReason) {
+        self.reaped[reason as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the number of lobbies reaped for a [`ReapReason`].
+    pub fn reaped(&self, reason: ReapReason) -> u64 {
+        self.reaped[reason as usize].load(Ordering::Relaxed)
+    }
+
+    /// Renders the metrics in the Prometheus text format, along with the number of active lobbies.
+    pub fn render(&self, active_lobbies: usize) -> String {
+        let mut text = String::new();
+
+        let _ = writeln!(
+            text,
+            "# HELP maginet_lobbies_reaped_total Lobbies removed by the reaper.\n\
+             # TYPE maginet_lobbies_reaped_total counter"
+        );
+
+        for reason in ReapReason::ALL {
+            let _ = writeln!(
+                text,
+                "maginet_lobbies_reaped_total{{reason=\"{}\"}} {}",
+                reason.label(),
+                self.reaped(reason)
+            );
+        }
+
+        let _ = writeln!(
+            text,
+            "# HELP maginet_lobbies_active Lobbies currently held by the server.\n\
+             # TYPE maginet_lobbies_active gauge\n\
+             maginet_lobbies_active {active_lobbies}"
+        );
+
+        text
+    }
+}
+
+/// Reaps lobbies at the interval of the [`ReapRules`].
+pub async fn reap_periodically(state: AppState) {
+    let mut interval = tokio::time::interval(state.reap_rules.interval);
+
+    loop {
+        interval.tick().await;
+        reap(&state).await;
+    }
+}
+
+/// Removes the lobbies due according to the [`ReapRules`] and archives them to storage.
+/// Their connections are notified, upon which they find the lobby gone.
+/// Players who stayed disconnected from a game in progress for too long forfeit it first.
+async fn reap(state: &AppState) {
+    let now = timestamp();
+
+    let (forfeits, reaped) = {
+        let mut lobbies = state.lobbies.lock().unwrap();
+
+        let forfeits: Vec<(u16, Presence)> = lobbies
+            .iter_mut()
+            .filter_map(|(id, lobby)| {
+                let team = lobby.forfeit_disconnected(now, state.reap_rules.forfeit_timeout)?;
+                state.mark_dirty(*id);
+
+                Some((*id, Presence::Forfeited(team)))
+            })
+            .collect();
+
+        let due: Vec<(u16, ReapReason)> = lobbies
+            .iter()
+            .filter_map(|(id, lobby)| Some((*id, state.reap_rules.reason(lobby, now)?)))
+            .collect();
+
+        let reaped: Vec<(u16, Lobby)> = due
+            .into_iter()
+            .filter_map(|(id, reason)| {
+                state.reap_metrics.count(reason);
+                state.mark_dirty(id);
+                lobbies.remove(&id).map(|lobby| (id, lobby))
+            })
+            .collect();
+
+        (forfeits, reaped)
+    };
+
+    for (id, presence) in forfeits {
+        state.announce(id, presence);
+    }
+
+    if reaped.is_empty() {
+        return;
+    }
+
+    for (id, _) in &reaped {
+        state.notify(*id, LobbyUpdate::Changed);
+    }
+
+    state
+        .updates
+        .lock()
+        .unwrap()
+        .retain(|_, sender| sender.receiver_count() > 0);
+
+    let storage = state.storage.clone();
+
+    let _ = tokio::task::spawn_blocking(move || {
+        for (id, lobby) in reaped {
+            if let Err(error) = storage.archive(id, &lobby) {
+                eprintln!("could not archive lobby {id}: {error}");
+            }
+        }
+    })
+    .await;
+}
@@ -0,0 +1,246 @@
+use std::{fmt::Display, time::Duration};
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::{header::AUTHORIZATION, request::Parts, StatusCode},
+    Json,
+};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde::Deserialize;
+use sha2::Sha256;
+use shared::{timestamp, LobbyError, Message, SessionToken};
+
+use crate::AppState;
+
+/// An error in authenticating a session token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SessionError {
+    /// No token was sent with the request.
+    Missing,
+    /// The token is not made of a session ID, an expiry and a signature.
+    Malformed,
+    /// The signature does not match the session ID and expiry.
+    InvalidSignature,
+    /// The token has expired, and a new session must be obtained.
+    Expired,
+}
+
+impl Display for SessionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            SessionError::Missing => "missing session token",
+            SessionError::Malformed => "malformed session token",
+            SessionError::InvalidSignature => "invalid session token",
+            SessionError::Expired => "session expired",
+        })
+    }
+}
+
+/// Issues and verifies session tokens of the form `<session ID>.<expiry>.<signature>`.
+/// The signature is an HMAC-SHA256 of the session ID and expiry with the server's secret, so tokens cannot be forged from a session ID alone.
+pub struct SessionKeys {
+    secret: Vec<u8>,
+    lifetime: Duration,
+}
+
+impl SessionKeys {
+    pub fn new(secret: impl Into<Vec<u8>>, lifetime: Duration) -> SessionKeys {
+        SessionKeys {
+            secret: secret.into(),
+            lifetime,
+        }
+    }
+
+    /// Creates keys with a random secret, whose tokens are only valid until the server restarts.
+    pub fn random(lifetime: Duration) -> SessionKeys {
+        SessionKeys::new(rand::thread_rng().gen::<[u8; 32]>(), lifetime)
+    }
+
+    fn mac(&self, session_id: &str, expires: u64) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts any key");
+        mac.update(format!("{session_id}.{expires}").as_bytes());
+        mac
+    }
+
+    /// Issues a token for a session ID, valid for the lifetime of the keys from now.
+    pub fn issue(&self, session_id: String) -> SessionToken {
+        let expires = (timestamp() + self.lifetime).as_secs();
+        let signature = hex::encode(self.mac(&session_id, expires).finalize().into_bytes());
+
+        SessionToken {
+            token: format!("{session_id}.{expires}.{signature}"),
+            session_id,
+            expires,
+        }
+    }
+
+    /// Verifies a token at the given time, returning its session ID.
+    pub fn verify(&self, token: &str, now: Duration) -> Result<String, SessionError> {
+        let mut parts = token.split('.');
+
+        let (Some(session_id), Some(expires), Some(signature), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(SessionError::Malformed);
+        };
+
+        let expires: u64 = expires.parse().map_err(|_| SessionError::Malformed)?;
+        let signature = hex::decode(signature).map_err(|_| SessionError::Malformed)?;
+
+        self.mac(session_id, expires)
+            .verify_slice(&signature)
+            .map_err(|_| SessionError::InvalidSignature)?;
+
+        if now.as_secs() >= expires {
+            return Err(SessionError::Expired);
+        }
+
+        Ok(session_id.to_string())
+    }
+}
+
+/// The ID of an authenticated session, extracted from a `Bearer` token in the `Authorization` header.
+/// WebSocket connections cannot set headers, so a `token` query parameter is accepted as well.
+pub struct Session(pub String);
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: String,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for Session {
+    type Rejection = (StatusCode, Json<Message>);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let token = parts
+            .headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::to_string)
+            .or_else(|| {
+                Query::<TokenQuery>::try_from_uri(&parts.uri)
+                    .ok()
+                    .map(|Query(query)| query.token)
+            });
+
+        token
+            .ok_or(SessionError::Missing)
+            .and_then(|token| state.sessions.verify(&token, timestamp()))
+            .map(Session)
+            .map_err(|error| {
+                (
+                    StatusCode::UNAUTHORIZED,
+                    Json(Message::LobbyError(LobbyError(error.to_string()))),
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIFETIME: Duration = Duration::from_secs(60);
+
+    fn keys() -> SessionKeys {
+        SessionKeys::new("secret", LIFETIME)
+    }
+
+    /// Splits a token into its session ID, expiry and signature.
+    fn parts(token: &str) -> (&str, u64, &str) {
+        let mut parts = token.split('.');
+
+        (
+            parts.next().unwrap(),
+            parts.next().unwrap().parse().unwrap(),
+            parts.next().unwrap(),
+        )
+    }
+
+    #[test]
+    fn verifies_issued_tokens() {
+        let token = keys().issue("abcdefgh".to_string());
+
+        assert_eq!(
+            keys().verify(&token.token, timestamp()),
+            Ok("abcdefgh".to_string())
+        );
+        assert_eq!(token.session_id, "abcdefgh");
+    }
+
+    #[test]
+    fn rejects_tampered_tokens() {
+        let token = keys().issue("abcdefgh".to_string()).token;
+        let (_, expires, signature) = parts(&token);
+
+        let other_session = format!("hgfedcba.{expires}.{signature}");
+        let later_expiry = format!("abcdefgh.{}.{signature}", expires + 1000);
+
+        let mut flipped = signature.to_string().into_bytes();
+        flipped[0] = if flipped[0] == b'0' { b'1' } else { b'0' };
+        let flipped = format!("abcdefgh.{expires}.{}", String::from_utf8(flipped).unwrap());
+
+        for token in [other_session, later_expiry, flipped] {
+            assert_eq!(
+                keys().verify(&token, timestamp()),
+                Err(SessionError::InvalidSignature)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_tokens_of_other_secrets() {
+        let token = SessionKeys::new("other", LIFETIME)
+            .issue("abcdefgh".to_string())
+            .token;
+
+        assert_eq!(
+            keys().verify(&token, timestamp()),
+            Err(SessionError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn expires_at_expiry() {
+        let token = keys().issue("abcdefgh".to_string()).token;
+        let (_, expires, _) = parts(&token);
+
+        assert!(keys()
+            .verify(&token, Duration::from_secs(expires - 1))
+            .is_ok());
+        assert_eq!(
+            keys().verify(&token, Duration::from_secs(expires)),
+            Err(SessionError::Expired)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_tokens() {
+        let token = keys().issue("abcdefgh".to_string()).token;
+        let (session_id, expires, signature) = parts(&token);
+
+        for token in [
+            String::new(),
+            session_id.to_string(),
+            format!("{session_id}.{expires}"),
+            format!("{token}.extra"),
+            format!("{session_id}.x.{expires}.{signature}"),
+            format!("{session_id}.soon.{signature}"),
+            format!("{session_id}.{expires}.not-hex"),
+            format!("{session_id}.{expires}.{}", &signature[1..]),
+        ] {
+            assert_eq!(
+                keys().verify(&token, timestamp()),
+                Err(SessionError::Malformed),
+                "{token}"
+            );
+        }
+    }
+}
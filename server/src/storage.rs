@@ -0,0 +1,193 @@
+use std::{collections::HashMap, fmt::Display, fs, io, path::PathBuf, sync::Mutex};
+
+use rusqlite::{params, Connection};
+use shared::{timestamp, Lobby};
+
+/// An error in reading or writing stored lobbies.
+#[derive(Debug)]
+pub struct StorageError(String);
+
+impl Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<io::Error> for StorageError {
+    fn from(error: io::Error) -> Self {
+        StorageError(format!("io error: {error}"))
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(error: serde_json::Error) -> Self {
+        StorageError(format!("serialisation error: {error}"))
+    }
+}
+
+impl From<rusqlite::Error> for StorageError {
+    fn from(error: rusqlite::Error) -> Self {
+        StorageError(format!("sqlite error: {error}"))
+    }
+}
+
+/// Persistent storage of lobbies, which lets them outlive a restart of the server.
+/// Writes are blocking, so they are made outside of the lobby lock.
+pub trait Storage: Send + Sync {
+    /// Loads every stored lobby.
+    fn load(&self) -> Result<HashMap<u16, Lobby>, StorageError>;
+
+    /// Stores a lobby, replacing any earlier version of it.
+    fn save(&self, id: u16, lobby: &Lobby) -> Result<(), StorageError>;
+
+    /// Removes a lobby, if it is stored.
+    fn remove(&self, id: u16) -> Result<(), StorageError>;
+
+    /// Keeps a lobby that is no longer active apart from the stored ones, for later reference.
+    /// Lobby IDs are reused, so an archive may hold several lobbies of the same ID.
+    fn archive(&self, id: u16, lobby: &Lobby) -> Result<(), StorageError>;
+}
+
+/// Stores each lobby as `<id>.json` in a directory, and archives them as `archive/<id>-<timestamp>.json` within it.
+pub struct FileStorage {
+    directory: PathBuf,
+}
+
+impl FileStorage {
+    pub fn new(directory: impl Into<PathBuf>) -> Result<FileStorage, StorageError> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+
+        Ok(FileStorage { directory })
+    }
+
+    fn path(&self, id: u16) -> PathBuf {
+        self.directory.join(format!("{id}.json"))
+    }
+}
+
+impl Storage for FileStorage {
+    fn load(&self) -> Result<HashMap<u16, Lobby>, StorageError> {
+        let mut lobbies = HashMap::new();
+
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+
+            // Other files, such as the event logs, share the directory.
+            let id = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|id| id.parse::<u16>().ok());
+
+            if let Some(id) = id {
+                lobbies.insert(id, serde_json::from_slice(&fs::read(&path)?)?);
+            }
+        }
+
+        Ok(lobbies)
+    }
+
+    fn save(&self, id: u16, lobby: &Lobby) -> Result<(), StorageError> {
+        // The lobby is written next to its file and then moved over it, so a crash never leaves it half-written.
+        let temporary_path = self.directory.join(format!("{id}.json.tmp"));
+
+        fs::write(&temporary_path, serde_json::to_vec(lobby)?)?;
+        fs::rename(temporary_path, self.path(id))?;
+
+        Ok(())
+    }
+
+    fn remove(&self, id: u16) -> Result<(), StorageError> {
+        match fs::remove_file(self.path(id)) {
+            Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    fn archive(&self, id: u16, lobby: &Lobby) -> Result<(), StorageError> {
+        let directory = self.directory.join("archive");
+        fs::create_dir_all(&directory)?;
+
+        fs::write(
+            directory.join(format!("{id}-{}.json", timestamp().as_secs())),
+            serde_json::to_vec(lobby)?,
+        )?;
+
+        Ok(())
+    }
+}
+
+/// Stores the lobbies in a table of an SQLite database, and archives them in another.
+pub struct SqliteStorage {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn new(path: impl Into<PathBuf>) -> Result<SqliteStorage, StorageError> {
+        let connection = Connection::open(path.into())?;
+
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS lobbies (id INTEGER PRIMARY KEY, lobby TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS archived_lobbies (
+                 id INTEGER NOT NULL,
+                 archived_at INTEGER NOT NULL,
+                 lobby TEXT NOT NULL
+             );",
+        )?;
+
+        Ok(SqliteStorage {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self) -> Result<HashMap<u16, Lobby>, StorageError> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection.prepare("SELECT id, lobby FROM lobbies")?;
+        let rows = statement.query_map([], |row| {
+            Ok((row.get::<_, u16>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut lobbies = HashMap::new();
+
+        for row in rows {
+            let (id, lobby) = row?;
+            lobbies.insert(id, serde_json::from_str(&lobby)?);
+        }
+
+        Ok(lobbies)
+    }
+
+    fn save(&self, id: u16, lobby: &Lobby) -> Result<(), StorageError> {
+        self.connection.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO lobbies (id, lobby) VALUES (?1, ?2)",
+            params![id, serde_json::to_string(lobby)?],
+        )?;
+
+        Ok(())
+    }
+
+    fn remove(&self, id: u16) -> Result<(), StorageError> {
+        self.connection
+            .lock()
+            .unwrap()
+            .execute("DELETE FROM lobbies WHERE id = ?1", params![id])?;
+
+        Ok(())
+    }
+
+    fn archive(&self, id: u16, lobby: &Lobby) -> Result<(), StorageError> {
+        self.connection.lock().unwrap().execute(
+            "INSERT INTO archived_lobbies (id, archived_at, lobby) VALUES (?1, ?2, ?3)",
+            params![
+                id,
+                timestamp().as_secs() as i64,
+                serde_json::to_string(lobby)?
+            ],
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use std::{collections::HashSet, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{BoardStyle, Level, LevelCodeError};
+
+/// Position of a [`CampaignPortal`] on the campaign map.
+pub type PortalPosition = (isize, isize);
+
+/// Orthogonal neighbours of a [`PortalPosition`].
+const PORTAL_DIRECTIONS: [PortalPosition; 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+
+/// Rule deciding when a [`CampaignPortal`] becomes playable.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnlockRule {
+    /// Playable from the start.
+    Unlocked,
+    /// Playable once an orthogonally adjacent portal has been won.
+    #[default]
+    Adjacent,
+    /// Playable once all of the listed portals have been won.
+    After(Vec<PortalPosition>),
+}
+
+/// A level on the campaign map.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CampaignPortal {
+    /// Position on the campaign map.
+    pub position: PortalPosition,
+    /// Title shown for the portal.
+    pub title: String,
+    /// Level code, as accepted by [`Level::from_code`].
+    pub code: String,
+    /// When the portal becomes playable.
+    #[serde(default)]
+    pub unlock: UnlockRule,
+    /// Board style overriding the one in the level code.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<BoardStyle>,
+    /// Whether the portal is included in demo builds.
+    #[serde(default)]
+    pub demo: bool,
+}
+
+impl CampaignPortal {
+    /// Decodes the portal's [`Level`], applying its style.
+    pub fn level(&self) -> Result<Level, LevelCodeError> {
+        let mut level = Level::from_code(&self.code)?;
+
+        if let Some(style) = &self.style {
+            level.board.style = style.clone();
+        }
+
+        Ok(level)
+    }
+}
+
+/// Errors in a [`Campaign`] definition, as found by [`Campaign::validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum CampaignError {
+    /// The campaign has no portals.
+    Empty,
+    /// No portal is [`UnlockRule::Unlocked`], so the campaign cannot be started.
+    NoStartingPortal,
+    /// Two portals share a position.
+    DuplicatePosition(PortalPosition),
+    /// A portal's level code does not decode.
+    InvalidLevel(PortalPosition, LevelCodeError),
+    /// A portal is unlocked after a position without a portal.
+    UnknownPrerequisite(PortalPosition, PortalPosition),
+}
+
+impl Display for CampaignError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CampaignError::Empty => f.write_str("campaign has no portals"),
+            CampaignError::NoStartingPortal => f.write_str("campaign has no unlocked portal"),
+            CampaignError::DuplicatePosition(position) => {
+                write!(f, "multiple portals at {position:?}")
+            }
+            CampaignError::InvalidLevel(position, error) => {
+                write!(f, "portal at {position:?} has an invalid level: {error}")
+            }
+            CampaignError::UnknownPrerequisite(position, prerequisite) => {
+                write!(
+                    f,
+                    "portal at {position:?} requires missing portal at {prerequisite:?}"
+                )
+            }
+        }
+    }
+}
+
+/// A map of [`CampaignPortal`]s, typically loaded from a JSON file.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Campaign {
+    /// Title of the campaign.
+    pub title: String,
+    /// Portals on the campaign map.
+    pub portals: Vec<CampaignPortal>,
+}
+
+impl Campaign {
+    /// Returns the [`CampaignPortal`] at the given position.
+    pub fn portal(&self, position: PortalPosition) -> Option<&CampaignPortal> {
+        self.portals
+            .iter()
+            .find(|portal| portal.position == position)
+    }
+
+    /// Checks that the campaign is playable and that all of its levels decode.
+    pub fn validate(&self) -> Result<(), CampaignError> {
+        if self.portals.is_empty() {
+            return Err(CampaignError::Empty);
+        }
+
+        let mut positions = HashSet::new();
+
+        for portal in &self.portals {
+            if !positions.insert(portal.position) {
+                return Err(CampaignError::DuplicatePosition(portal.position));
+            }
+
+            portal
+                .level()
+                .map_err(|error| CampaignError::InvalidLevel(portal.position, error))?;
+        }
+
+        for portal in &self.portals {
+            if let UnlockRule::After(prerequisites) = &portal.unlock {
+                if let Some(prerequisite) = prerequisites
+                    .iter()
+                    .find(|prerequisite| !positions.contains(prerequisite))
+                {
+                    return Err(CampaignError::UnknownPrerequisite(
+                        portal.position,
+                        *prerequisite,
+                    ));
+                }
+            }
+        }
+
+        if !self
+            .portals
+            .iter()
+            .any(|portal| portal.unlock == UnlockRule::Unlocked)
+        {
+            return Err(CampaignError::NoStartingPortal);
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether a portal is playable given the positions of the portals already won.
+    pub fn is_unlocked(&self, portal: &CampaignPortal, won: &HashSet<PortalPosition>) -> bool {
+        match &portal.unlock {
+            UnlockRule::Unlocked => true,
+            UnlockRule::Adjacent => PORTAL_DIRECTIONS.iter().any(|direction| {
+                won.contains(&(
+                    portal.position.0 + direction.0,
+                    portal.position.1 + direction.1,
+                ))
+            }),
+            UnlockRule::After(prerequisites) => prerequisites
+                .iter()
+                .all(|prerequisite| won.contains(prerequisite)),
+        }
+    }
+
+    /// Returns the campaign restricted to the portals included in demo builds.
+    pub fn demo(mut self) -> Campaign {
+        self.portals.retain(|portal| portal.demo);
+        self
+    }
+}
@@ -0,0 +1,213 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Level, LobbyError, Mage, Position, Team};
+
+/// Errors concerning a [`Deployment`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeploymentError {
+    /// The team has already committed its arrangement.
+    AlreadyDeployed,
+    /// The arrangement does not have a [`Position`] for every mage of the team.
+    WrongCount,
+    /// A [`Position`] lies outside the team's home rows.
+    NotHomeRow,
+    /// A [`Position`] is taken by another mage or a power-up.
+    Occupied,
+}
+
+impl Display for DeploymentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DeploymentError::AlreadyDeployed => "already deployed",
+            DeploymentError::WrongCount => "every mage must be deployed",
+            DeploymentError::NotHomeRow => "outside home rows",
+            DeploymentError::Occupied => "tile is occupied",
+        })
+    }
+}
+
+impl From<DeploymentError> for LobbyError {
+    fn from(error: DeploymentError) -> Self {
+        LobbyError(error.to_string())
+    }
+}
+
+/// The deployment phase of a lobby with [`crate::LobbySettings::deployment`].
+/// Each team secretly arranges its mages within its home rows, and both arrangements are revealed once both teams have committed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Deployment {
+    level: Level,
+    arrangements: Vec<(Team, Vec<Position>)>,
+}
+
+impl Deployment {
+    /// Starts a deployment of the mages of a [`Level`], or returns [`None`] if either team's mages do not fit into its home rows.
+    pub fn new(level: Level) -> Option<Deployment> {
+        let home_tiles = level.board.width * level.board.home_rows();
+
+        [Team::Red, Team::Blue]
+            .iter()
+            .all(|team| level.mages.iter().filter(|mage| mage.team == *team).count() <= home_tiles)
+            .then_some(Deployment {
+                level,
+                arrangements: Vec::new(),
+            })
+    }
+
+    /// Returns the mages of a [`Team`], in the order their [`Position`]s are arranged in.
+    pub fn mages(&self, team: Team) -> Vec<&Mage> {
+        self.level
+            .mages
+            .iter()
+            .filter(|mage| mage.team == team)
+            .collect()
+    }
+
+    /// Determines if a [`Team`] has committed its arrangement.
+    pub fn has_deployed(&self, team: Team) -> bool {
+        self.arrangements
+            .iter()
+            .any(|(deployed_by, _)| *deployed_by == team)
+    }
+
+    /// Returns the [`Team`] yet to deploy, the starting team first, or [`None`] once both have.
+    pub fn turn_for(&self) -> Option<Team> {
+        [self.level.starting_team, self.level.starting_team.enemy()]
+            .into_iter()
+            .find(|team| !self.has_deployed(*team))
+    }
+
+    /// Determines if both teams have committed their arrangements.
+    pub fn is_complete(&self) -> bool {
+        self.turn_for().is_none()
+    }
+
+    /// Checks that an arrangement places every mage of a [`Team`] on a distinct free tile of its home rows.
+    pub fn check(&self, team: Team, positions: &[Position]) -> Result<(), DeploymentError> {
+        if self.has_deployed(team) {
+            Err(DeploymentError::AlreadyDeployed)
+        } else if positions.len() != self.mages(team).len() {
+            Err(DeploymentError::WrongCount)
+        } else if positions
+            .iter()
+            .any(|position| !self.level.board.is_home_position(*position, team))
+        {
+            Err(DeploymentError::NotHomeRow)
+        } else if positions.iter().enumerate().any(|(index, position)| {
+            positions[..index].contains(position) || self.level.powerups.contains_key(position)
+        }) {
+            Err(DeploymentError::Occupied)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Commits the arrangement of a [`Team`].
+    pub fn commit(&mut self, team: Team, positions: Vec<Position>) -> Result<(), DeploymentError> {
+        self.check(team, &positions)?;
+
+        self.arrangements.push((team, positions));
+
+        Ok(())
+    }
+
+    /// Returns an arrangement for a [`Team`] which keeps every mage that already stands on a free home tile, moving the others onto the remaining ones.
+    pub fn default_positions(&self, team: Team) -> Vec<Position> {
+        let board = &self.level.board;
+        let is_free = |position: &Position, taken: &[Option<Position>]| {
+            board.is_home_position(*position, team)
+                && !self.level.powerups.contains_key(position)
+                && !taken.contains(&Some(*position))
+        };
+
+        let mut positions: Vec<Option<Position>> = Vec::new();
+
+        for mage in self.mages(team) {
+            let position = Some(mage.position).filter(|position| is_free(position, &positions));
+            positions.push(position);
+        }
+
+        let mut tiles = (0..board.height as i8)
+            .flat_map(|y| (0..board.width as i8).map(move |x| Position(x, y)));
+
+        for index in 0..positions.len() {
+            if positions[index].is_none() {
+                positions[index] = tiles.find(|position| is_free(position, &positions));
+            }
+        }
+
+        positions.into_iter().flatten().collect()
+    }
+
+    /// Returns the [`Level`] with every committed arrangement applied.
+    pub fn level(&self) -> Level {
+        let mut level = self.level.clone();
+
+        for (team, positions) in &self.arrangements {
+            for (mage, position) in level
+                .mages
+                .iter_mut()
+                .filter(|mage| mage.team == *team)
+                .zip(positions)
+            {
+                mage.position = *position;
+            }
+        }
+
+        level
+    }
+
+    /// Returns the [`Level`] as seen by a [`Team`] while deploying, with its own mages arranged and the enemy's hidden.
+    /// Without an arrangement, the committed one or else [`Deployment::default_positions`] is shown.
+    pub fn level_for(&self, team: Option<Team>, positions: Option<&[Position]>) -> Level {
+        let mut level = self.level.clone();
+
+        level.mages = match team {
+            Some(team) => {
+                let positions = positions.map(<[Position]>::to_vec).unwrap_or_else(|| {
+                    self.arrangements
+                        .iter()
+                        .find(|(deployed_by, _)| *deployed_by == team)
+                        .map(|(_, positions)| positions.clone())
+                        .unwrap_or_else(|| self.default_positions(team))
+                });
+
+                self.mages(team)
+                    .into_iter()
+                    .zip(positions)
+                    .enumerate()
+                    .map(|(index, (mage, position))| Mage {
+                        index,
+                        position,
+                        ..mage.clone()
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        level.mage_index = level.mages.len();
+
+        level
+    }
+
+    /// Returns a copy of the [`Deployment`] with every arrangement but that of the given [`Team`] masked.
+    /// Only the fact that the other team has committed is revealed.
+    pub fn masked(&self, team: Option<Team>) -> Deployment {
+        Deployment {
+            level: self.level.clone(),
+            arrangements: self
+                .arrangements
+                .iter()
+                .map(|(deployed_by, positions)| {
+                    if Some(*deployed_by) == team {
+                        (*deployed_by, positions.clone())
+                    } else {
+                        (*deployed_by, Vec::new())
+                    }
+                })
+                .collect(),
+        }
+    }
+}
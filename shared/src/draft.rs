@@ -0,0 +1,217 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use rand_chacha::{
+    rand_core::{RngCore, SeedableRng},
+    ChaCha8Rng,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{Board, Level, LobbyError, Mage, MageSort, Position, Team};
+
+/// Errors concerning a [`Draft`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DraftError {
+    /// It is not the team's turn to pick or place.
+    NotYourTurn,
+    /// The [`MageSort`] is not left in the pool.
+    NotInPool,
+    /// The team has no unplaced pick of the [`MageSort`].
+    NotPicked,
+    /// The [`Position`] lies outside the team's home rows.
+    NotHomeRow,
+    /// The [`Position`] is already taken by another mage.
+    Occupied,
+    /// Mages cannot be placed until both teams have finished picking.
+    StillPicking,
+    /// Mages cannot be picked once picking is over.
+    PickingOver,
+    /// The draft is over and the game has started.
+    DraftOver,
+}
+
+impl Display for DraftError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            DraftError::NotYourTurn => "not your turn",
+            DraftError::NotInPool => "mage not in pool",
+            DraftError::NotPicked => "mage not picked",
+            DraftError::NotHomeRow => "outside home rows",
+            DraftError::Occupied => "tile is occupied",
+            DraftError::StillPicking => "still picking",
+            DraftError::PickingOver => "picking is over",
+            DraftError::DraftOver => "draft is over",
+        })
+    }
+}
+
+impl From<DraftError> for LobbyError {
+    fn from(error: DraftError) -> Self {
+        LobbyError(error.to_string())
+    }
+}
+
+/// The drafting phase of a [`crate::LoadoutMethod::Draft`] lobby.
+/// Both teams alternately pick [`MageSort`]s from a shared pool, and then alternately place their picks in their home rows.
+/// The starting team picks and places first.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Draft {
+    board: Board,
+    starting_team: Team,
+    mages: usize,
+    pool: Vec<MageSort>,
+    picks: Vec<(Team, MageSort)>,
+    placed: Vec<Mage>,
+}
+
+impl Draft {
+    /// Starts a draft of `mages` mages per team on the board, picking from the given pool.
+    pub fn new(board: Board, mages: usize, pool: Vec<MageSort>, starting_team: Team) -> Draft {
+        Draft {
+            board,
+            starting_team,
+            mages: mages.min(pool.len() / 2),
+            pool,
+            picks: Vec::new(),
+            placed: Vec::new(),
+        }
+    }
+
+    /// Returns the [`Board`] being drafted on.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    /// Returns the [`MageSort`]s left to pick.
+    pub fn pool(&self) -> &[MageSort] {
+        &self.pool
+    }
+
+    /// Returns the [`MageSort`]s picked by a [`Team`], in order.
+    pub fn picks(&self, team: Team) -> Vec<MageSort> {
+        self.picks
+            .iter()
+            .filter(|(picked_by, _)| *picked_by == team)
+            .map(|(_, sort)| *sort)
+            .collect()
+    }
+
+    /// Returns the [`MageSort`]s picked by a [`Team`] that are yet to be placed.
+    pub fn unplaced(&self, team: Team) -> Vec<MageSort> {
+        let mut unplaced = self.picks(team);
+
+        for mage in self.placed.iter().filter(|mage| mage.team == team) {
+            if let Some(index) = unplaced.iter().position(|sort| *sort == mage.sort) {
+                unplaced.remove(index);
+            }
+        }
+
+        unplaced
+    }
+
+    /// Returns the [`Mage`]s placed so far.
+    pub fn placed(&self) -> &[Mage] {
+        &self.placed
+    }
+
+    /// Determines if the teams are still picking, rather than placing.
+    pub fn is_picking(&self) -> bool {
+        self.picks.len() < self.mages * 2
+    }
+
+    /// Determines if every pick has been placed.
+    pub fn is_complete(&self) -> bool {
+        self.placed.len() >= self.mages * 2
+    }
+
+    /// Returns the [`Team`] to pick or place next, or [`None`] once the draft is complete.
+    pub fn turn_for(&self) -> Option<Team> {
+        if self.is_complete() {
+            None
+        } else {
+            match (self.picks.len() + self.placed.len()) % 2 {
+                0 => Some(self.starting_team),
+                _ => Some(self.starting_team.enemy()),
+            }
+        }
+    }
+
+    /// Picks a [`MageSort`] from the pool for a [`Team`].
+    pub fn pick(&mut self, team: Team, sort: MageSort) -> Result<(), DraftError> {
+        if !self.is_picking() {
+            return Err(DraftError::PickingOver);
+        } else if self.turn_for() != Some(team) {
+            return Err(DraftError::NotYourTurn);
+        }
+
+        let index = self
+            .pool
+            .iter()
+            .position(|pooled| *pooled == sort)
+            .ok_or(DraftError::NotInPool)?;
+
+        self.picks.push((team, self.pool.remove(index)));
+
+        Ok(())
+    }
+
+    /// Places one of a [`Team`]'s picks of the given [`MageSort`] on a free tile of its home rows.
+    pub fn place(
+        &mut self,
+        team: Team,
+        sort: MageSort,
+        position: Position,
+    ) -> Result<(), DraftError> {
+        if self.is_complete() {
+            return Err(DraftError::DraftOver);
+        } else if self.is_picking() {
+            return Err(DraftError::StillPicking);
+        } else if self.turn_for() != Some(team) {
+            return Err(DraftError::NotYourTurn);
+        } else if !self.unplaced(team).contains(&sort) {
+            return Err(DraftError::NotPicked);
+        } else if !self.board.is_home_position(position, team) {
+            return Err(DraftError::NotHomeRow);
+        } else if self.placed.iter().any(|mage| mage.position == position) {
+            return Err(DraftError::Occupied);
+        }
+
+        self.placed
+            .push(Mage::new(self.placed.len(), team, sort, position));
+
+        Ok(())
+    }
+
+    /// Returns the [`Level`] with the mages placed so far.
+    pub fn level(&self) -> Level {
+        Level::new(
+            self.board.clone(),
+            self.placed.clone(),
+            BTreeMap::new(),
+            self.starting_team,
+        )
+    }
+
+    /// Picks or places on behalf of the [`Team`] whose turn it is, choosing at random from the seed.
+    /// Used for the AI opponent.
+    pub fn auto_draft(&mut self, seed: u64) -> Result<(), DraftError> {
+        let team = self.turn_for().ok_or(DraftError::DraftOver)?;
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        if self.is_picking() {
+            let sort = self.pool[(rng.next_u64() % self.pool.len() as u64) as usize];
+
+            self.pick(team, sort)
+        } else {
+            let sort = self.unplaced(team)[0];
+            let free: Vec<Position> = (0..self.board.height as i8)
+                .flat_map(|y| (0..self.board.width as i8).map(move |x| Position(x, y)))
+                .filter(|position| self.board.is_home_position(*position, team))
+                .filter(|position| !self.placed.iter().any(|mage| mage.position == *position))
+                .collect();
+
+            let position = free[(rng.next_u64() % free.len() as u64) as usize];
+
+            self.place(team, sort, position)
+        }
+    }
+}
@@ -2,11 +2,17 @@
 
 //! The `shared` crate contains all the components which are used by both the client and the server, which includes the entire game logic too.
 
+mod campaign;
+mod deployment;
+mod draft;
 mod lobby;
 mod logic;
 mod net;
 mod vecmap;
 
+pub use campaign::*;
+pub use deployment::*;
+pub use draft::*;
 pub use lobby::*;
 pub use logic::*;
 pub use net::*;
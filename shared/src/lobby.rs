@@ -11,8 +11,11 @@ use rand_chacha::{
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "server")]
-use crate::Turn;
-use crate::{Board, Game, Level, Mage, MageSort, Message, Team};
+use crate::GameEvent;
+use crate::{
+    Board, Deployment, Draft, DraftError, Game, GeneratorParams, Level, LevelIssue, Mage, MageSort,
+    Message, MoveError, Position, PowerUp, Spell, Team, Turn, MAX_MANA,
+};
 
 /// A identifier for a lobby, shared by the client and the server.
 pub type LobbyID = u16;
@@ -30,6 +33,54 @@ impl<T> From<Result<T, LobbyError>> for Message {
     }
 }
 
+impl From<MoveError> for LobbyError {
+    fn from(error: MoveError) -> Self {
+        LobbyError(error.to_string())
+    }
+}
+
+/// Problems with [`LobbySettings`] sent by a client, as found by [`LobbySettings::normalized`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingsError {
+    /// A board does not conform to the size limits of [`Board::new`].
+    InvalidBoard(&'static str),
+    /// A level has an issue that prevents it from being played.
+    InvalidLevel(LevelIssue),
+    /// A team has more mages than fit into half of the board.
+    TooManyMages(Team),
+    /// The mage at the position has more mana than its maximum, or a maximum above [`MAX_MANA`].
+    InvalidMana(Position),
+    /// The mage at the position holds a boulder, which can only lie on the board.
+    InvalidPowerUp(Position),
+    /// The parameters of a generated level are out of range.
+    InvalidGenerator(&'static str),
+}
+
+impl Display for SettingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SettingsError::InvalidBoard(error) => write!(f, "invalid board: {error}"),
+            SettingsError::InvalidLevel(issue) => write!(f, "invalid level: {issue}"),
+            SettingsError::TooManyMages(team) => write!(f, "{team:?} team has too many mages"),
+            SettingsError::InvalidMana(position) => {
+                write!(f, "invalid mana of the mage at {position:?}")
+            }
+            SettingsError::InvalidPowerUp(position) => {
+                write!(f, "mage holding a boulder at {position:?}")
+            }
+            SettingsError::InvalidGenerator(error) => {
+                write!(f, "invalid generator parameters: {error}")
+            }
+        }
+    }
+}
+
+impl From<SettingsError> for LobbyError {
+    fn from(error: SettingsError) -> Self {
+        LobbyError(error.to_string())
+    }
+}
+
 /// A player in a lobby, used in online lobbies only.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Player {
@@ -66,6 +117,24 @@ pub struct Lobby {
     player_slots: VecDeque<Player>,
     ticks: usize,
     first_heartbeat: Duration,
+    /// [`Turn`]s committed by each session for the current round of a simultaneous game.
+    #[serde(default)]
+    commitments: HashMap<String, Turn>,
+    #[serde(default)]
+    draw_offer: Option<Team>,
+    /// The drafting phase of a [`LoadoutMethod::Draft`] lobby, until all mages have been placed.
+    #[serde(default)]
+    draft: Option<Draft>,
+    /// The hidden deployment phase, until both teams have committed their arrangements.
+    #[serde(default)]
+    deployment: Option<Deployment>,
+    /// Number of spectators watching the lobby without playing.
+    #[serde(default)]
+    spectators: usize,
+    /// Tokens with which players reclaim their slot from another session, by the [`Team`] of the slot.
+    /// They are kept out of every view of the lobby.
+    #[serde(default)]
+    rejoin_tokens: HashMap<String, Team>,
     /// The [`Lobby`]s sort.
     pub settings: LobbySettings,
 }
@@ -74,10 +143,17 @@ impl Lobby {
     /// Instantiates the [`Lobby`] `struct` with a given [`LobbySort`].
     pub fn new(settings: LobbySettings, first_heartbeat: Duration) -> Lobby {
         let mut rng = ChaCha8Rng::seed_from_u64(settings.seed);
+        let level = settings.level(&mut rng);
+        let draft = settings.draft(&level, &mut rng);
+        // Drafted mages are already placed by the players, so they are not deployed again.
+        let deployment = if settings.deployment && draft.is_none() {
+            Deployment::new(level.clone())
+        } else {
+            None
+        };
 
         Lobby {
-            game: Game::new(&settings.level(&mut rng), settings.can_stalemate)
-                .expect("game should be instantiable with default values"),
+            game: settings.game(&level),
             players: HashMap::new(),
             player_slots: VecDeque::from([
                 Player::new(Team::Red, Duration::default()),
@@ -85,6 +161,12 @@ impl Lobby {
             ]),
             ticks: 0,
             first_heartbeat,
+            commitments: HashMap::new(),
+            draw_offer: None,
+            draft,
+            deployment,
+            spectators: 0,
+            rejoin_tokens: HashMap::new(),
             settings,
         }
     }
@@ -101,59 +183,180 @@ impl Lobby {
     }
 
     #[cfg(feature = "server")]
-    /// Includes a new session ID into the lobby, and assigns a player index to it.
-    pub fn join_player(&mut self, session_id: String) -> Result<(), LobbyError> {
+    /// Includes a new session ID into the lobby, and assigns a player slot to it, returning the slot's [`Team`].
+    /// The rejoin token lets the player reclaim the slot from another session with [`Lobby::rejoin_player`].
+    pub fn join_player(
+        &mut self,
+        session_id: String,
+        rejoin_token: String,
+    ) -> Result<Team, LobbyError> {
         if self.all_ready() {
             Err(LobbyError("cannot join an active game".to_string()))
         } else if self.players.contains_key(&session_id) {
             Err(LobbyError("already in lobby".to_string()))
         } else if let Some(player) = self.player_slots.pop_front() {
+            let team = player.team;
+
+            self.rejoin_tokens.insert(rejoin_token, team);
             self.players.insert(session_id.clone(), player);
 
             self.tick();
 
-            Ok(())
+            Ok(team)
         } else {
             Err(LobbyError("no available slots in lobby".to_string()))
         }
     }
 
-    // #[cfg(feature = "server")]
-    // pub fn leave_player(&mut self, session_id: String) -> Result<String, LobbyError> {
-    //     if self.state == LobbyState::Finished {
-    //         Err(LobbyError("cannot leave a finished game".to_string()))
-    //     } else {
-    //         match self.players.remove(&session_id) {
-    //             Some(player) => {
-    //                 self.player_slots.push_back(player.index);
+    #[cfg(feature = "server")]
+    /// Moves the slot of a rejoin token to the given session ID, along with its commitment, returning the slot's [`Team`].
+    /// The session which held the slot before is no longer a player.
+    pub fn rejoin_player(
+        &mut self,
+        session_id: String,
+        rejoin_token: &str,
+    ) -> Result<Team, LobbyError> {
+        let team = *self
+            .rejoin_tokens
+            .get(rejoin_token)
+            .ok_or_else(|| LobbyError("invalid rejoin token".to_string()))?;
 
-    //                 self.players.remove(&session_id);
+        let previous_id = self
+            .players
+            .iter()
+            .find(|(_, player)| player.team == team)
+            .map(|(previous_id, _)| previous_id.clone())
+            .ok_or_else(|| LobbyError("invalid rejoin token".to_string()))?;
 
-    //                 self.tick();
+        if previous_id != session_id {
+            if self.players.contains_key(&session_id) {
+                return Err(LobbyError("already in lobby".to_string()));
+            }
+
+            if let Some(player) = self.players.remove(&previous_id) {
+                self.players.insert(session_id.clone(), player);
+            }
 
-    //                 Ok(session_id)
-    //             }
-    //             None => Err(LobbyError("player not in lobby".to_string())),
-    //         }
-    //     }
-    // }
+            if let Some(turn) = self.commitments.remove(&previous_id) {
+                self.commitments.insert(session_id, turn);
+            }
+
+            self.tick();
+        }
+
+        Ok(team)
+    }
 
     #[cfg(feature = "server")]
-    /// Executes a certain [`Message`] for the player.
-    pub fn act_player(&mut self, session_id: String, message: Message) -> Result<(), LobbyError> {
+    /// Removes a session from the lobby, returning the [`Team`] it played.
+    /// Before the game starts, its slot is opened to other sessions again.
+    /// Once it has started, the slot is kept for the player to rejoin, until [`Lobby::forfeit_disconnected`] ends the game.
+    pub fn leave_player(&mut self, session_id: &str) -> Result<Team, LobbyError> {
+        let team = self
+            .players
+            .get(session_id)
+            .map(|player| player.team)
+            .ok_or_else(|| LobbyError("player not in lobby".to_string()))?;
+
+        if !self.all_ready() {
+            self.players.remove(session_id);
+            self.commitments.remove(session_id);
+            self.rejoin_tokens.retain(|_, slot_team| *slot_team != team);
+
+            // Slots are handed out red first, so a freed red slot goes back to the front.
+            match team {
+                Team::Red => self
+                    .player_slots
+                    .push_front(Player::new(team, Duration::default())),
+                Team::Blue => self
+                    .player_slots
+                    .push_back(Player::new(team, Duration::default())),
+            }
+
+            self.tick();
+        }
+
+        Ok(team)
+    }
+
+    #[cfg(feature = "server")]
+    /// Forfeits the game of a player without a heartbeat within the timeout, while their opponent is still connected.
+    /// Returns the [`Team`] that forfeited, if any. Games that are in setup or finished are left alone.
+    pub fn forfeit_disconnected(&mut self, timestamp: Duration, timeout: Duration) -> Option<Team> {
+        if !self.all_ready() || self.in_setup() || self.finished() {
+            return None;
+        }
+
+        let (disconnected, connected): (Vec<&Player>, Vec<&Player>) = self
+            .players
+            .values()
+            .partition(|player| timestamp.saturating_sub(player.latest_heartbeat) >= timeout);
+
+        match (disconnected.as_slice(), connected.is_empty()) {
+            ([player], false) => {
+                let team = player.team;
+
+                self.game.forfeit(team);
+                self.tick();
+
+                Some(team)
+            }
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "server")]
+    /// Executes a certain [`Message`] for the player, returning the [`GameEvent`]s it caused.
+    pub fn act_player(
+        &mut self,
+        session_id: String,
+        message: Message,
+    ) -> Result<Vec<GameEvent>, LobbyError> {
         if !self.all_ready() {
             Err(LobbyError("game not yet started".to_string()))
         } else {
             match self.players.get(&session_id) {
                 Some(player) => {
-                    if self.game.turn_for() == player.team {
-                        if let Message::Turn(Turn(from, to)) = message {
-                            self.game.take_move(from, to);
+                    let team = player.team;
+
+                    match message {
+                        message if self.is_drafting() => match message {
+                            Message::Pick(sort) => self.pick(team, sort).map(|_| Vec::new()),
+                            Message::Place(sort, position) => {
+                                self.place(team, sort, position).map(|_| Vec::new())
+                            }
+                            _ => Err(LobbyError("draft not yet complete".to_string())),
+                        },
+                        message if self.is_deploying() => match message {
+                            Message::Deploy(positions) => {
+                                self.deploy(team, positions).map(|_| Vec::new())
+                            }
+                            _ => Err(LobbyError("deployment not yet complete".to_string())),
+                        },
+                        Message::OfferDraw => self.offer_draw(team),
+                        Message::AcceptDraw => self.accept_draw(team),
+                        Message::DeclineDraw => self.decline_draw(team).map(|_| Vec::new()),
+                        message if self.game.is_simultaneous() => {
+                            if let Message::Turn(turn) = message {
+                                let events = self.commit_turn(session_id, team, turn)?;
+                                self.lapse_draw_offer();
+
+                                Ok(events)
+                            } else {
+                                Ok(Vec::new())
+                            }
                         }
+                        message if self.game.turn_for() == team => {
+                            if let Message::Turn(Turn(from, to)) = message {
+                                let events = self.game.take_move_checked(from, to)?;
+                                self.lapse_draw_offer();
 
-                        Ok(())
-                    } else {
-                        Err(LobbyError("not your turn".to_string()))
+                                Ok(events)
+                            } else {
+                                Ok(Vec::new())
+                            }
+                        }
+                        _ => Err(MoveError::NotYourTurn.into()),
                     }
                 }
                 None => Err(LobbyError("player not in lobby".to_string())),
@@ -161,6 +364,129 @@ impl Lobby {
         }
     }
 
+    #[cfg(feature = "server")]
+    /// Offers a draw on behalf of a [`Team`], or accepts the opponent's standing offer.
+    fn offer_draw(&mut self, team: Team) -> Result<Vec<GameEvent>, LobbyError> {
+        if self.finished() {
+            Err(MoveError::GameOver.into())
+        } else if self.draw_offer == Some(team.enemy()) {
+            self.accept_draw(team)
+        } else {
+            self.draw_offer = Some(team);
+
+            self.tick();
+
+            Ok(Vec::new())
+        }
+    }
+
+    #[cfg(feature = "server")]
+    /// Accepts the opponent's draw offer, ending the game in a draw.
+    fn accept_draw(&mut self, team: Team) -> Result<Vec<GameEvent>, LobbyError> {
+        if self.finished() {
+            Err(MoveError::GameOver.into())
+        } else if self.draw_offer == Some(team.enemy()) {
+            self.game.agree_draw();
+            self.draw_offer = None;
+
+            self.tick();
+
+            Ok(vec![GameEvent::GameEnded { winner: None }])
+        } else {
+            Err(LobbyError("no draw offer to accept".to_string()))
+        }
+    }
+
+    #[cfg(feature = "server")]
+    /// Declines the opponent's draw offer.
+    fn decline_draw(&mut self, team: Team) -> Result<(), LobbyError> {
+        if self.finished() {
+            Err(MoveError::GameOver.into())
+        } else if self.draw_offer == Some(team.enemy()) {
+            self.draw_offer = None;
+
+            self.tick();
+
+            Ok(())
+        } else {
+            Err(LobbyError("no draw offer to decline".to_string()))
+        }
+    }
+
+    #[cfg(feature = "server")]
+    /// Withdraws any standing draw offer once a move is made, so it cannot be accepted on a changed board.
+    fn lapse_draw_offer(&mut self) {
+        self.draw_offer = None;
+    }
+
+    /// Returns the [`Team`] with a standing draw offer, if any.
+    pub fn draw_offer(&self) -> Option<Team> {
+        self.draw_offer
+    }
+
+    #[cfg(feature = "server")]
+    /// Commits a player's [`Turn`] for the current round of a simultaneous game.
+    /// Once every player has committed, the [`Turn`]s are revealed and resolved together, the starting team's first.
+    /// Returns the [`GameEvent`]s of the round once it is resolved.
+    fn commit_turn(
+        &mut self,
+        session_id: String,
+        team: Team,
+        turn: Turn,
+    ) -> Result<Vec<GameEvent>, LobbyError> {
+        use crate::Mages;
+
+        let Turn(from, to) = turn;
+
+        if self.commitments.contains_key(&session_id) {
+            return Err(LobbyError("turn already committed".to_string()));
+        }
+
+        self.game.check_move(from, to)?;
+
+        if !self.game.live_occupied_by(&from, team) {
+            return Err(MoveError::NotYourTurn.into());
+        }
+
+        self.commitments.insert(session_id.clone(), turn);
+
+        if self.commitments.len() < self.players.len() {
+            return Ok(Vec::new());
+        }
+
+        let mut round: Vec<(Team, Turn)> = self
+            .commitments
+            .iter()
+            .filter_map(|(session_id, turn)| {
+                self.players
+                    .get(session_id)
+                    .map(|player| (player.team, *turn))
+            })
+            .collect();
+
+        round.sort_by_key(|(team, _)| *team != self.game.starting_team());
+
+        // The round is resolved on a copy of the game, so that it is applied either entirely or not at all.
+        let mut game = self.game.clone();
+        let mut events = Vec::new();
+
+        for (_, Turn(from, to)) in round {
+            match game.take_move_checked(from, to) {
+                Ok(mut turn_events) => events.append(&mut turn_events),
+                Err(error) => {
+                    // The last commitment is withdrawn, leaving its player free to commit another turn.
+                    self.commitments.remove(&session_id);
+                    return Err(error.into());
+                }
+            }
+        }
+
+        self.game = game;
+        self.commitments.clear();
+
+        Ok(events)
+    }
+
     #[cfg(feature = "server")]
     /// Requests a rematch for the active game.
     pub fn request_rematch(&mut self, session_id: String) -> Result<bool, LobbyError> {
@@ -185,13 +511,123 @@ impl Lobby {
     }
 
     /// Makes a fully-reset clone of this [`Lobby`].
+    /// Spectators keep watching the remade lobby.
     pub fn remake(&mut self, first_heartbeat: Duration) {
+        let spectators = self.spectators;
+
         *self = Lobby::new(self.settings.clone(), first_heartbeat);
+        self.spectators = spectators;
     }
 
     /// Determines if the game is finished.
     pub fn finished(&self) -> bool {
-        self.game.result().is_some()
+        !self.in_setup() && self.game.result().is_some()
+    }
+
+    /// Determines if the lobby is still drafting or deploying its mages, so the game has not begun yet.
+    pub fn in_setup(&self) -> bool {
+        self.is_drafting() || self.is_deploying()
+    }
+
+    /// Determines if the lobby is still in its hidden deployment phase.
+    pub fn is_deploying(&self) -> bool {
+        self.deployment.is_some()
+    }
+
+    /// Returns the [`Deployment`] while the lobby is deploying.
+    pub fn deployment(&self) -> Option<&Deployment> {
+        self.deployment.as_ref()
+    }
+
+    /// Shows an arrangement of a [`Team`]'s mages without committing it, hiding the enemy's mages.
+    /// Used by clients while the player arranges their mages.
+    pub fn arrange(&mut self, team: Team, positions: &[Position]) -> Result<(), LobbyError> {
+        let deployment = self
+            .deployment
+            .as_ref()
+            .ok_or(LobbyError("lobby is not deploying".to_string()))?;
+
+        deployment.check(team, positions)?;
+
+        self.game = self
+            .settings
+            .game(&deployment.level_for(Some(team), Some(positions)));
+
+        Ok(())
+    }
+
+    /// Commits the arrangement of a [`Team`]'s mages.
+    /// Once both teams have committed, both arrangements are revealed and the game begins.
+    pub fn deploy(&mut self, team: Team, positions: Vec<Position>) -> Result<(), LobbyError> {
+        let deployment = self
+            .deployment
+            .as_mut()
+            .ok_or(LobbyError("lobby is not deploying".to_string()))?;
+
+        deployment.commit(team, positions)?;
+
+        if deployment.is_complete() {
+            self.game = self.settings.game(&deployment.level());
+            self.deployment = None;
+        }
+
+        self.tick();
+
+        Ok(())
+    }
+
+    /// Determines if the lobby is still drafting its mages.
+    pub fn is_drafting(&self) -> bool {
+        self.draft.is_some()
+    }
+
+    /// Returns the [`Draft`] while the lobby is drafting.
+    pub fn draft(&self) -> Option<&Draft> {
+        self.draft.as_ref()
+    }
+
+    /// Picks a [`MageSort`] from the draft's pool for a [`Team`].
+    pub fn pick(&mut self, team: Team, sort: MageSort) -> Result<(), LobbyError> {
+        self.update_draft(|draft| draft.pick(team, sort))
+    }
+
+    /// Places one of a [`Team`]'s drafted mages in its home rows.
+    pub fn place(
+        &mut self,
+        team: Team,
+        sort: MageSort,
+        position: Position,
+    ) -> Result<(), LobbyError> {
+        self.update_draft(|draft| draft.place(team, sort, position))
+    }
+
+    /// Picks or places a mage on behalf of the AI opponent.
+    pub fn auto_draft(&mut self, seed: u64) -> Result<(), LobbyError> {
+        self.update_draft(|draft| draft.auto_draft(seed))
+    }
+
+    /// Applies an action to the [`Draft`] and rebuilds the [`Game`] from the mages placed so far.
+    /// Once every mage has been placed, the draft ends and the game begins.
+    fn update_draft(
+        &mut self,
+        action: impl FnOnce(&mut Draft) -> Result<(), DraftError>,
+    ) -> Result<(), LobbyError> {
+        let draft = self
+            .draft
+            .as_mut()
+            .ok_or(LobbyError("lobby is not drafting".to_string()))?;
+
+        action(draft)?;
+
+        self.game = self.settings.game(&draft.level());
+
+        if draft.is_complete() {
+            self.draft = None;
+        }
+
+        self.tick();
+
+        Ok(())
     }
 
     /// Determines if the game is local (`true`) or online.
@@ -206,13 +642,30 @@ impl Lobby {
 
     /// Determines if the given session ID is the one taking its turn.
     pub fn is_active_player(&self, session_id: Option<&String>) -> bool {
-        if self.is_local() {
+        if let Some(deployment) = &self.deployment {
+            match self.player_team(session_id) {
+                _ if self.is_local() => {
+                    !(self.has_ai() && deployment.turn_for() == Some(Team::Blue))
+                }
+                Some(team) => self.all_ready() && !deployment.has_deployed(team),
+                None => false,
+            }
+        } else if let Some(draft) = &self.draft {
+            match draft.turn_for() {
+                Some(team) if self.is_local() => !(self.has_ai() && team == Team::Blue),
+                Some(team) => self.all_ready() && self.player_team(session_id) == Some(team),
+                None => false,
+            }
+        } else if self.is_local() {
             !(self.has_ai() && self.game.turn_for() == Team::Blue)
         } else if !self.all_ready() {
             false
         } else {
             match session_id {
                 Some(session_id) => match self.players.get(session_id) {
+                    Some(_) if self.game.is_simultaneous() => {
+                        !self.finished() && !self.commitments.contains_key(session_id)
+                    }
                     Some(player) => self.game.turn_for() == player.team,
                     None => false,
                 },
@@ -221,6 +674,52 @@ impl Lobby {
         }
     }
 
+    /// Returns the [`Team`] of the player with the given session ID, if they are in this lobby.
+    pub fn player_team(&self, session_id: Option<&String>) -> Option<Team> {
+        session_id
+            .and_then(|session_id| self.players.get(session_id))
+            .map(|player| player.team)
+    }
+
+    #[cfg(feature = "server")]
+    /// Returns a copy of the [`Lobby`] as seen by the given session ID.
+    /// Rejoin tokens are never included, and with fog of war, the [`Game`] is stripped of everything the session's team cannot see once the game has started.
+    /// While deploying, only the session's own mages are shown and the other team's arrangement is masked.
+    /// Other sessions' commitments are always masked, so only the fact that they have committed is revealed.
+    pub fn view_for(&self, session_id: Option<&String>) -> Lobby {
+        let mut lobby = if let Some(deployment) = &self.deployment {
+            let team = self.player_team(session_id);
+
+            Lobby {
+                game: self.settings.game(&deployment.level_for(team, None)),
+                deployment: Some(deployment.masked(team)),
+                ..self.clone()
+            }
+        } else if self.settings.fog_of_war && self.all_ready() {
+            Lobby {
+                game: self.game.fogged(self.player_team(session_id)),
+                ..self.clone()
+            }
+        } else {
+            self.clone()
+        };
+
+        for (committed_id, turn) in lobby.commitments.iter_mut() {
+            if Some(committed_id) != session_id {
+                *turn = Turn::sentinel();
+            }
+        }
+
+        lobby.rejoin_tokens.clear();
+
+        lobby
+    }
+
+    /// Determines if the given session ID has committed its [`Turn`] for the current round of a simultaneous game.
+    pub fn has_committed(&self, session_id: Option<&String>) -> bool {
+        session_id.is_some_and(|session_id| self.commitments.contains_key(session_id))
+    }
+
     /// Detemines whether or not the given session ID is in this lobby.
     pub fn has_session_id(&self, session_id: Option<&String>) -> bool {
         match session_id {
@@ -238,6 +737,11 @@ impl Lobby {
     pub fn rewind(&mut self, delta: usize) {
         let rewinded_game = self.game.rewind(delta);
 
+        // A pending draw offer was made for the position being rewound past.
+        if rewinded_game.turns() != self.game.turns() {
+            self.draw_offer = None;
+        }
+
         self.game = rewinded_game;
 
         // This is a state-modfying action and in turn must be communicated with the connected clients.
@@ -245,11 +749,11 @@ impl Lobby {
         self.tick();
     }
 
-    /// Checks if any [`Player`]s are connected to this [`Lobby`].
-    pub fn any_connected(&self, timestamp: Duration) -> bool {
+    /// Checks if any [`Player`]s are connected to this [`Lobby`], having sent a heartbeat within the timeout.
+    pub fn any_connected(&self, timestamp: Duration, timeout: Duration) -> bool {
         self.players
             .iter()
-            .any(|(_, player)| timestamp - player.latest_heartbeat < Duration::from_secs(15))
+            .any(|(_, player)| timestamp - player.latest_heartbeat < timeout)
     }
 
     /// Returns the latest heartbeat timestamp for this [`Lobby`].
@@ -271,6 +775,34 @@ impl Lobby {
         }
     }
 
+    /// Prepares a [`Lobby`] restored from storage after a restart of the server.
+    /// Its players are given a fresh heartbeat to reconnect within, and its spectators are gone.
+    #[cfg(feature = "server")]
+    pub fn restore(&mut self, timestamp: Duration) {
+        for player in self.players.values_mut() {
+            player.latest_heartbeat = timestamp;
+        }
+
+        self.spectators = 0;
+    }
+
+    /// Returns the number of spectators watching this [`Lobby`].
+    pub fn spectators(&self) -> usize {
+        self.spectators
+    }
+
+    /// Counts a spectator who started watching this [`Lobby`].
+    #[cfg(feature = "server")]
+    pub fn add_spectator(&mut self) {
+        self.spectators += 1;
+    }
+
+    /// Stops counting a spectator who stopped watching this [`Lobby`].
+    #[cfg(feature = "server")]
+    pub fn remove_spectator(&mut self) {
+        self.spectators = self.spectators.saturating_sub(1);
+    }
+
     /// Returns the first heartbeat of this [`Lobby`].
     pub fn first_heartbeat(&self) -> Duration {
         self.first_heartbeat
@@ -296,6 +828,18 @@ pub enum LoadoutMethod {
     EditorPrefab(Level),
     /// An Arena level with attached position.
     Arena(Level, (isize, isize)),
+    /// A level procedurally generated from the lobby's seed.
+    Generated {
+        /// Parameters passed to [`Level::generate`].
+        params: GeneratorParams,
+    },
+    /// Both players pick mages from a pool and place them in their home rows, as a [`Draft`].
+    Draft {
+        /// Board to draft on.
+        board: Board,
+        /// Number of mages on each team.
+        mages: usize,
+    },
 }
 
 impl Display for LoadoutMethod {
@@ -315,6 +859,12 @@ impl Display for LoadoutMethod {
             LoadoutMethod::Prefab(_) => "Custom".to_string(),
             LoadoutMethod::EditorPrefab(_) => "Custom".to_string(),
             LoadoutMethod::Arena(_, _) => todo!(),
+            LoadoutMethod::Generated { params } => {
+                format!("Generated ({0} by {1})", params.width, params.height)
+            }
+            LoadoutMethod::Draft { board, .. } => {
+                format!("Draft ({0} by {1})", board.width, board.height)
+            }
         }.as_str())
     }
 }
@@ -352,6 +902,15 @@ pub struct LobbySettings {
     pub seed: u64,
     /// Can stalemate
     pub can_stalemate: bool,
+    /// Each team only sees the tiles within its mages' movement or spell range.
+    #[serde(default)]
+    pub fog_of_war: bool,
+    /// Both teams commit their turns secretly, and they are resolved together.
+    #[serde(default)]
+    pub simultaneous: bool,
+    /// Both teams arrange their mages within their home rows secretly before the game begins, as a [`Deployment`].
+    #[serde(default)]
+    pub deployment: bool,
 }
 
 impl LobbySettings {
@@ -420,7 +979,112 @@ impl LobbySettings {
             LoadoutMethod::Prefab(level)
             | LoadoutMethod::EditorPrefab(level)
             | LoadoutMethod::Arena(level, _) => level.clone(),
+            // Invalid parameters fall back to the default level, as the lobby has to be instantiable.
+            LoadoutMethod::Generated { params } => Level::generate(params, rng.next_u64())
+                .unwrap_or_else(|_| {
+                    Level::default_with_mages(Self::generate_loadout_by_sorts(
+                        &Board::default(),
+                        Self::default_loadout(),
+                        Self::default_loadout(),
+                    ))
+                }),
+            LoadoutMethod::Draft { board, .. } => Level::new(
+                board.clone(),
+                Vec::new(),
+                BTreeMap::default(),
+                Team::default(),
+            ),
+        }
+    }
+
+    /// Starts the [`Draft`] for a [`LoadoutMethod::Draft`] lobby, with a pool of two more mages than both teams need.
+    /// The number of mages is clamped to what fits into the home rows.
+    fn draft(&self, level: &Level, rng: &mut ChaCha8Rng) -> Option<Draft> {
+        if let LoadoutMethod::Draft { board, mages } = &self.loadout_method {
+            let mages = (*mages).clamp(1, board.width * board.home_rows());
+            let pool = (0..mages * 2 + 2)
+                .map(|_| ((rng.next_u64() % 4) as usize).into())
+                .collect::<Vec<MageSort>>();
+
+            Some(Draft::new(board.clone(), mages, pool, level.starting_team))
+        } else {
+            None
+        }
+    }
+
+    fn game(&self, level: &Level) -> Game {
+        if self.simultaneous {
+            Game::new_simultaneous(level, self.can_stalemate)
+        } else {
+            Game::new(level, self.can_stalemate)
         }
+        .expect("game should be instantiable with default values")
+    }
+
+    /// Validates settings sent by a client, and normalises what a client should not decide.
+    /// Boards are checked against the limits of [`Board::new`], which deserialising skips, and levels with [`Level::validate`].
+    /// Mages are renumbered and given the spells of their sorts, drafts are clamped to the home rows, and the seed is replaced.
+    pub fn normalized(mut self, seed: u64) -> Result<LobbySettings, SettingsError> {
+        match &mut self.loadout_method {
+            LoadoutMethod::Default | LoadoutMethod::Random { .. } => (),
+            LoadoutMethod::DefaultBoard(board) => Self::check_board(board)?,
+            LoadoutMethod::Prefab(level)
+            | LoadoutMethod::EditorPrefab(level)
+            | LoadoutMethod::Arena(level, _) => Self::normalize_level(level)?,
+            LoadoutMethod::Generated { params } => {
+                params.validate().map_err(SettingsError::InvalidGenerator)?
+            }
+            LoadoutMethod::Draft { board, mages } => {
+                Self::check_board(board)?;
+                *mages = (*mages).clamp(1, board.width * board.home_rows());
+            }
+        }
+
+        self.seed = seed;
+
+        Ok(self)
+    }
+
+    fn check_board(board: &Board) -> Result<(), SettingsError> {
+        Board::with_style(board.width, board.height, board.style.clone())
+            .map(|_| ())
+            .map_err(SettingsError::InvalidBoard)
+    }
+
+    fn normalize_level(level: &mut Level) -> Result<(), SettingsError> {
+        // The board is checked first, as validating a level walks all of its tiles.
+        Self::check_board(&level.board)?;
+
+        for team in [Team::Red, Team::Blue] {
+            if level.mages.iter().filter(|mage| mage.team == team).count()
+                > level.board.width * level.board.height / 2
+            {
+                return Err(SettingsError::TooManyMages(team));
+            }
+        }
+
+        for mage in &level.mages {
+            if mage.mana.0 > mage.mana.1 || mage.mana.1 > MAX_MANA {
+                return Err(SettingsError::InvalidMana(mage.position));
+            }
+
+            if let Some(PowerUp::Boulder(_)) = mage.powerup {
+                return Err(SettingsError::InvalidPowerUp(mage.position));
+            }
+        }
+
+        if let Some(issue) = level.validate().into_iter().find(LevelIssue::is_error) {
+            return Err(SettingsError::InvalidLevel(issue));
+        }
+
+        for (index, mage) in level.mages.iter_mut().enumerate() {
+            mage.index = index;
+            mage.spell = Spell::select(mage.sort);
+        }
+
+        level.mage_index = level.mages.len();
+
+        Ok(())
     }
 }
 
@@ -431,6 +1095,9 @@ impl Default for LobbySettings {
             loadout_method: Default::default(),
             seed: Default::default(),
             can_stalemate: true,
+            fog_of_war: false,
+            simultaneous: false,
+            deployment: false,
         }
     }
 }
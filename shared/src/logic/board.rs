@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use crate::{Mage, MageSort, Position, Team};
 
 /// Style for board.
-#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone, PartialEq)]
 pub enum BoardStyle {
     /// Default grassy style.
     #[default]
@@ -58,9 +58,42 @@ impl BoardStyle {
     }
 }
 
+impl From<u8> for BoardStyle {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Grass,
+            1 => Self::Teleport,
+            2 => Self::Desert,
+            3 => Self::Flesh,
+            4 => Self::Crust,
+            5 => Self::Eldritch,
+            _ => Self::default(),
+        }
+    }
+}
+
+impl From<&BoardStyle> for u8 {
+    fn from(value: &BoardStyle) -> Self {
+        match value {
+            BoardStyle::Grass => 0,
+            BoardStyle::Teleport => 1,
+            BoardStyle::Desert => 2,
+            BoardStyle::Flesh => 3,
+            BoardStyle::Crust => 4,
+            BoardStyle::Eldritch => 5,
+        }
+    }
+}
+
 /// Default size of the game board.
 pub const DEFAULT_BOARD_SIZE: (usize, usize) = (6, 6);
 
+/// Smallest allowed width and height of the game board.
+pub const MIN_BOARD_SIZE: usize = 3;
+
+/// Largest allowed width and height of the game board.
+pub const MAX_BOARD_SIZE: usize = 16;
+
 /// [`Board`] is a struct which currently only contains the size of the playing field.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Board {
@@ -74,32 +107,28 @@ pub struct Board {
 
 impl Board {
     /// Instantiates the [`Board`] `struct` with a certain size.
-    /// Restricted to `4..=8` on both axes. Currently always 8-by-8.
+    /// Restricted to [`MIN_BOARD_SIZE`]`..=`[`MAX_BOARD_SIZE`] on both axes.
     pub fn new(width: usize, height: usize) -> Result<Board, &'static str> {
-        match (width, height) {
-            (width, height) if (3..=8).contains(&width) && (3..=8).contains(&height) => Ok(Board {
-                width,
-                height,
-                ..Default::default()
-            }),
-            _ => Err("board size does not conform to limits"),
-        }
+        Board::with_style(width, height, BoardStyle::default())
     }
 
-    /// Instantiates the [`Board`] `struct` with a certain size.
-    /// Restricted to `4..=8` on both axes. Currently always 8-by-8.
+    /// Instantiates the [`Board`] `struct` with a certain size and style.
+    /// Restricted to [`MIN_BOARD_SIZE`]`..=`[`MAX_BOARD_SIZE`] on both axes.
     pub fn with_style(
         width: usize,
         height: usize,
         style: BoardStyle,
     ) -> Result<Board, &'static str> {
-        match (width, height) {
-            (width, height) if (3..=8).contains(&width) && (3..=8).contains(&height) => Ok(Board {
+        let limits = MIN_BOARD_SIZE..=MAX_BOARD_SIZE;
+
+        if limits.contains(&width) && limits.contains(&height) {
+            Ok(Board {
                 width,
                 height,
                 style,
-            }),
-            _ => Err("board size does not conform to limits"),
+            })
+        } else {
+            Err("board size does not conform to limits")
         }
     }
 
@@ -112,6 +141,25 @@ impl Board {
         }
     }
 
+    /// Number of rows at either end of the board that make up each team's home zone.
+    /// At least one row is always left between the two teams.
+    pub fn home_rows(&self) -> usize {
+        (self.height.saturating_sub(2) / 2).max(1)
+    }
+
+    /// Determines if a [`Position`] lies within the home rows of a [`Team`], [`Team::Red`]'s being at the bottom.
+    pub fn is_home_position(&self, position: Position, team: Team) -> bool {
+        let home_rows = self.home_rows() as i8;
+
+        (0..self.width as i8).contains(&position.0)
+            && match team {
+                Team::Red => {
+                    (self.height as i8 - home_rows..self.height as i8).contains(&position.1)
+                }
+                Team::Blue => (0..home_rows).contains(&position.1),
+            }
+    }
+
     /// Returns a list of [`Mage`]s already indexed, positioned on the board, and instantiated.
     pub fn place_mages(&self, team: Team, mage_sorts: Vec<MageSort>, offset: usize) -> Vec<Mage> {
         let x_offset = ((self.width - mage_sorts.len()) / 2) as i8;
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Position, PowerUp, Team};
+
+/// Something that happened while a [`crate::Turn`] was taken, as reported by [`crate::Game::take_move`].
+/// [`Mage`](crate::Mage)s are referred to by their index.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum GameEvent {
+    /// A mage moved from one [`Position`] to another.
+    Moved {
+        /// Index of the mage.
+        mage: usize,
+        /// [`Position`] the mage moved from.
+        from: Position,
+        /// [`Position`] the mage moved to.
+        to: Position,
+    },
+    /// A mage picked up a [`PowerUp`] lying on the board.
+    PickedUp {
+        /// Index of the mage.
+        mage: usize,
+        /// [`Position`] of the [`PowerUp`].
+        at: Position,
+        /// The [`PowerUp`] picked up.
+        powerup: PowerUp,
+    },
+    /// A mage was hit by a spell.
+    Hit {
+        /// Index of the mage hit.
+        mage: usize,
+        /// [`Position`] of the mage hit.
+        at: Position,
+        /// Mana lost by the mage.
+        damage: u8,
+    },
+    /// A mage ran out of mana.
+    MageFell {
+        /// Index of the mage.
+        mage: usize,
+        /// [`Position`] of the mage.
+        at: Position,
+    },
+    /// A mage attacked into a shielded [`Position`] and its spell was turned back on itself.
+    ShieldBlocked {
+        /// Index of the attacking mage.
+        mage: usize,
+        /// [`Position`] of the attacking mage.
+        at: Position,
+    },
+    /// A mage used up its [`PowerUp::Beam`].
+    BeamFired {
+        /// Index of the mage.
+        mage: usize,
+        /// [`Position`] the beam was fired from.
+        at: Position,
+    },
+    /// The game finished, with the winning [`Team`] or `None` for a stalemate.
+    GameEnded {
+        /// The winning [`Team`], if any.
+        winner: Option<Team>,
+    },
+}
+
+impl GameEvent {
+    /// Returns the [`Position`] of a [`GameEvent::Hit`].
+    pub fn hit(&self) -> Option<Position> {
+        match self {
+            GameEvent::Hit { at, .. } => Some(*at),
+            _ => None,
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use std::{
     collections::{BTreeMap, HashSet},
+    fmt::Display,
+    hash::{Hash, Hasher},
     ops::Neg,
 };
 
@@ -9,7 +11,10 @@ use rand_chacha::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{Board, Level, Mage, Mages, Position, PowerUp, Team, Turn};
+use crate::{
+    Board, GameEvent, Level, LevelCodeError, LevelMetadata, Mage, Mages, Position, PowerUp, Team,
+    Turn,
+};
 
 /// Leaf node for use in search algorithms.
 pub struct TurnLeaf(pub Turn, pub isize);
@@ -34,6 +39,82 @@ impl PartialOrd<isize> for TurnLeaf {
     }
 }
 
+/// Directions a [`Mage`] can move in, and whether or not they are diagonal.
+pub(crate) const MOVE_DIRECTIONS: [(Position, bool); 8] = [
+    (Position(0, -1), false),
+    (Position(-1, 0), false),
+    (Position(1, 0), false),
+    (Position(0, 1), false),
+    (Position(-1, -1), true),
+    (Position(-1, 1), true),
+    (Position(1, -1), true),
+    (Position(1, 1), true),
+];
+
+/// A [FNV-1a](https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function) hasher for position hashes.
+/// Unlike [`std::collections::hash_map::DefaultHasher`], it is stable across builds and pointer widths, so the client and the server agree on its output.
+struct PositionHasher(u64);
+
+impl PositionHasher {
+    fn new() -> PositionHasher {
+        PositionHasher(0xcbf29ce484222325)
+    }
+}
+
+impl Hasher for PositionHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write(&(i as u64).to_le_bytes());
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write(&(i as i64).to_le_bytes());
+    }
+}
+
+/// Reasons for a [`Turn`] to be rejected by [`Game::check_move`] and [`Game::take_move_checked`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveError {
+    /// The [`Mage`] at the source belongs to the [`Team`] not taking its turn.
+    NotYourTurn,
+    /// There is no [`Mage`] at the source.
+    NoMage,
+    /// The [`Mage`] at the source has run out of mana.
+    MageAsleep,
+    /// The destination is not next to the source, or lies off the [`Board`].
+    OutOfReach,
+    /// The destination is occupied by a [`Mage`] or a boulder.
+    DestinationBlocked,
+    /// The destination is diagonal, and the [`Mage`] does not have [`PowerUp::Diagonal`].
+    DiagonalNotAllowed,
+    /// The game has already finished.
+    GameOver,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            MoveError::NotYourTurn => "not your turn",
+            MoveError::NoMage => "no mage to move",
+            MoveError::MageAsleep => "mage is asleep",
+            MoveError::OutOfReach => "out of reach",
+            MoveError::DestinationBlocked => "destination blocked",
+            MoveError::DiagonalNotAllowed => "diagonal not allowed",
+            MoveError::GameOver => "game over",
+        })
+    }
+}
+
 /// Result of a game.
 #[derive(PartialEq)]
 pub enum GameResult {
@@ -54,6 +135,16 @@ pub struct Game {
     available_turns: Vec<Turn>,
     shielded_positions: HashSet<(Position, Team)>,
     can_stalemate: bool,
+    #[serde(default)]
+    fogged: bool,
+    #[serde(default)]
+    simultaneous: bool,
+    #[serde(default)]
+    position_hashes: Vec<u64>,
+    #[serde(default)]
+    drawn: bool,
+    #[serde(default)]
+    forfeited: Option<Team>,
 }
 
 impl Game {
@@ -70,14 +161,34 @@ impl Game {
             available_turns: Vec::new(),
             shielded_positions: HashSet::new(),
             can_stalemate,
+            fogged: false,
+            simultaneous: false,
+            position_hashes: Vec::new(),
+            drawn: false,
+            forfeited: None,
         };
 
         game.available_turns = game.generate_available_turns();
         game.shielded_positions = game.generate_shielded_positions();
+        game.position_hashes.push(game.position_hash());
+
+        Ok(game)
+    }
+
+    /// Instantiates a simultaneous-turn [`Game`], where both teams commit a [`Turn`] per round and the two are resolved together.
+    /// Each round occupies two consecutive entries of the [`Turn`] history, the starting team's first.
+    pub fn new_simultaneous(level: &Level, can_stalemate: bool) -> Result<Game, &'static str> {
+        let mut game = Game::new(level, can_stalemate)?;
+        game.simultaneous = true;
 
         Ok(game)
     }
 
+    /// Determines if both teams take their turns at the same time.
+    pub fn is_simultaneous(&self) -> bool {
+        self.simultaneous
+    }
+
     /// Can the game stalemate.
     pub fn can_stalemate(&self) -> bool {
         self.can_stalemate
@@ -99,7 +210,29 @@ impl Game {
 
     /// Determines if the game is finished.
     pub fn result(&self) -> Option<GameResult> {
-        if self.available_turns.is_empty() || self.stalemate().0 {
+        if let Some(team) = self.forfeited {
+            return Some(GameResult::Win(team.enemy()));
+        }
+
+        if self.fogged {
+            // The outcome cannot be determined from a partial view of the board.
+            return None;
+        }
+
+        // A simultaneous round only resolves once both of its turns are in, so the game cannot end halfway through one.
+        if self.simultaneous && !self.turns().is_multiple_of(2) {
+            return self.drawn.then_some(GameResult::Stalemate);
+        }
+
+        if self.can_stalemate && self.repetitions() >= 3 {
+            return Some(GameResult::Stalemate);
+        }
+
+        // In simultaneous games, a round cannot be played unless both teams are able to move.
+        let stuck = self.available_turns.is_empty()
+            || self.simultaneous && self.available_turns_for(self.turn_for().enemy()).is_empty();
+
+        let outcome = (stuck || self.stalemate().0).then(|| {
             let mana_diff: isize = self
                 .level
                 .mages
@@ -111,15 +244,74 @@ impl Game {
                 .sum();
 
             match mana_diff.cmp(&0) {
-                std::cmp::Ordering::Less => Some(GameResult::Win(Team::Blue)),
-                std::cmp::Ordering::Equal => Some(GameResult::Stalemate),
-                std::cmp::Ordering::Greater => Some(GameResult::Win(Team::Red)),
+                std::cmp::Ordering::Less => GameResult::Win(Team::Blue),
+                std::cmp::Ordering::Equal => GameResult::Stalemate,
+                std::cmp::Ordering::Greater => GameResult::Win(Team::Red),
             }
-        } else {
-            None
+        });
+
+        // A win decided on the board stands, even if a draw is agreed after it.
+        match outcome {
+            Some(GameResult::Win(_)) => outcome,
+            _ if self.drawn => Some(GameResult::Stalemate),
+            _ => outcome,
         }
     }
 
+    /// Returns a hash of the current position, covering the [`Mage`]s, the [`PowerUp`]s on the board and the [`Team`] to move.
+    /// The hash does not depend on the order the [`Mage`]s are stored in.
+    pub fn position_hash(&self) -> u64 {
+        let mut hasher = PositionHasher::new();
+
+        self.turn_for().hash(&mut hasher);
+
+        let mut mages: Vec<&Mage> = self.iter_mages().collect();
+        mages.sort_by_key(|mage| mage.index);
+
+        for mage in mages {
+            mage.index.hash(&mut hasher);
+            mage.position.hash(&mut hasher);
+            mage.mana.0.hash(&mut hasher);
+            mage.powerup.hash(&mut hasher);
+        }
+
+        self.level.powerups.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Returns the number of times the current position has occurred, including now.
+    pub fn repetitions(&self) -> usize {
+        match self.position_hashes.last() {
+            Some(latest) => self
+                .position_hashes
+                .iter()
+                .filter(|hash| *hash == latest)
+                .count(),
+            None => 0,
+        }
+    }
+
+    /// Ends the [`Game`] in a draw agreed upon by both teams.
+    pub fn agree_draw(&mut self) {
+        self.drawn = true;
+    }
+
+    /// Determines if the [`Game`] was ended in a draw agreed upon by both teams.
+    pub fn is_drawn(&self) -> bool {
+        self.drawn
+    }
+
+    /// Ends the [`Game`] with a win for the enemy of the [`Team`] that forfeits it.
+    pub fn forfeit(&mut self, team: Team) {
+        self.forfeited = Some(team);
+    }
+
+    /// Returns the [`Team`] that forfeited the [`Game`], if any.
+    pub fn forfeited(&self) -> Option<Team> {
+        self.forfeited
+    }
+
     /// Returns a list of [`Turn`]s skipping the first `since` turns.
     pub fn turns_since(&self, since: usize) -> Vec<&Turn> {
         self.turns.iter().skip(since).collect()
@@ -155,24 +347,18 @@ impl Game {
         self.turns.len()
     }
 
-    /// Returns the index for the team which will be making the next move.
-    fn turn_index(&self) -> usize {
-        self.turns() % 2
+    /// Returns the [`Team`] which takes the turn with the given index.
+    fn team_for_turn(&self, turn: usize) -> Team {
+        if turn.is_multiple_of(2) {
+            self.level.starting_team
+        } else {
+            self.level.starting_team.enemy()
+        }
     }
 
     /// Returns the [`Team`] which will be taking their turn.
     pub fn turn_for(&self) -> Team {
-        let team = if self.turn_index() == 0 {
-            Team::Red
-        } else {
-            Team::Blue
-        };
-
-        if self.level.starting_team == Team::Red {
-            team
-        } else {
-            team.enemy()
-        }
+        self.team_for_turn(self.turns())
     }
 
     /// Returns the [`Team`] which makes the first move.
@@ -182,20 +368,9 @@ impl Game {
 
     /// Returns a list of all available [`Position`]s a [`Mage`] can move to, including metadata on the direction and whether or not it's a diagonal.
     pub fn available_moves(&self, mage: &Mage) -> Vec<(Position, Position, bool)> {
-        const DIRS: [(Position, bool); 8] = [
-            (Position(0, -1), false),
-            (Position(-1, 0), false),
-            (Position(1, 0), false),
-            (Position(0, 1), false),
-            (Position(-1, -1), true),
-            (Position(-1, 1), true),
-            (Position(1, -1), true),
-            (Position(1, 1), true),
-        ];
-
-        let mut moves = Vec::with_capacity(DIRS.len());
-
-        for (dir, diagonal) in DIRS {
+        let mut moves = Vec::with_capacity(MOVE_DIRECTIONS.len());
+
+        for (dir, diagonal) in MOVE_DIRECTIONS {
             let position = &mage.position + &dir;
             // let position = position.wrap(self.level.board.width as i8, self.level.board.height as i8);
 
@@ -210,10 +385,14 @@ impl Game {
     }
 
     fn generate_available_turns(&self) -> Vec<Turn> {
+        self.available_turns_for(self.turn_for())
+    }
+
+    fn available_turns_for(&self, team: Team) -> Vec<Turn> {
         self.level
             .mages
             .iter()
-            .filter(|mage| mage.is_alive() && mage.team == self.turn_for())
+            .filter(|mage| mage.is_alive() && mage.team == team)
             .map(|mage| (mage, self.available_moves(mage)))
             .flat_map(|(mage, moves)| {
                 moves
@@ -307,6 +486,14 @@ impl Game {
         }
     }
 
+    /// Returns the best turn at the [`crate::Difficulty`] recommended by the [`LevelMetadata`], or from [`Game::best_turn_auto`] without one.
+    pub fn best_turn_recommended(&self, seed: u64) -> Option<TurnLeaf> {
+        match self.metadata().difficulty {
+            Some(difficulty) => self.best_turn(difficulty.depth(), seed),
+            None => self.best_turn_auto(seed),
+        }
+    }
+
     /// Returns the best turn based on the evaluation function and [alpha-beta pruning](https://en.wikipedia.org/wiki/Alpha%E2%80%93beta_pruning).
     pub fn alphabeta(
         &self,
@@ -454,87 +641,243 @@ impl Game {
         }
     }
 
-    /// Executes a [`Turn`], modifying the game state.
-    pub fn take_move(&mut self, from: Position, to: Position) -> Option<Vec<Position>> {
-        if self.result().is_none() {
-            if let Some(mage) = self.level.mages.live_occupant(&from) {
-                if mage.team == self.turn_for() {
-                    let available_moves = self.available_moves(mage);
-                    let potential_move = available_moves
-                        .iter()
-                        .find(|(position, _, _)| *position == to);
+    /// Determines why a [`Turn`] cannot be taken, checking the [`Team`] of the [`Mage`] unless `any_team` is set.
+    fn validate_move(&self, from: Position, to: Position, any_team: bool) -> Result<(), MoveError> {
+        if self.result().is_some() {
+            return Err(MoveError::GameOver);
+        }
 
-                    let mage = self.level.mages.live_occupant_mut(&from).unwrap();
+        let mage = match self.level.mages.live_occupant(&from) {
+            Some(mage) => mage,
+            None if self.level.mages.occupied(&from) => return Err(MoveError::MageAsleep),
+            None => return Err(MoveError::NoMage),
+        };
 
-                    if let Some((to, _, _)) = potential_move {
-                        mage.position = *to;
+        if !any_team && mage.team != self.turn_for() {
+            return Err(MoveError::NotYourTurn);
+        }
 
-                        if let Some(powerup) = self.level.powerups.remove(to) {
-                            mage.powerup = Some(powerup);
-                        }
+        let direction = &to - &from;
 
-                        let attacks = self.attack(*to);
+        match MOVE_DIRECTIONS.iter().find(|(dir, _)| *dir == direction) {
+            Some(_) if self.level.board.validate_position(to).is_none() => {
+                Err(MoveError::OutOfReach)
+            }
+            Some((_, diagonal)) if *diagonal && !mage.has_diagonals() => {
+                Err(MoveError::DiagonalNotAllowed)
+            }
+            Some(_) if self.level.is_blocked(&to) => Err(MoveError::DestinationBlocked),
+            Some(_) => Ok(()),
+            None => Err(MoveError::OutOfReach),
+        }
+    }
 
-                        if !attacks.is_empty() {
-                            self.last_nominal = self.turns();
-                        }
+    /// Checks if a [`Turn`] can be taken, explaining why not otherwise.
+    /// In simultaneous games, the [`Mage`]s of both teams can be moved.
+    pub fn check_move(&self, from: Position, to: Position) -> Result<(), MoveError> {
+        self.validate_move(from, to, self.simultaneous)
+    }
 
-                        self.turns.push(Turn(from, *to));
+    /// Executes a [`Turn`], modifying the game state, and returns the [`GameEvent`]s it caused.
+    pub fn take_move_checked(
+        &mut self,
+        from: Position,
+        to: Position,
+    ) -> Result<Vec<GameEvent>, MoveError> {
+        self.validate_move(from, to, false)?;
 
-                        self.available_turns = self.generate_available_turns();
-                        self.shielded_positions = self.generate_shielded_positions();
+        if self.simultaneous {
+            return Ok(self.take_simultaneous_move(Turn(from, to)));
+        }
 
-                        return Some(attacks);
-                    }
-                }
-            }
+        let mut events = self.move_mage(from, to);
+        events.append(&mut self.attack(to));
+
+        if events.iter().any(|event| event.hit().is_some()) {
+            self.last_nominal = self.turns();
         }
 
-        None
+        self.turns.push(Turn(from, to));
+
+        self.finish_turn(&mut events);
+
+        Ok(events)
     }
 
     /// Executes a [`Turn`], modifying the game state.
+    pub fn take_move(&mut self, from: Position, to: Position) -> Option<Vec<GameEvent>> {
+        self.take_move_checked(from, to).ok()
+    }
+
+    /// Checks if a [`Turn`] can be taken, without modifying the game state.
+    /// In simultaneous games, the [`Mage`]s of both teams can be moved.
     pub fn try_move(&mut self, from: Position, to: Position) -> bool {
-        if self.result().is_none() {
-            if let Some(mage) = self.level.mages.live_occupant(&from) {
-                if mage.team == self.turn_for() {
-                    let available_moves = self.available_moves(mage);
-                    let potential_move = available_moves
-                        .iter()
-                        .find(|(position, _, _)| *position == to);
-
-                    return potential_move.is_some();
-                }
+        self.check_move(from, to).is_ok()
+    }
+
+    /// Records the first [`Turn`] of a simultaneous round, or resolves the round once its second [`Turn`] arrives.
+    /// Both [`Turn`]s must have been validated against the state at the start of the round.
+    ///
+    /// Collisions are resolved as follows:
+    /// - If both [`Mage`]s move to the same [`Position`], they bounce off each other and neither moves nor attacks.
+    /// - Otherwise both [`Mage`]s move, and their attacks land together, so mutual attacks hit both.
+    fn take_simultaneous_move(&mut self, turn: Turn) -> Vec<GameEvent> {
+        let pending = if self.turns() % 2 == 1 {
+            self.turns.last().copied()
+        } else {
+            None
+        };
+
+        self.turns.push(turn);
+        self.available_turns = self.generate_available_turns();
+
+        let Some(pending) = pending else {
+            return Vec::new();
+        };
+
+        let mut events = Vec::new();
+
+        if pending.1 != turn.1 {
+            let mut moved = Vec::with_capacity(2);
+
+            for Turn(from, to) in [pending, turn] {
+                events.append(&mut self.move_mage(from, to));
+                moved.push(self.level.mages.live_occupant(&to).unwrap().index);
+            }
+
+            // Attacks are collected before any damage is dealt, so neither can prevent the other.
+            let attacks: Vec<(usize, usize)> = moved
+                .iter()
+                .flat_map(|index| self.attacks_of(*index))
+                .collect();
+
+            for index in moved {
+                events.extend(self.discharge_beam(index));
+            }
+
+            events.append(&mut self.deal_damage(attacks));
+
+            if events.iter().any(|event| event.hit().is_some()) {
+                self.last_nominal = self.turns();
             }
         }
 
-        false
+        self.finish_turn(&mut events);
+
+        events
+    }
+
+    /// Moves a live [`Mage`] and lets it pick up any [`PowerUp`] at its destination.
+    fn move_mage(&mut self, from: Position, to: Position) -> Vec<GameEvent> {
+        let mage = self.level.mages.live_occupant_mut(&from).unwrap();
+        mage.position = to;
+
+        let mut events = vec![GameEvent::Moved {
+            mage: mage.index,
+            from,
+            to,
+        }];
+
+        if let Some(powerup) = self.level.powerups.remove(&to) {
+            mage.powerup = Some(powerup);
+
+            events.push(GameEvent::PickedUp {
+                mage: mage.index,
+                at: to,
+                powerup,
+            });
+        }
+
+        events
+    }
+
+    /// Regenerates the derived state after a [`Turn`], and announces the end of the game.
+    fn finish_turn(&mut self, events: &mut Vec<GameEvent>) {
+        self.available_turns = self.generate_available_turns();
+        self.shielded_positions = self.generate_shielded_positions();
+        self.position_hashes.push(self.position_hash());
+
+        if let Some(result) = self.result() {
+            events.push(GameEvent::GameEnded {
+                winner: match result {
+                    GameResult::Win(team) => Some(team),
+                    GameResult::Stalemate => None,
+                },
+            });
+        }
     }
 
     /// Executes an attack on the given [`Position`].
     /// A mage must already be present on the tile.
-    pub fn attack(&mut self, at: Position) -> Vec<Position> {
-        let mut hits = Vec::new();
+    pub fn attack(&mut self, at: Position) -> Vec<GameEvent> {
+        match self.level.mages.live_occupant(&at).map(|mage| mage.index) {
+            Some(index) => {
+                let attacks = self.attacks_of(index);
 
-        if let Some(active_mage) = self.level.mages.live_occupant(&at) {
-            let targets = self.targets(active_mage, at);
+                let mut events: Vec<GameEvent> = self.discharge_beam(index).into_iter().collect();
+                events.append(&mut self.deal_damage(attacks));
 
-            for (is_enemy, tile) in targets {
-                if is_enemy {
-                    self.level.mages.live_occupant_mut(&tile).unwrap().mana -= 1;
-                    hits.push(tile);
-                }
+                events
             }
+            None => Vec::new(),
         }
+    }
+
+    /// Returns the attacks a [`Mage`] makes from its [`Position`], as pairs of attacking and attacked indices.
+    fn attacks_of(&self, index: usize) -> Vec<(usize, usize)> {
+        let mage = self.get_mage(index).unwrap();
+
+        self.targets(mage, mage.position)
+            .into_iter()
+            .filter(|(is_enemy, _)| *is_enemy)
+            .filter_map(|(_, tile)| self.level.mages.live_occupant(&tile))
+            .map(|target| (index, target.index))
+            .collect()
+    }
+
+    /// Removes a [`PowerUp::Beam`] after use.
+    fn discharge_beam(&mut self, index: usize) -> Option<GameEvent> {
+        let mage = self.get_mage_mut(index).unwrap();
 
-        if let Some(active_mage) = self.level.mages.live_occupant_mut(&at) {
-            if active_mage.powerup == Some(PowerUp::Beam) {
-                // Remove beam after use
-                active_mage.powerup = None;
+        if mage.powerup == Some(PowerUp::Beam) {
+            mage.powerup = None;
+
+            Some(GameEvent::BeamFired {
+                mage: index,
+                at: mage.position,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Deals the damage of attacks given as pairs of attacking and attacked indices.
+    fn deal_damage(&mut self, attacks: Vec<(usize, usize)>) -> Vec<GameEvent> {
+        let mut events = Vec::with_capacity(attacks.len());
+
+        for (attacker, target) in attacks {
+            let mage = self.get_mage_mut(target).unwrap();
+            let at = mage.position;
+            let was_alive = mage.is_alive();
+
+            mage.mana -= 1;
+
+            if attacker == target {
+                events.push(GameEvent::ShieldBlocked { mage: attacker, at });
+            }
+
+            events.push(GameEvent::Hit {
+                mage: target,
+                at,
+                damage: 1,
+            });
+
+            if was_alive && !mage.is_alive() {
+                events.push(GameEvent::MageFell { mage: target, at });
             }
         }
 
-        hits
+        events
     }
 
     /// Returns the list of targets a [`Mage`] can attack to on a certain [`Position`].
@@ -602,17 +945,108 @@ impl Game {
 
     /// Rewinds the [`Game`] by `delta` turns.
     /// Works via replicating the game from the initial [`Level`] with its [`Turn`] history.
+    /// An agreed draw or a forfeit came after the last turn, so it is kept only if no turns are rewound.
     pub fn rewind(&self, delta: usize) -> Game {
         let mut rewinded_game = Game::new(&self.level_prototype, self.can_stalemate).unwrap();
+        rewinded_game.simultaneous = self.simultaneous;
         let turn_toward = self.turns().saturating_sub(delta);
 
         for Turn(from, to) in self.turns.iter().take(turn_toward) {
             rewinded_game.take_move(*from, *to);
         }
 
+        if turn_toward == self.turns() {
+            rewinded_game.drawn = self.drawn;
+            rewinded_game.forfeited = self.forfeited;
+        }
+
         rewinded_game
     }
 
+    /// Returns the set of [`Position`]s a [`Team`] can see under fog of war.
+    /// Every live mage reveals its own tile, the tiles around it, and the tiles its spell reaches.
+    pub fn visible_positions(&self, team: Team) -> HashSet<Position> {
+        let mut visible = HashSet::new();
+
+        for mage in self
+            .iter_mages()
+            .filter(|mage| mage.is_alive() && mage.team == team)
+        {
+            visible.insert(mage.position);
+
+            for (dir, _) in MOVE_DIRECTIONS {
+                if let Some(position) = self.level.board.validate_position(&mage.position + &dir) {
+                    visible.insert(position);
+                }
+            }
+
+            visible.extend(mage.targets(&self.level.board, &mage.position));
+        }
+
+        visible
+    }
+
+    /// Returns a copy of the [`Game`] as seen through fog of war by a [`Team`], or by an outsider if `None`.
+    /// Hidden mages, power-ups and the opponent's turns are stripped, so the copy cannot be used to recover them.
+    /// Finished games are returned in full.
+    pub fn fogged(&self, team: Option<Team>) -> Game {
+        if self.result().is_some() {
+            return self.clone();
+        }
+
+        let visible = team
+            .map(|team| self.visible_positions(team))
+            .unwrap_or_default();
+        let prototype_visible = team
+            .map(|team| {
+                Game::new(&self.level_prototype, self.can_stalemate)
+                    .unwrap()
+                    .visible_positions(team)
+            })
+            .unwrap_or_default();
+
+        let mut game = self.clone();
+
+        game.level
+            .mages
+            .retain(|mage| Some(mage.team) == team || visible.contains(&mage.position));
+        game.level
+            .powerups
+            .retain(|position, _| visible.contains(position));
+
+        game.level_prototype
+            .mages
+            .retain(|mage| Some(mage.team) == team || prototype_visible.contains(&mage.position));
+        game.level_prototype
+            .powerups
+            .retain(|position, _| prototype_visible.contains(position));
+
+        game.turns = self
+            .turns
+            .iter()
+            .enumerate()
+            .map(|(i, turn)| {
+                if Some(self.team_for_turn(i)) == team {
+                    *turn
+                } else {
+                    Turn::sentinel()
+                }
+            })
+            .collect();
+
+        game.position_hashes.clear();
+        game.fogged = true;
+        game.available_turns = game.generate_available_turns();
+        game.shielded_positions = game.generate_shielded_positions();
+
+        game
+    }
+
+    /// Determines if this [`Game`] is a partial view produced by [`Game::fogged`].
+    pub fn is_fogged(&self) -> bool {
+        self.fogged
+    }
+
     fn generate_shielded_positions(&self) -> HashSet<(Position, Team)> {
         HashSet::from_iter(
             self.iter_mages()
@@ -632,10 +1066,15 @@ impl Game {
             .sort_by(|a, b| a.position.1.cmp(&b.position.1));
     }
 
-    /// Returns the level prototype code.
-    pub fn prototype_code(&self) -> String {
+    /// Returns the level prototype code, or an error if the prototype does not fit in one.
+    pub fn prototype_code(&self) -> Result<String, LevelCodeError> {
         self.level_prototype.as_code()
     }
+
+    /// Returns the [`LevelMetadata`] of the level prototype.
+    pub fn metadata(&self) -> &LevelMetadata {
+        &self.level_prototype.metadata
+    }
 }
 
 impl Mages for Game {
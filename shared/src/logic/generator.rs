@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+
+use rand_chacha::{
+    rand_core::{RngCore, SeedableRng},
+    ChaCha8Rng,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Board, BoardStyle, BoulderStyle, Level, LevelIssue, Mage, MageSort, Position, PowerUp, Team,
+    DEFAULT_BOARD_SIZE,
+};
+
+/// Number of levels rolled by [`Level::generate`] before it gives up on boulders.
+const GENERATOR_ATTEMPTS: usize = 16;
+
+/// How a generated [`Level`] mirrors the red team's half onto the blue team's half.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub enum Symmetry {
+    /// Rotated by 180 degrees around the centre of the board, as in [`Position::rotate`].
+    #[default]
+    Point,
+    /// Reflected across the horizontal centre line of the board.
+    Mirror,
+    /// Both teams get the same mages, but everything is placed independently.
+    None,
+}
+
+impl Symmetry {
+    /// Returns the counterpart of a [`Position`], or [`None`] if the symmetry has none.
+    pub fn counterpart(&self, board: &Board, position: Position) -> Option<Position> {
+        match self {
+            Symmetry::Point => Some(position.rotate(board)),
+            Symmetry::Mirror => Some(Position(position.0, board.height as i8 - position.1 - 1)),
+            Symmetry::None => None,
+        }
+    }
+}
+
+/// Parameters for [`Level::generate`].
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GeneratorParams {
+    /// Width of the board.
+    pub width: usize,
+    /// Height of the board.
+    pub height: usize,
+    /// Number of mages on each team.
+    pub mages: usize,
+    /// Percentage of the free tiles covered by power-ups.
+    pub powerup_density: u8,
+    /// [`Symmetry`] between the two halves of the board.
+    pub symmetry: Symmetry,
+}
+
+impl GeneratorParams {
+    /// Number of rows at either end of the board in which mages are placed.
+    /// At least one row is always left between the two teams.
+    pub fn home_rows(&self) -> usize {
+        Board::unchecked(self.width, self.height, BoardStyle::default()).home_rows()
+    }
+
+    /// Checks that a [`Level`] can be generated with the parameters.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        Board::new(self.width, self.height)?;
+
+        if self.mages == 0 || self.mages > self.width * self.home_rows() {
+            Err("number of mages does not fit the board")
+        } else if self.powerup_density > 100 {
+            Err("power-up density is a percentage")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        Self {
+            width: DEFAULT_BOARD_SIZE.0,
+            height: DEFAULT_BOARD_SIZE.1,
+            mages: 4,
+            powerup_density: 10,
+            symmetry: Symmetry::default(),
+        }
+    }
+}
+
+impl Level {
+    /// Procedurally generates a [`Level`] from a seed, such that the same parameters and seed always yield the same level.
+    /// Both teams receive the same mages, and with [`Symmetry::Point`] or [`Symmetry::Mirror`] the board is symmetric as well.
+    /// Levels whose boulders would trap a mage or wall off tiles are rerolled, dropping the boulders if none of the tries succeed.
+    pub fn generate(params: &GeneratorParams, seed: u64) -> Result<Level, &'static str> {
+        params.validate()?;
+
+        let board = Board::new(params.width, params.height)?;
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        for _ in 0..GENERATOR_ATTEMPTS {
+            let level = generate_level(params, &board, &mut rng, true);
+
+            if is_fair(&level) {
+                return Ok(level);
+            }
+        }
+
+        Ok(generate_level(params, &board, &mut rng, false))
+    }
+}
+
+/// Checks that a generated level is playable and that every tile can be reached.
+fn is_fair(level: &Level) -> bool {
+    !level
+        .validate()
+        .iter()
+        .any(|issue| issue.is_error() || matches!(issue, LevelIssue::UnreachableTiles(_)))
+}
+
+/// Shuffles the positions with a Fisher-Yates shuffle.
+fn shuffle(positions: &mut [Position], rng: &mut ChaCha8Rng) {
+    for i in (1..positions.len()).rev() {
+        positions.swap(i, (rng.next_u64() % (i as u64 + 1)) as usize);
+    }
+}
+
+/// Returns a random [`PowerUp`], only including boulders if allowed.
+fn random_powerup(rng: &mut ChaCha8Rng, boulders: bool) -> PowerUp {
+    match rng.next_u64() % if boulders { 6 } else { 3 } {
+        0 => PowerUp::Shield,
+        1 => PowerUp::Beam,
+        2 => PowerUp::Diagonal,
+        3 => PowerUp::Boulder(BoulderStyle::Rock),
+        4 => PowerUp::Boulder(BoulderStyle::Pedestal),
+        _ => PowerUp::Boulder(BoulderStyle::Tentacle),
+    }
+}
+
+/// Rolls a single level, without checking whether it is fair.
+fn generate_level(
+    params: &GeneratorParams,
+    board: &Board,
+    rng: &mut ChaCha8Rng,
+    boulders: bool,
+) -> Level {
+    let home_rows = params.home_rows() as i8;
+    let (width, height) = (board.width as i8, board.height as i8);
+
+    let tiles = |rows: std::ops::Range<i8>| {
+        rows.flat_map(move |y| (0..width).map(move |x| Position(x, y)))
+            .collect::<Vec<Position>>()
+    };
+
+    let sorts: Vec<MageSort> = (0..params.mages)
+        .map(|_| ((rng.next_u64() % 4) as usize).into())
+        .collect();
+
+    // Red's home rows are at the bottom of the board, as with `Board::place_mages`.
+    let mut red_tiles = tiles(height - home_rows..height);
+    shuffle(&mut red_tiles, rng);
+
+    let mut mages: Vec<Mage> = sorts
+        .iter()
+        .zip(&red_tiles)
+        .map(|(sort, position)| Mage::new(0, Team::Red, *sort, *position))
+        .collect();
+
+    if params.symmetry == Symmetry::None {
+        let mut blue_tiles = tiles(0..home_rows);
+        shuffle(&mut blue_tiles, rng);
+
+        mages.extend(
+            sorts
+                .iter()
+                .zip(&blue_tiles)
+                .map(|(sort, position)| Mage::new(0, Team::Blue, *sort, *position)),
+        );
+    } else {
+        let blue_mages = mages
+            .iter()
+            .filter_map(|mage| {
+                params
+                    .symmetry
+                    .counterpart(board, mage.position)
+                    .map(|position| Mage::new(0, Team::Blue, mage.sort, position))
+            })
+            .collect::<Vec<Mage>>();
+
+        mages.extend(blue_mages);
+    }
+
+    // Power-ups are rolled for red's half of the board, including any tiles on the line of symmetry, and then mirrored.
+    let mut candidates: Vec<Position> = tiles(0..height)
+        .into_iter()
+        .filter(|position| !mages.iter().any(|mage| mage.position == *position))
+        .filter(
+            |position| match params.symmetry.counterpart(board, *position) {
+                Some(counterpart) => (position.1, position.0) >= (counterpart.1, counterpart.0),
+                None => true,
+            },
+        )
+        .collect();
+    shuffle(&mut candidates, rng);
+
+    let count = candidates.len() * params.powerup_density as usize / 100;
+
+    let mut powerups = BTreeMap::new();
+
+    for position in candidates.into_iter().take(count) {
+        let powerup = random_powerup(rng, boulders);
+
+        powerups.insert(position, powerup);
+
+        if let Some(counterpart) = params.symmetry.counterpart(board, position) {
+            powerups.insert(counterpart, powerup);
+        }
+    }
+
+    Level::new(board.clone(), mages, powerups, Team::Red)
+}
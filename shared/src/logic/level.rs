@@ -1,12 +1,12 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, fmt::Display};
 
 use data_encoding::Encoding;
 use data_encoding_macro::new_encoding;
-use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    vecmap, Board, Game, Mage, Mages, Position, PowerUp, PowerUpEntry, Team, Turn, TurnLeaf,
+    vecmap, Board, Game, LevelMetadata, Mage, Mages, Position, PowerUp, PowerUpEntry, Team, Turn,
+    TurnLeaf, MAX_MANA,
 };
 
 /// Base 32 (Crockford) encoding for levels.
@@ -14,6 +14,91 @@ pub const BASE32: Encoding = new_encoding! {
     symbols: "0123456789abcdefghjkmnpqrstvwxyz",
 };
 
+/// Version of the checksummed level code format with the unversioned layout.
+const LEVEL_CODE_VERSION: u8 = 1;
+
+/// Version of the level code format written by [`Level::as_code`], which encodes the board style,
+/// board sizes up to [`crate::MAX_BOARD_SIZE`], and positions in a full byte.
+const LEVEL_CODE_EXTENDED_VERSION: u8 = 2;
+
+/// Version of the level code format written by [`Level::as_code`] for levels with [`LevelMetadata`],
+/// which precedes the layout of [`LEVEL_CODE_EXTENDED_VERSION`] with the metadata.
+const LEVEL_CODE_METADATA_VERSION: u8 = 3;
+
+/// Unversioned level codes start with the board byte, whose width bits are never below `0b010`.
+/// Header bytes below this value are therefore free to mark versioned codes.
+const LEVEL_CODE_HEADER_LIMIT: u8 = 0b0100_0000;
+
+/// Errors in decoding a level code with [`Level::from_code`], or in encoding a level that the layout cannot represent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LevelCodeError {
+    /// The code contains characters outside of [`BASE32`].
+    InvalidEncoding,
+    /// The code ends before the level does.
+    Truncated,
+    /// The code continues after the level ends.
+    TrailingBytes,
+    /// The code was written by an unknown version of the format.
+    UnsupportedVersion(u8),
+    /// The checksum does not match the contents, most likely due to a typo.
+    ChecksumMismatch,
+    /// The board size does not conform to the limits of [`Board::new`].
+    InvalidBoard,
+    /// A mage or power-up lies outside of the board.
+    OutOfBounds,
+    /// The [`LevelMetadata`] is malformed.
+    InvalidMetadata,
+    /// The board, the positions or the number of pieces exceed what the layout can represent.
+    DoesNotFit,
+}
+
+impl Display for LevelCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LevelCodeError::InvalidEncoding => f.write_str("invalid characters in level code"),
+            LevelCodeError::Truncated => f.write_str("level code is too short"),
+            LevelCodeError::TrailingBytes => f.write_str("level code is too long"),
+            LevelCodeError::UnsupportedVersion(version) => {
+                write!(f, "unsupported level code version {version}")
+            }
+            LevelCodeError::ChecksumMismatch => f.write_str("level code checksum does not match"),
+            LevelCodeError::InvalidBoard => f.write_str("invalid board size in level code"),
+            LevelCodeError::OutOfBounds => f.write_str("level code places pieces off the board"),
+            LevelCodeError::InvalidMetadata => f.write_str("invalid metadata in level code"),
+            LevelCodeError::DoesNotFit => f.write_str("level does not fit in a level code"),
+        }
+    }
+}
+
+/// Computes the [Fletcher-16](https://en.wikipedia.org/wiki/Fletcher%27s_checksum) checksum of the given bytes.
+fn checksum(bytes: &[u8]) -> [u8; 2] {
+    let (sum, check) = bytes.iter().fold((0u16, 0u16), |(sum, check), byte| {
+        let sum = (sum + *byte as u16) % 255;
+        (sum, (check + sum) % 255)
+    });
+
+    [check as u8, sum as u8]
+}
+
+/// Packs a [`Position`] on a board of up to 16-by-16 into a single byte.
+fn encode_position(position: Position) -> u8 {
+    ((position.0 as u8 & 0b1111) << 4) | (position.1 as u8 & 0b1111)
+}
+
+/// Unpacks a [`Position`] packed by [`encode_position`].
+fn decode_position(byte: u8) -> Position {
+    Position((byte >> 4) as i8, (byte & 0b1111) as i8)
+}
+
+/// Confirms that the remaining bytes of a level code are exactly `length` long.
+fn check_length(value: &[u8], length: usize) -> Result<(), LevelCodeError> {
+    match value.len().cmp(&length) {
+        std::cmp::Ordering::Less => Err(LevelCodeError::Truncated),
+        std::cmp::Ordering::Greater => Err(LevelCodeError::TrailingBytes),
+        std::cmp::Ordering::Equal => Ok(()),
+    }
+}
+
 /// [`Level`] is the builder for a [`Game`] instance.
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Level {
@@ -28,6 +113,9 @@ pub struct Level {
     pub powerups: BTreeMap<Position, PowerUp>,
     /// Level's starting [`Team`].
     pub starting_team: Team,
+    /// Level's descriptive [`LevelMetadata`].
+    #[serde(default)]
+    pub metadata: LevelMetadata,
 }
 
 impl Level {
@@ -53,9 +141,16 @@ impl Level {
             mages,
             powerups,
             starting_team,
+            metadata: LevelMetadata::default(),
         }
     }
 
+    /// Attaches [`LevelMetadata`] to the level.
+    pub fn with_metadata(mut self, metadata: LevelMetadata) -> Level {
+        self.metadata = metadata;
+        self
+    }
+
     /// Instantiates a new [`Level`] with default parameters but provided mages.
     pub fn default_with_mages(mages: Vec<Mage>) -> Level {
         Level::new(
@@ -99,14 +194,194 @@ impl Level {
     }
 
     /// Converts the level to a Base 32 code string.
-    pub fn as_code(&self) -> String {
-        let encoded_level: Vec<u8> = self.into();
-        BASE32.encode(encoded_level.as_slice())
+    /// The code starts with a version header and ends with a checksum.
+    /// Levels without [`LevelMetadata`] omit it, keeping their codes unchanged.
+    pub fn as_code(&self) -> Result<String, LevelCodeError> {
+        self.check_fits(16)?;
+
+        let mut encoded_level = if self.metadata.is_empty() {
+            vec![LEVEL_CODE_EXTENDED_VERSION]
+        } else {
+            let mut encoded_level = vec![LEVEL_CODE_METADATA_VERSION];
+            encoded_level.append(&mut self.metadata.as_bytes());
+            encoded_level
+        };
+        encoded_level.append(&mut self.as_extended_bytes());
+        encoded_level.extend(checksum(&encoded_level));
+
+        Ok(BASE32.encode(encoded_level.as_slice()))
+    }
+
+    /// Confirms that the board and all pieces lie within `size` tiles of the origin,
+    /// that the numbers of mages and power-ups fit in a byte, and that all mana fits in four bits.
+    /// Larger levels would be truncated into a valid code for a different level.
+    fn check_fits(&self, size: usize) -> Result<(), LevelCodeError> {
+        let fits = |position: Position| {
+            (0..size as i8).contains(&position.0) && (0..size as i8).contains(&position.1)
+        };
+
+        if self.board.width <= size
+            && self.board.height <= size
+            && self.mages.len() <= u8::MAX as usize
+            && self.powerups.len() <= u8::MAX as usize
+            && self.mages.iter().all(|mage| fits(mage.position))
+            && self
+                .mages
+                .iter()
+                .all(|mage| mage.mana.0 <= MAX_MANA && mage.mana.1 <= MAX_MANA)
+            && self.powerups.keys().all(|position| fits(*position))
+        {
+            Ok(())
+        } else {
+            Err(LevelCodeError::DoesNotFit)
+        }
+    }
+
+    /// Encodes the level in the layout of [`LEVEL_CODE_EXTENDED_VERSION`], without header or checksum.
+    /// The level must pass [`Level::check_fits`] for boards of up to 16-by-16.
+    fn as_extended_bytes(&self) -> Vec<u8> {
+        let mut result = vec![
+            ((self.board.width as u8 - 1) << 4) | (self.board.height as u8 - 1),
+            (u8::from(&self.board.style) << 2) | (self.starting_team as u8 & 0b11),
+            self.mages.len() as u8,
+        ];
+
+        for mage in &self.mages {
+            result.extend([
+                encode_position(mage.position),
+                mage.team as u8,
+                mage.sort as u8,
+                (&mage.mana).into(),
+            ]);
+        }
+
+        result.push(self.powerups.len() as u8);
+
+        for (position, powerup) in &self.powerups {
+            result.extend([encode_position(*position), u8::from(*powerup)]);
+        }
+
+        result
+    }
+
+    /// Decodes a level from the layout of [`LEVEL_CODE_EXTENDED_VERSION`].
+    fn from_extended_bytes(value: &[u8]) -> Result<Level, LevelCodeError> {
+        let [size_byte, style_byte, num_mages, value @ ..] = value else {
+            return Err(LevelCodeError::Truncated);
+        };
+
+        let board = Board::with_style(
+            (size_byte >> 4) as usize + 1,
+            (size_byte & 0b1111) as usize + 1,
+            (style_byte >> 2).into(),
+        )
+        .map_err(|_| LevelCodeError::InvalidBoard)?;
+
+        let starting_team = Team::from_index((style_byte & 0b11) as usize);
+
+        let mages_length = *num_mages as usize * 4;
+
+        if value.len() < mages_length {
+            return Err(LevelCodeError::Truncated);
+        }
+
+        let (mage_bytes, value) = value.split_at(mages_length);
+        let mages: Vec<Mage> = mage_bytes
+            .chunks(4)
+            .map(|chunk| {
+                let mut mage = Mage::new(
+                    0,
+                    Team::from_index(chunk[1] as usize),
+                    (chunk[2] as usize).into(),
+                    decode_position(chunk[0]),
+                );
+                mage.mana = chunk[3].into();
+                mage
+            })
+            .collect();
+
+        let (&num_props, value) = value.split_first().ok_or(LevelCodeError::Truncated)?;
+        check_length(value, num_props as usize * 2)?;
+
+        let powerups: BTreeMap<Position, PowerUp> = value
+            .chunks(2)
+            .map(|chunk| PowerUpEntry(decode_position(chunk[0]), chunk[1].into()))
+            .collect();
+
+        Level::checked(board, mages, powerups, starting_team)
+    }
+
+    /// Instantiates a new [`Level`] if all of its pieces reside on the board.
+    fn checked(
+        board: Board,
+        mages: Vec<Mage>,
+        powerups: BTreeMap<Position, PowerUp>,
+        starting_team: Team,
+    ) -> Result<Level, LevelCodeError> {
+        let in_bounds = mages
+            .iter()
+            .map(|mage| mage.position)
+            .chain(powerups.keys().copied())
+            .all(|position| board.validate_position(position).is_some());
+
+        if in_bounds {
+            Ok(Level::new(board, mages, powerups, starting_team))
+        } else {
+            Err(LevelCodeError::OutOfBounds)
+        }
+    }
+
+    /// Converts the level to the unversioned Base 32 code string that preceded [`Level::as_code`].
+    /// Results recorded under old codes can be looked up with it.
+    /// The legacy layout cannot represent board styles, and fails on boards larger than 8-by-8.
+    pub fn as_legacy_code(&self) -> Result<String, LevelCodeError> {
+        let encoded_level = Vec::<u8>::try_from(self)?;
+        Ok(BASE32.encode(encoded_level.as_slice()))
+    }
+
+    /// Decodes a level from a Base 32 code string, accepting both versioned and unversioned codes.
+    pub fn from_code(code: &str) -> Result<Level, LevelCodeError> {
+        let bytes = BASE32
+            .decode(code.trim().to_lowercase().as_bytes())
+            .map_err(|_| LevelCodeError::InvalidEncoding)?;
+
+        match bytes.first() {
+            None => Err(LevelCodeError::Truncated),
+            Some(&header) if header >= LEVEL_CODE_HEADER_LIMIT => Level::try_from(bytes.as_slice()),
+            Some(&version @ LEVEL_CODE_VERSION..=LEVEL_CODE_METADATA_VERSION) => {
+                if bytes.len() < 3 {
+                    return Err(LevelCodeError::Truncated);
+                }
+
+                let (body, sum) = bytes.split_at(bytes.len() - 2);
+
+                if checksum(body) != sum {
+                    return Err(LevelCodeError::ChecksumMismatch);
+                }
+
+                match version {
+                    LEVEL_CODE_VERSION => Level::try_from(&body[1..]),
+                    LEVEL_CODE_EXTENDED_VERSION => Level::from_extended_bytes(&body[1..]),
+                    _ => {
+                        let (metadata, body) = LevelMetadata::from_bytes(&body[1..])
+                            .ok_or(LevelCodeError::InvalidMetadata)?;
+
+                        Ok(Level::from_extended_bytes(body)?.with_metadata(metadata))
+                    }
+                }
+            }
+            Some(&version) => Err(LevelCodeError::UnsupportedVersion(version)),
+        }
     }
 }
 
-impl From<&Level> for Vec<u8> {
-    fn from(level: &Level) -> Self {
+impl TryFrom<&Level> for Vec<u8> {
+    type Error = LevelCodeError;
+
+    /// Encodes a level in the unversioned layout, failing if it does not fit in 8-by-8 tiles.
+    fn try_from(level: &Level) -> Result<Self, Self::Error> {
+        level.check_fits(8)?;
+
         let board_width = level.board.width as u8 - 1;
         let board_height = level.board.height as u8 - 1;
 
@@ -133,54 +408,53 @@ impl From<&Level> for Vec<u8> {
             result.append(&mut prop_bytes);
         }
 
-        result
+        Ok(result)
     }
 }
 
-impl From<Vec<u8>> for Level {
-    fn from(value: Vec<u8>) -> Self {
-        let board_byte = value[0];
+impl TryFrom<&[u8]> for Level {
+    type Error = LevelCodeError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (&board_byte, value) = value.split_first().ok_or(LevelCodeError::Truncated)?;
 
         let board_width = ((board_byte >> 5) & 0b111) + 1;
         let board_height = ((board_byte >> 2) & 0b111) + 1;
 
-        let board = Board::new(board_width.into(), board_height.into()).unwrap();
+        let board = Board::new(board_width.into(), board_height.into())
+            .map_err(|_| LevelCodeError::InvalidBoard)?;
 
         let starting_team = Team::from_index((board_byte & 0b11) as usize);
 
-        let num_mages = value[1];
-        let mages: Vec<Mage> = value
-            .iter()
-            .skip(2)
-            .take(num_mages as usize * 3)
+        let (&num_mages, value) = value.split_first().ok_or(LevelCodeError::Truncated)?;
+        let mages_length = num_mages as usize * 3;
+
+        if value.len() < mages_length {
+            return Err(LevelCodeError::Truncated);
+        }
+
+        let (mage_bytes, value) = value.split_at(mages_length);
+        let mages: Vec<Mage> = mage_bytes
             .chunks(3)
-            .into_iter()
-            .map(|chunk| chunk.cloned().collect::<Vec<u8>>().into())
+            .map(|chunk| chunk.to_vec().into())
             .collect();
 
-        let num_props = value[2 + num_mages as usize * 3];
-        let powerup_entries: Vec<PowerUpEntry> = value
-            .iter()
-            .skip(3 + num_mages as usize * 3)
-            .take(num_props as usize * 2)
+        let (&num_props, value) = value.split_first().ok_or(LevelCodeError::Truncated)?;
+        check_length(value, num_props as usize * 2)?;
+
+        let powerups: BTreeMap<Position, PowerUp> = value
             .chunks(2)
-            .into_iter()
-            .map(|chunk| chunk.cloned().collect::<Vec<u8>>().into())
+            .map(|chunk| PowerUpEntry::from(chunk.to_vec()))
             .collect();
 
-        let powerups: BTreeMap<Position, PowerUp> = powerup_entries.iter().cloned().collect();
-
-        Level::new(board, mages, powerups, starting_team)
+        Level::checked(board, mages, powerups, starting_team)
     }
 }
 
 impl From<&str> for Level {
+    /// Decodes a level with [`Level::from_code`], falling back to [`Level::default`] on invalid codes.
     fn from(value: &str) -> Self {
-        if let Ok(decoded) = BASE32.decode(value.as_bytes()) {
-            decoded.into()
-        } else {
-            Level::default()
-        }
+        Level::from_code(value).unwrap_or_default()
     }
 }
 
@@ -203,6 +477,7 @@ impl Clone for Level {
             mage_index: self.mage_index,
             powerups: self.powerups.clone(),
             starting_team: self.starting_team,
+            metadata: self.metadata.clone(),
         }
     }
 }
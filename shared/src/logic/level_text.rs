@@ -0,0 +1,408 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{
+    Board, BoardStyle, BoulderStyle, Difficulty, Level, LevelMetadata, Mage, MageSort, Mana,
+    Position, PowerUp, Team,
+};
+
+/// Names of the [`Team`]s in the level text format.
+const TEAM_NAMES: [(&str, Team); 2] = [("red", Team::Red), ("blue", Team::Blue)];
+
+/// Names of the [`BoardStyle`]s in the level text format.
+const STYLE_NAMES: [(&str, BoardStyle); 6] = [
+    ("grass", BoardStyle::Grass),
+    ("teleport", BoardStyle::Teleport),
+    ("desert", BoardStyle::Desert),
+    ("flesh", BoardStyle::Flesh),
+    ("crust", BoardStyle::Crust),
+    ("eldritch", BoardStyle::Eldritch),
+];
+
+/// Names of the [`MageSort`]s in the level text format.
+const SORT_NAMES: [(&str, MageSort); 5] = [
+    ("diamond", MageSort::Diamond),
+    ("cross", MageSort::Cross),
+    ("knight", MageSort::Knight),
+    ("spike", MageSort::Spike),
+    ("plus", MageSort::Plus),
+];
+
+/// Names of the [`PowerUp`]s in the level text format.
+const POWERUP_NAMES: [(&str, PowerUp); 6] = [
+    ("shield", PowerUp::Shield),
+    ("beam", PowerUp::Beam),
+    ("diagonal", PowerUp::Diagonal),
+    ("rock", PowerUp::Boulder(BoulderStyle::Rock)),
+    ("pedestal", PowerUp::Boulder(BoulderStyle::Pedestal)),
+    ("tentacle", PowerUp::Boulder(BoulderStyle::Tentacle)),
+];
+
+/// Grid cells of the boulders, which are drawn on the board rather than listed in the header.
+const BOULDER_CELLS: [(&str, BoulderStyle); 3] = [
+    ("#", BoulderStyle::Rock),
+    ("#p", BoulderStyle::Pedestal),
+    ("#t", BoulderStyle::Tentacle),
+];
+
+/// Names of the [`Difficulty`] levels in the level text format.
+const DIFFICULTY_NAMES: [(&str, Difficulty); 3] = [
+    ("easy", Difficulty::Easy),
+    ("normal", Difficulty::Normal),
+    ("hard", Difficulty::Hard),
+];
+
+/// Looks up the name of a value in one of the name tables.
+fn name_of<T: PartialEq>(names: &[(&'static str, T)], value: &T) -> &'static str {
+    names
+        .iter()
+        .find(|(_, named)| named == value)
+        .map(|(name, _)| *name)
+        .unwrap_or_default()
+}
+
+/// Looks up the value of a name in one of the name tables, ignoring case.
+fn value_of<T: Clone>(names: &[(&str, T)], name: &str) -> Option<T> {
+    names
+        .iter()
+        .find(|(named, _)| named.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.clone())
+}
+
+/// Escapes line breaks and line separators so that a metadata text fits on a single header line.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('|', "\\|")
+}
+
+/// Reverts [`escape`].
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                result.push('\n');
+                chars.next();
+            }
+            ('\\', Some(escaped @ ('\\' | '|'))) => {
+                result.push(escaped);
+                chars.next();
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+/// Splits a level text into lines, at line breaks as well as at `|` separators that are not escaped.
+fn split_lines(text: &str) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' | '|' => lines.push(String::new()),
+            '\\' => {
+                let line = lines.last_mut().unwrap();
+                line.push(c);
+                line.extend(chars.next());
+            }
+            _ => lines.last_mut().unwrap().push(c),
+        }
+    }
+
+    lines
+}
+
+/// Errors in parsing a level text with [`Level::from_text`].
+/// Line numbers start at 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LevelTextError {
+    /// A header line has an unknown key or a malformed value.
+    InvalidHeader(usize),
+    /// A grid cell is neither empty, a boulder, nor a mage.
+    InvalidCell(usize),
+    /// A grid row differs in width from the first row.
+    RaggedRow(usize),
+    /// The grid size does not conform to the limits of [`Board::new`].
+    InvalidBoard,
+    /// A power-up lies outside of the board.
+    OutOfBounds,
+    /// A mage number appears more than once in the grid or the header.
+    DuplicateMage(usize),
+    /// A mage number is missing from the grid or the header, or the numbers are not consecutive from 1.
+    MissingMage(usize),
+}
+
+impl Display for LevelTextError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LevelTextError::InvalidHeader(line) => write!(f, "invalid header on line {line}"),
+            LevelTextError::InvalidCell(line) => write!(f, "invalid cell on line {line}"),
+            LevelTextError::RaggedRow(line) => write!(f, "row on line {line} has the wrong width"),
+            LevelTextError::InvalidBoard => f.write_str("invalid board size in level text"),
+            LevelTextError::OutOfBounds => f.write_str("level text places pieces off the board"),
+            LevelTextError::DuplicateMage(number) => write!(f, "mage {number} appears twice"),
+            LevelTextError::MissingMage(number) => write!(f, "mage {number} is missing"),
+        }
+    }
+}
+
+impl Level {
+    /// Converts the level to a human-readable text, which [`Level::from_text`] reads back.
+    ///
+    /// The text starts with a header of `key: value` lines, followed by a blank line and the board as a grid of cells.
+    /// A cell is `.` when empty, `#`, `#p` or `#t` for a rock, pedestal or tentacle boulder, or the team's initial and
+    /// the mage's number, such as `R1` or `B2`. Mages are numbered in the order of [`Level::mages`], which keeps level
+    /// codes stable across a round trip, and every number has a `mage N: sort` header line, followed by the mana when
+    /// it differs from the default. The header also lists the starting team, the board style, the other power-ups as
+    /// `powerup: x y name`, and the [`LevelMetadata`].
+    pub fn as_text(&self) -> String {
+        let mut lines = vec![
+            format!("team: {}", name_of(&TEAM_NAMES, &self.starting_team)),
+            format!("style: {}", name_of(&STYLE_NAMES, &self.board.style)),
+        ];
+
+        let metadata = &self.metadata;
+
+        for (key, text) in [
+            ("title", &metadata.title),
+            ("author", &metadata.author),
+            ("description", &metadata.description),
+        ] {
+            if let Some(text) = text {
+                lines.push(format!("{key}: {}", escape(text)));
+            }
+        }
+
+        if let Some(difficulty) = &metadata.difficulty {
+            lines.push(format!(
+                "difficulty: {}",
+                name_of(&DIFFICULTY_NAMES, difficulty)
+            ));
+        }
+
+        if let Some(par) = metadata.par {
+            lines.push(format!("par: {par}"));
+        }
+
+        for (number, mage) in self.mages.iter().enumerate() {
+            let mut line = format!("mage {}: {}", number + 1, name_of(&SORT_NAMES, &mage.sort));
+            let Mana(current, max) = Mana::select(mage.sort);
+
+            if (mage.mana.0, mage.mana.1) != (current, max) {
+                line += &format!(" {}/{}", mage.mana.0, mage.mana.1);
+            }
+
+            lines.push(line);
+        }
+
+        let mut cells = vec![vec![String::from("."); self.board.width]; self.board.height];
+
+        for (number, mage) in self.mages.iter().enumerate() {
+            let initial = match mage.team {
+                Team::Red => 'R',
+                Team::Blue => 'B',
+            };
+
+            cells[mage.position.1 as usize][mage.position.0 as usize] =
+                format!("{initial}{}", number + 1);
+        }
+
+        for (position, powerup) in &self.powerups {
+            let cell = &mut cells[position.1 as usize][position.0 as usize];
+
+            match powerup {
+                PowerUp::Boulder(style) if cell == "." => {
+                    *cell = name_of(&BOULDER_CELLS, style).to_string();
+                }
+                _ => lines.push(format!(
+                    "powerup: {} {} {}",
+                    position.0,
+                    position.1,
+                    name_of(&POWERUP_NAMES, powerup)
+                )),
+            }
+        }
+
+        let width = cells.iter().flatten().map(String::len).max().unwrap_or(1);
+
+        lines.push(String::new());
+        lines.extend(cells.iter().map(|row| {
+            row.iter()
+                .map(|cell| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+                .trim_end()
+                .to_string()
+        }));
+
+        lines.join("\n") + "\n"
+    }
+
+    /// Converts the level to the text of [`Level::as_text`] on a single line, with the lines separated by `|`.
+    /// Used where only single-line input is available, such as the text input of the editor.
+    pub fn as_text_line(&self) -> String {
+        self.as_text().trim_end().replace('\n', " | ")
+    }
+
+    /// Parses a level from the text format written by [`Level::as_text`] or [`Level::as_text_line`].
+    /// Header lines may appear in any order, and all but the `mage` lines are optional.
+    pub fn from_text(text: &str) -> Result<Level, LevelTextError> {
+        let mut starting_team = Team::default();
+        let mut style = BoardStyle::default();
+        let mut metadata = LevelMetadata::default();
+        let mut sorts: BTreeMap<usize, (MageSort, Option<Mana>)> = BTreeMap::new();
+        let mut listed_powerups: Vec<(usize, Position, PowerUp)> = Vec::new();
+        let mut rows: Vec<(usize, Vec<&str>)> = Vec::new();
+
+        let lines = split_lines(text);
+
+        for (index, line) in lines.iter().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once(':') else {
+                rows.push((line_number, line.split_whitespace().collect()));
+                continue;
+            };
+
+            let invalid = LevelTextError::InvalidHeader(line_number);
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "team" => starting_team = value_of(&TEAM_NAMES, value).ok_or(invalid)?,
+                "style" => style = value_of(&STYLE_NAMES, value).ok_or(invalid)?,
+                "title" => metadata.title = Some(unescape(value)),
+                "author" => metadata.author = Some(unescape(value)),
+                "description" => metadata.description = Some(unescape(value)),
+                "difficulty" => {
+                    metadata.difficulty = Some(value_of(&DIFFICULTY_NAMES, value).ok_or(invalid)?)
+                }
+                "par" => metadata.par = Some(value.parse().map_err(|_| invalid)?),
+                "powerup" => {
+                    let [x, y, name] = value.split_whitespace().collect::<Vec<_>>()[..] else {
+                        return Err(invalid);
+                    };
+
+                    let position = Position(
+                        x.parse().map_err(|_| invalid)?,
+                        y.parse().map_err(|_| invalid)?,
+                    );
+
+                    listed_powerups.push((
+                        line_number,
+                        position,
+                        value_of(&POWERUP_NAMES, name).ok_or(invalid)?,
+                    ));
+                }
+                _ => {
+                    let number: usize = key
+                        .strip_prefix("mage")
+                        .and_then(|number| number.trim().parse().ok())
+                        .ok_or(invalid)?;
+
+                    let (sort, mana) = match value.split_whitespace().collect::<Vec<_>>()[..] {
+                        [sort] => (sort, None),
+                        [sort, mana] => (sort, Some(mana)),
+                        _ => return Err(invalid),
+                    };
+
+                    let sort = value_of(&SORT_NAMES, sort).ok_or(invalid)?;
+                    let mana = match mana {
+                        Some(mana) => {
+                            let (current, max) = mana.split_once('/').ok_or(invalid)?;
+                            let mana = Mana(
+                                current.parse().map_err(|_| invalid)?,
+                                max.parse().map_err(|_| invalid)?,
+                            );
+
+                            if mana.0 > 0b1111 || mana.1 > 0b1111 {
+                                return Err(invalid);
+                            }
+
+                            Some(mana)
+                        }
+                        None => None,
+                    };
+
+                    if sorts.insert(number, (sort, mana)).is_some() {
+                        return Err(LevelTextError::DuplicateMage(number));
+                    }
+                }
+            }
+        }
+
+        let width = rows.first().map(|(_, cells)| cells.len()).unwrap_or(0);
+
+        if let Some((line_number, _)) = rows.iter().find(|(_, cells)| cells.len() != width) {
+            return Err(LevelTextError::RaggedRow(*line_number));
+        }
+
+        let board = Board::with_style(width, rows.len(), style)
+            .map_err(|_| LevelTextError::InvalidBoard)?;
+
+        let mut placed: BTreeMap<usize, (Team, Position)> = BTreeMap::new();
+        let mut powerups = BTreeMap::new();
+
+        for (y, (line_number, cells)) in rows.iter().enumerate() {
+            for (x, cell) in cells.iter().enumerate() {
+                let position = Position(x as i8, y as i8);
+
+                if *cell == "." {
+                    continue;
+                } else if let Some(style) = value_of(&BOULDER_CELLS, cell) {
+                    powerups.insert(position, PowerUp::Boulder(style));
+                    continue;
+                }
+
+                let invalid = LevelTextError::InvalidCell(*line_number);
+                let mut chars = cell.chars();
+                let team = match chars.next().map(|c| c.to_ascii_uppercase()) {
+                    Some('R') => Team::Red,
+                    Some('B') => Team::Blue,
+                    _ => return Err(invalid),
+                };
+                let number: usize = chars.as_str().parse().map_err(|_| invalid)?;
+
+                if placed.insert(number, (team, position)).is_some() {
+                    return Err(LevelTextError::DuplicateMage(number));
+                }
+            }
+        }
+
+        for (line_number, position, powerup) in listed_powerups {
+            if board.validate_position(position).is_none() {
+                return Err(LevelTextError::OutOfBounds);
+            } else if powerups.insert(position, powerup).is_some() {
+                return Err(LevelTextError::InvalidHeader(line_number));
+            }
+        }
+
+        let mages = (1..=placed.len().max(sorts.len()))
+            .map(|number| {
+                let ((team, position), (sort, mana)) = placed
+                    .get(&number)
+                    .zip(sorts.get(&number))
+                    .ok_or(LevelTextError::MissingMage(number))?;
+
+                let mut mage = Mage::new(0, *team, *sort, *position);
+                if let Some(mana) = mana {
+                    mage.mana = mana.clone();
+                }
+
+                Ok(mage)
+            })
+            .collect::<Result<Vec<Mage>, LevelTextError>>()?;
+
+        Ok(Level::new(board, mages, powerups, starting_team).with_metadata(metadata))
+    }
+}
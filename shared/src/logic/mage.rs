@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use crate::{Board, Mana, Position, PowerUp, Spell, Team};
 
 /// A [`MageSort`] is the distinct type of the mage, determining its visual appearance and spell.
-#[derive(Debug, Serialize, Deserialize, Copy, Clone)]
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq)]
 pub enum MageSort {
     /// A simple mage who attacks with a diamond pattern.
     Diamond,
@@ -6,6 +6,9 @@ use crate::MageSort;
 
 const DEFAULT_MANA: u8 = 4;
 
+/// Most mana a mage may have, as level codes store it in four bits.
+pub const MAX_MANA: u8 = 15;
+
 /// Mana is a `struct` which contains the current mana level for a specific wizard. It stores the current and maximum values.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Mana(pub u8, pub u8);
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+
+/// Recommended strength of the AI opponent for a [`crate::Level`].
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum Difficulty {
+    /// Shallow search, suitable for learning the level.
+    Easy,
+    /// The regular strength of the AI.
+    Normal,
+    /// Deep search, for those who have mastered the level.
+    Hard,
+}
+
+impl Difficulty {
+    /// Search depth of [`crate::Game::best_turn`] matching the difficulty.
+    pub fn depth(&self) -> usize {
+        match self {
+            Difficulty::Easy => 2,
+            Difficulty::Normal => 4,
+            Difficulty::Hard => 5,
+        }
+    }
+}
+
+impl TryFrom<u8> for Difficulty {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Difficulty::Easy),
+            1 => Ok(Difficulty::Normal),
+            2 => Ok(Difficulty::Hard),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<Difficulty> for u8 {
+    fn from(value: Difficulty) -> Self {
+        match value {
+            Difficulty::Easy => 0,
+            Difficulty::Normal => 1,
+            Difficulty::Hard => 2,
+        }
+    }
+}
+
+/// Optional descriptive information about a [`crate::Level`], carried along in its code.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct LevelMetadata {
+    /// Title of the level.
+    pub title: Option<String>,
+    /// Author of the level.
+    pub author: Option<String>,
+    /// Description of the level.
+    pub description: Option<String>,
+    /// Recommended [`Difficulty`] of the AI opponent.
+    pub difficulty: Option<Difficulty>,
+    /// Number of turns in which the level is expected to be won.
+    pub par: Option<u8>,
+}
+
+impl LevelMetadata {
+    /// Maximum length in bytes of each text field when encoded in a level code.
+    pub const MAX_TEXT_LENGTH: usize = u8::MAX as usize;
+
+    /// Returns `true` if no metadata is set.
+    pub fn is_empty(&self) -> bool {
+        self == &LevelMetadata::default()
+    }
+
+    /// Encodes the metadata for a level code, starting with a byte flagging the fields that are set.
+    /// Text fields are truncated to [`LevelMetadata::MAX_TEXT_LENGTH`] bytes.
+    pub(crate) fn as_bytes(&self) -> Vec<u8> {
+        let mut flags = 0;
+        let mut result = Vec::new();
+
+        for (bit, text) in [&self.title, &self.author, &self.description]
+            .into_iter()
+            .enumerate()
+        {
+            if let Some(text) = text {
+                let mut length = text.len().min(LevelMetadata::MAX_TEXT_LENGTH);
+
+                while !text.is_char_boundary(length) {
+                    length -= 1;
+                }
+
+                flags |= 1 << bit;
+                result.push(length as u8);
+                result.extend(&text.as_bytes()[..length]);
+            }
+        }
+
+        if let Some(difficulty) = self.difficulty {
+            flags |= 1 << 3;
+            result.push(difficulty.into());
+        }
+
+        if let Some(par) = self.par {
+            flags |= 1 << 4;
+            result.push(par);
+        }
+
+        result.insert(0, flags);
+
+        result
+    }
+
+    /// Decodes metadata encoded by [`LevelMetadata::as_bytes`], returning the remaining bytes.
+    /// Returns [`None`] if the bytes are not valid metadata.
+    pub(crate) fn from_bytes(value: &[u8]) -> Option<(LevelMetadata, &[u8])> {
+        let (&flags, mut value) = value.split_first()?;
+
+        if flags >> 5 != 0 {
+            return None;
+        }
+
+        let present = |bit: usize| flags & (1 << bit) != 0;
+
+        let mut texts = [None, None, None];
+
+        for (bit, text) in texts.iter_mut().enumerate() {
+            if present(bit) {
+                let length = take_byte(&mut value)? as usize;
+                let bytes = value.get(..length)?;
+                value = &value[length..];
+
+                *text = Some(String::from_utf8(bytes.to_vec()).ok()?);
+            }
+        }
+
+        let difficulty = if present(3) {
+            Some(Difficulty::try_from(take_byte(&mut value)?).ok()?)
+        } else {
+            None
+        };
+
+        let par = if present(4) {
+            Some(take_byte(&mut value)?)
+        } else {
+            None
+        };
+
+        let [title, author, description] = texts;
+
+        Some((
+            LevelMetadata {
+                title,
+                author,
+                description,
+                difficulty,
+                par,
+            },
+            value,
+        ))
+    }
+}
+
+/// Splits off the first byte of `value`.
+fn take_byte(value: &mut &[u8]) -> Option<u8> {
+    let (&byte, rest) = value.split_first()?;
+    *value = rest;
+
+    Some(byte)
+}
@@ -1,21 +1,33 @@
 mod board;
+mod event;
 mod game;
+mod generator;
 mod level;
+mod level_text;
 mod mage;
 mod mana;
+mod metadata;
 mod position;
 mod powerup;
+mod simulation;
 mod spell;
 mod team;
 mod turn;
+mod validation;
 
 pub use board::*;
+pub use event::*;
 pub use game::*;
+pub use generator::*;
 pub use level::*;
+pub use level_text::*;
 pub use mage::*;
 pub use mana::*;
+pub use metadata::*;
 pub use position::*;
 pub use powerup::*;
+pub use simulation::*;
 pub use spell::*;
 pub use team::*;
 pub use turn::*;
+pub use validation::*;
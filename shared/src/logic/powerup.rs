@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::Position;
 
 /// Style for a [`PowerUp::Boulder`]
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Hash)]
 pub enum BoulderStyle {
     /// Default rock block.
     Rock,
@@ -16,7 +16,7 @@ pub enum BoulderStyle {
 }
 
 /// A [`PowerUp`] is a the distinct type of the powerup.
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Hash)]
 pub enum PowerUp {
     /// Turns the mage into defensive mode.
     Shield,
@@ -0,0 +1,359 @@
+use rand_chacha::{
+    rand_core::{RngCore, SeedableRng},
+    ChaCha8Rng,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Game, GameEvent, GameResult, Level, MageSort, Position, PowerUp, Team, Turn, TurnLeaf,
+};
+
+/// Number of moves after which a simulated game is called a draw.
+pub const SIMULATION_MOVE_LIMIT: usize = 50;
+
+/// How strongly a [`crate::Mage`] influenced the simulated games, as part of a [`SimulationReport`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MageImpact {
+    /// Starting [`Position`] of the mage.
+    pub position: Position,
+    /// [`Team`] of the mage.
+    pub team: Team,
+    /// [`MageSort`] of the mage.
+    pub sort: MageSort,
+    /// Average damage dealt to the enemy team per game.
+    pub damage: f64,
+}
+
+/// How strongly a [`PowerUp`] influenced the simulated games, as part of a [`SimulationReport`].
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PowerUpImpact {
+    /// [`Position`] of the power-up.
+    pub position: Position,
+    /// The [`PowerUp`].
+    pub powerup: PowerUp,
+    /// Share of games in which the power-up was picked up.
+    pub pickup_rate: f64,
+    /// Share of those games won by the team that picked it up.
+    pub win_rate: f64,
+}
+
+/// Summary of a [`Simulation`] of AI-versus-AI games on a [`Level`].
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SimulationReport {
+    /// Number of games simulated.
+    pub games: usize,
+    /// Number of games won by [`Team::Red`].
+    pub red_wins: usize,
+    /// Number of games won by [`Team::Blue`].
+    pub blue_wins: usize,
+    /// Number of games that ended in a stalemate or ran into [`SIMULATION_MOVE_LIMIT`].
+    pub draws: usize,
+    /// Average number of moves per game.
+    pub average_length: f64,
+    /// Win rate of the starting team minus that of the other team.
+    pub first_move_advantage: f64,
+    /// 95% [Wilson score interval](https://en.wikipedia.org/wiki/Binomial_proportion_confidence_interval#Wilson_score_interval) of the red team's win rate.
+    pub red_win_interval: (f64, f64),
+    /// Mages ordered from most to least damage dealt.
+    pub decisive_mages: Vec<MageImpact>,
+    /// Power-ups that were picked up, ordered from highest to lowest win rate.
+    pub decisive_powerups: Vec<PowerUpImpact>,
+}
+
+impl SimulationReport {
+    /// Share of games won by [`Team::Red`].
+    pub fn red_rate(&self) -> f64 {
+        self.rate(self.red_wins)
+    }
+
+    /// Share of games won by [`Team::Blue`].
+    pub fn blue_rate(&self) -> f64 {
+        self.rate(self.blue_wins)
+    }
+
+    /// Share of games drawn.
+    pub fn draw_rate(&self) -> f64 {
+        self.rate(self.draws)
+    }
+
+    fn rate(&self, count: usize) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            count as f64 / self.games as f64
+        }
+    }
+}
+
+/// Outcome of a single simulated game.
+struct GameRecord {
+    winner: Option<Team>,
+    moves: usize,
+    /// Damage dealt to the enemy team, indexed like the mages of the [`Level`].
+    damage: Vec<usize>,
+    /// Power-ups picked up, with the [`Team`] that picked them up.
+    pickups: Vec<(Position, Team)>,
+}
+
+/// A series of AI-versus-AI games on a [`Level`], played one move at a time with [`Simulation::step`].
+/// The AI plays at the strength recommended by the [`crate::LevelMetadata`], as it would against a player.
+pub struct Simulation {
+    level: Level,
+    runs: usize,
+    /// Draws the seed of each game, so that games do not share seeds.
+    rng: ChaCha8Rng,
+    /// Seed of the current game, to which the number of moves is added for each move.
+    game_seed: u64,
+    game: Game,
+    record: GameRecord,
+    records: Vec<GameRecord>,
+}
+
+impl Simulation {
+    /// Prepares `runs` games on the level.
+    pub fn new(level: &Level, runs: usize, seed: u64) -> Simulation {
+        let level = level.clone();
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        Simulation {
+            game: Game::new(&level, true).unwrap(),
+            record: GameRecord::new(&level),
+            level,
+            runs,
+            game_seed: rng.next_u64(),
+            rng,
+            records: Vec::with_capacity(runs),
+        }
+    }
+
+    /// Returns the number of games finished and the number of games in total.
+    pub fn progress(&self) -> (usize, usize) {
+        (self.records.len(), self.runs)
+    }
+
+    /// Determines if all games have been played.
+    pub fn is_finished(&self) -> bool {
+        self.records.len() >= self.runs
+    }
+
+    /// Plays a single move of the current game, moving on to the next game once it ends.
+    pub fn step(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+
+        let seed = self.game_seed.wrapping_add(self.record.moves as u64);
+
+        let events = match self.game.best_turn_recommended(seed) {
+            Some(TurnLeaf(Turn(from, to), _)) if self.record.moves < SIMULATION_MOVE_LIMIT => {
+                self.game.take_move(from, to)
+            }
+            _ => None,
+        };
+
+        match events {
+            Some(events) => {
+                self.record.moves += 1;
+                self.record.add_events(&self.level, &events);
+
+                if let Some(result) = self.game.result() {
+                    self.finish_game(result);
+                }
+            }
+            None => self.finish_game(self.game.result().unwrap_or(GameResult::Stalemate)),
+        }
+    }
+
+    /// Plays all remaining games.
+    pub fn play_out(&mut self) {
+        while !self.is_finished() {
+            self.step();
+        }
+    }
+
+    /// Plays all remaining games and returns the [`SimulationReport`].
+    pub fn run(mut self) -> SimulationReport {
+        self.play_out();
+
+        self.report()
+    }
+
+    /// Takes over the finished games of another simulation of the same level,
+    /// so that games can be played separately, e.g. in parallel, and reported on together.
+    pub fn merge(&mut self, other: Simulation) {
+        self.runs += other.runs;
+        self.records.extend(other.records);
+    }
+
+    fn finish_game(&mut self, result: GameResult) {
+        self.record.winner = match result {
+            GameResult::Win(team) => Some(team),
+            GameResult::Stalemate => None,
+        };
+
+        self.records.push(std::mem::replace(
+            &mut self.record,
+            GameRecord::new(&self.level),
+        ));
+        self.game = Game::new(&self.level, true).unwrap();
+        self.game_seed = self.rng.next_u64();
+    }
+
+    /// Summarises the games finished so far.
+    pub fn report(&self) -> SimulationReport {
+        let games = self.records.len();
+        let wins = |team: Team| {
+            self.records
+                .iter()
+                .filter(|record| record.winner == Some(team))
+                .count()
+        };
+
+        let (red_wins, blue_wins) = (wins(Team::Red), wins(Team::Blue));
+
+        let mut report = SimulationReport {
+            games,
+            red_wins,
+            blue_wins,
+            draws: games - red_wins - blue_wins,
+            ..Default::default()
+        };
+
+        if games == 0 {
+            return report;
+        }
+
+        report.average_length = self
+            .records
+            .iter()
+            .map(|record| record.moves)
+            .sum::<usize>() as f64
+            / games as f64;
+
+        report.first_move_advantage = match self.level.starting_team {
+            Team::Red => report.red_rate() - report.blue_rate(),
+            Team::Blue => report.blue_rate() - report.red_rate(),
+        };
+
+        report.red_win_interval = wilson_interval(red_wins, games);
+
+        report.decisive_mages = self
+            .level
+            .mages
+            .iter()
+            .enumerate()
+            .map(|(index, mage)| MageImpact {
+                position: mage.position,
+                team: mage.team,
+                sort: mage.sort,
+                damage: self
+                    .records
+                    .iter()
+                    .map(|record| record.damage[index])
+                    .sum::<usize>() as f64
+                    / games as f64,
+            })
+            .collect();
+        report
+            .decisive_mages
+            .sort_by(|a, b| b.damage.total_cmp(&a.damage));
+
+        report.decisive_powerups = self
+            .level
+            .powerups
+            .iter()
+            .filter_map(|(position, powerup)| {
+                let pickups: Vec<(Option<Team>, Team)> = self
+                    .records
+                    .iter()
+                    .filter_map(|record| {
+                        record
+                            .pickups
+                            .iter()
+                            .find(|(at, _)| at == position)
+                            .map(|(_, team)| (record.winner, *team))
+                    })
+                    .collect();
+
+                (!pickups.is_empty()).then(|| PowerUpImpact {
+                    position: *position,
+                    powerup: *powerup,
+                    pickup_rate: pickups.len() as f64 / games as f64,
+                    win_rate: pickups
+                        .iter()
+                        .filter(|(winner, team)| *winner == Some(*team))
+                        .count() as f64
+                        / pickups.len() as f64,
+                })
+            })
+            .collect();
+        report
+            .decisive_powerups
+            .sort_by(|a, b| b.win_rate.total_cmp(&a.win_rate));
+
+        report
+    }
+}
+
+impl GameRecord {
+    fn new(level: &Level) -> GameRecord {
+        GameRecord {
+            winner: None,
+            moves: 0,
+            damage: vec![0; level.mages.len()],
+            pickups: Vec::new(),
+        }
+    }
+
+    /// Attributes the damage of a move to the mage that moved, and notes the power-ups it picked up.
+    /// Events name mages by their index, which is looked up rather than assumed to match their place in the level.
+    fn add_events(&mut self, level: &Level, events: &[GameEvent]) {
+        let find = |index: usize| {
+            level
+                .mages
+                .iter()
+                .enumerate()
+                .find(|(_, mage)| mage.index == index)
+        };
+        let mut attacker = None;
+
+        for event in events {
+            match event {
+                GameEvent::Moved { mage, .. } => attacker = find(*mage),
+                GameEvent::PickedUp { mage, at, .. } => {
+                    if let Some((_, mage)) = find(*mage) {
+                        self.pickups.push((*at, mage.team));
+                    }
+                }
+                GameEvent::Hit { mage, damage, .. } => {
+                    if let (Some((slot, attacker)), Some((_, target))) = (attacker, find(*mage)) {
+                        if attacker.team != target.team {
+                            self.damage[slot] += *damage as usize;
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Computes the 95% Wilson score interval of a binomial proportion.
+fn wilson_interval(successes: usize, trials: usize) -> (f64, f64) {
+    const Z: f64 = 1.96;
+
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let denominator = 1.0 + Z * Z / n;
+    let centre = (p + Z * Z / (2.0 * n)) / denominator;
+    let margin = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt() / denominator;
+
+    ((centre - margin).max(0.0), (centre + margin).min(1.0))
+}
+
+impl Level {
+    /// Simulates `n` AI-versus-AI games as a [`Simulation`] and reports on the results.
+    pub fn simulation_report(&self, n: usize, seed: u64) -> SimulationReport {
+        Simulation::new(self, n, seed).run()
+    }
+}
@@ -0,0 +1,232 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt::Display,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Game, Level, Position, PowerUp, Team, MOVE_DIRECTIONS};
+
+/// How serious a [`LevelIssue`] is.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IssueSeverity {
+    /// The level is playable, but likely not as intended.
+    Warning,
+    /// The level cannot be played.
+    Error,
+}
+
+/// A problem with a [`Level`], as found by [`Level::validate`].
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub enum LevelIssue {
+    /// A mage or power-up lies outside of the board.
+    OutOfBounds(Position),
+    /// Several mages share a position.
+    DuplicatePosition(Position),
+    /// A mage stands on a [`PowerUp::Boulder`].
+    Overlap(Position),
+    /// A team has no live mages.
+    MissingTeam(Team),
+    /// The starting team cannot make a move.
+    NoLegalMoves(Team),
+    /// Free tiles that no mage can ever reach, as they are walled off by boulders.
+    UnreachableTiles(Vec<Position>),
+    /// Positions whose mage or power-up has no counterpart when the board is rotated by 180 degrees.
+    Asymmetry(Vec<Position>),
+}
+
+impl LevelIssue {
+    /// Returns the [`IssueSeverity`] of the issue.
+    pub fn severity(&self) -> IssueSeverity {
+        match self {
+            LevelIssue::OutOfBounds(_)
+            | LevelIssue::DuplicatePosition(_)
+            | LevelIssue::Overlap(_)
+            | LevelIssue::MissingTeam(_)
+            | LevelIssue::NoLegalMoves(_) => IssueSeverity::Error,
+            LevelIssue::UnreachableTiles(_) | LevelIssue::Asymmetry(_) => IssueSeverity::Warning,
+        }
+    }
+
+    /// Returns `true` if the issue prevents the level from being played.
+    pub fn is_error(&self) -> bool {
+        self.severity() == IssueSeverity::Error
+    }
+}
+
+impl Display for LevelIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LevelIssue::OutOfBounds(position) => write!(f, "piece off the board at {position:?}"),
+            LevelIssue::DuplicatePosition(position) => {
+                write!(f, "multiple mages at {position:?}")
+            }
+            LevelIssue::Overlap(position) => write!(f, "mage on a boulder at {position:?}"),
+            LevelIssue::MissingTeam(team) => write!(f, "{team:?} team has no mages"),
+            LevelIssue::NoLegalMoves(team) => write!(f, "{team:?} team cannot move"),
+            LevelIssue::UnreachableTiles(positions) => {
+                write!(f, "{} unreachable tiles", positions.len())
+            }
+            LevelIssue::Asymmetry(positions) => {
+                write!(
+                    f,
+                    "{} pieces without a symmetric counterpart",
+                    positions.len()
+                )
+            }
+        }
+    }
+}
+
+impl Level {
+    /// Lints the level, returning a list of [`LevelIssue`]s ordered from most to least severe.
+    /// An empty list means that the level is playable and symmetric.
+    pub fn validate(&self) -> Vec<LevelIssue> {
+        let mut issues = Vec::new();
+
+        let mut positions = HashSet::new();
+        let mut duplicates = BTreeSet::new();
+
+        for mage in &self.mages {
+            if !positions.insert(mage.position) {
+                duplicates.insert(mage.position);
+            }
+        }
+
+        issues.extend(
+            self.mages
+                .iter()
+                .map(|mage| mage.position)
+                .chain(self.powerups.keys().copied())
+                .filter(|position| self.board.validate_position(*position).is_none())
+                .collect::<BTreeSet<Position>>()
+                .into_iter()
+                .map(LevelIssue::OutOfBounds),
+        );
+
+        issues.extend(duplicates.into_iter().map(LevelIssue::DuplicatePosition));
+
+        issues.extend(
+            self.mages
+                .iter()
+                .map(|mage| mage.position)
+                .filter(|position| self.has_boulder(position))
+                .collect::<BTreeSet<Position>>()
+                .into_iter()
+                .map(LevelIssue::Overlap),
+        );
+
+        for team in [Team::Red, Team::Blue] {
+            if !self
+                .mages
+                .iter()
+                .any(|mage| mage.team == team && mage.is_alive())
+            {
+                issues.push(LevelIssue::MissingTeam(team));
+            }
+        }
+
+        if let Ok(game) = Game::new(self, true) {
+            if !game
+                .iter_mages()
+                .filter(|mage| mage.team == self.starting_team && mage.is_alive())
+                .any(|mage| !game.available_moves(mage).is_empty())
+            {
+                issues.push(LevelIssue::NoLegalMoves(self.starting_team));
+            }
+        }
+
+        let unreachable = self.unreachable_tiles();
+
+        if !unreachable.is_empty() {
+            issues.push(LevelIssue::UnreachableTiles(unreachable));
+        }
+
+        let asymmetric = self.asymmetric_positions();
+
+        if !asymmetric.is_empty() {
+            issues.push(LevelIssue::Asymmetry(asymmetric));
+        }
+
+        issues
+    }
+
+    /// Checks if a [`PowerUp::Boulder`] lies on the tile.
+    fn has_boulder(&self, position: &Position) -> bool {
+        matches!(self.powerups.get(position), Some(PowerUp::Boulder(_)))
+    }
+
+    /// Returns the free tiles that cannot be reached from any mage, moving around boulders.
+    /// Diagonal steps are taken into account if any mage has or can pick up [`PowerUp::Diagonal`].
+    fn unreachable_tiles(&self) -> Vec<Position> {
+        let is_free = |position: &Position| {
+            self.board.validate_position(*position).is_some() && !self.has_boulder(position)
+        };
+
+        let diagonals = self.mages.iter().any(|mage| mage.has_diagonals())
+            || self
+                .powerups
+                .values()
+                .any(|powerup| *powerup == PowerUp::Diagonal);
+
+        let steps: Vec<Position> = MOVE_DIRECTIONS
+            .iter()
+            .filter(|(_, diagonal)| diagonals || !diagonal)
+            .map(|(step, _)| *step)
+            .collect();
+
+        let mut frontier: Vec<Position> = self
+            .mages
+            .iter()
+            .filter(|mage| mage.is_alive())
+            .map(|mage| mage.position)
+            .filter(is_free)
+            .collect();
+
+        if frontier.is_empty() {
+            return Vec::new();
+        }
+
+        let mut reached: HashSet<Position> = frontier.iter().copied().collect();
+
+        while let Some(position) = frontier.pop() {
+            for step in &steps {
+                let next = &position + step;
+
+                if is_free(&next) && reached.insert(next) {
+                    frontier.push(next);
+                }
+            }
+        }
+
+        (0..self.board.height as i8)
+            .flat_map(|y| (0..self.board.width as i8).map(move |x| Position(x, y)))
+            .filter(|position| is_free(position) && !reached.contains(position))
+            .collect()
+    }
+
+    /// Returns the positions of mages and power-ups that are not mirrored by [`Position::rotate`],
+    /// where a mirrored mage is of the same sort and mana but belongs to the enemy team.
+    fn asymmetric_positions(&self) -> Vec<Position> {
+        let mages = self.mages.iter().filter(|mage| {
+            !self.mages.iter().any(|other| {
+                other.position == mage.position.rotate(&self.board)
+                    && other.team == mage.team.enemy()
+                    && other.sort as usize == mage.sort as usize
+                    && other.mana.0 == mage.mana.0
+                    && other.mana.1 == mage.mana.1
+            })
+        });
+
+        let powerups = self.powerups.iter().filter(|(position, powerup)| {
+            self.powerups.get(&position.rotate(&self.board)) != Some(powerup)
+        });
+
+        mages
+            .map(|mage| mage.position)
+            .chain(powerups.map(|(position, _)| *position))
+            .collect::<BTreeSet<Position>>()
+            .into_iter()
+            .collect()
+    }
+}
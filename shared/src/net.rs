@@ -4,7 +4,7 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 use serde_json_any_key::*;
 
-use crate::{Lobby, LobbyError, LobbySettings, Turn};
+use crate::{Lobby, LobbyError, LobbySettings, MageSort, Position, Team, Turn};
 
 /// A network message.
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,28 +15,57 @@ pub enum Message {
     Turn(Turn),
     /// A list of [`Turn`]s for synchronising observers who may be multiple turns behind.
     Turns(Vec<Turn>),
+    /// Offers the opponent to end the game in a draw.
+    OfferDraw,
+    /// Accepts the opponent's draw offer.
+    AcceptDraw,
+    /// Declines the opponent's draw offer.
+    DeclineDraw,
+    /// Picks a [`MageSort`] from the pool of a [`crate::Draft`].
+    Pick(MageSort),
+    /// Places a picked [`MageSort`] of a [`crate::Draft`] in the home rows.
+    Place(MageSort, Position),
+    /// Commits the arrangement of a team's mages for a [`crate::Deployment`].
+    Deploy(Vec<Position>),
     /// An entire [`Lobby`] state for complete synchronisation.
     Lobby(Box<Lobby>),
     /// List of lobbies
     Lobbies(#[serde(with = "any_key_map")] HashMap<u16, Lobby>),
+    /// Confirms joining a lobby, with the token to rejoin it from another session.
+    Joined(String),
+    /// A [`Presence`] change of a player in a lobby.
+    Presence(Presence),
     /// A [`LobbyError`].
     LobbyError(LobbyError),
 }
 
-/// An HTTP request made with a certain session ID.
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SessionRequest {
-    /// The session ID for this request.
+/// A player joining or leaving a lobby, announced to everyone following it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Presence {
+    /// The player of a [`Team`] joined or rejoined.
+    Joined(Team),
+    /// The player of a [`Team`] left.
+    Left(Team),
+    /// The player of a [`Team`] stayed away for too long and forfeited the game.
+    Forfeited(Team),
+}
+
+/// A signed token authenticating a session, handed out by the server and sent back with every lobby request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionToken {
+    /// The session ID the token was issued for.
     pub session_id: String,
+    /// The token to send as a `Bearer` token in the `Authorization` header.
+    pub token: String,
+    /// The UNIX timestamp in seconds at which the token expires and has to be refreshed.
+    pub expires: u64,
 }
 
-/// An HTTP request made with a session ID, containing a [`Message`] payload.
+/// An HTTP request to reclaim a player's slot in a lobby for the session making it.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct SessionMessage {
-    /// The session ID for this request.
-    pub session_id: String,
-    /// A [`Message`] payload.
-    pub message: Message,
+pub struct SessionRejoin {
+    /// The token that was handed out when the slot was joined.
+    pub rejoin_token: String,
 }
 
 /// An HTTP request made with a session ID, containing a [`Message`] payload.
@@ -56,4 +85,4 @@ pub fn timestamp() -> Duration {
         .expect("time went backwards");
 
     Duration::from_secs_f64(since_the_epoch.as_secs_f64())
-}
\ No newline at end of file
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use std::collections::HashSet;
+
+use shared::{CampaignError, UnlockRule};
+
+#[test]
+fn arena_is_valid() {
+    let campaign = common::arena();
+
+    assert_eq!(campaign.validate(), Ok(()));
+    assert_eq!(campaign.portals.len(), 28);
+    assert_eq!(campaign.clone().demo().validate(), Ok(()));
+    assert_eq!(campaign.demo().portals.len(), 4);
+}
+
+#[test]
+fn unlocks_by_rule() {
+    let mut campaign = common::arena();
+    let won = HashSet::from([(0, 0)]);
+
+    let basics_i = campaign.portal((0, 0)).unwrap();
+    let basics_ii = campaign.portal((1, 0)).unwrap();
+    let basics_iii = campaign.portal((2, 0)).unwrap();
+
+    assert!(campaign.is_unlocked(basics_i, &HashSet::new()));
+    assert!(campaign.is_unlocked(basics_ii, &won));
+    assert!(!campaign.is_unlocked(basics_iii, &won));
+
+    campaign.portals[2].unlock = UnlockRule::After(vec![(0, 0), (1, 0)]);
+
+    let basics_iii = campaign.portal((2, 0)).unwrap();
+
+    assert!(!campaign.is_unlocked(basics_iii, &won));
+    assert!(campaign.is_unlocked(basics_iii, &HashSet::from([(0, 0), (1, 0)])));
+}
+
+#[test]
+fn rejects_broken_campaigns() {
+    let mut campaign = common::arena();
+    campaign.portals[1].position = (0, 0);
+
+    assert_eq!(
+        campaign.validate(),
+        Err(CampaignError::DuplicatePosition((0, 0)))
+    );
+
+    let mut campaign = common::arena();
+    campaign.portals[1].unlock = UnlockRule::After(vec![(99, 99)]);
+
+    assert_eq!(
+        campaign.validate(),
+        Err(CampaignError::UnknownPrerequisite((1, 0), (99, 99)))
+    );
+
+    let mut campaign = common::arena();
+    campaign.portals[0].unlock = UnlockRule::Adjacent;
+
+    assert_eq!(campaign.validate(), Err(CampaignError::NoStartingPortal));
+
+    let mut campaign = common::arena();
+    campaign.portals[3].code = "not a level".to_string();
+
+    assert!(matches!(
+        campaign.validate(),
+        Err(CampaignError::InvalidLevel((2, -1), _))
+    ));
+}
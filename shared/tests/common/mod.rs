@@ -0,0 +1,53 @@
+//! Fixtures shared by the integration tests, of which each test only uses some.
+#![allow(dead_code)]
+
+use std::collections::BTreeMap;
+
+use shared::{Board, Campaign, Level, Mage, MageSort, Position, PowerUp, Team};
+
+/// The Arena campaign shipped with the client, whose levels are stored as unversioned codes.
+const ARENA: &str = include_str!("../../../static/campaign/arena.json");
+
+/// Parses the Arena campaign.
+pub fn arena() -> Campaign {
+    serde_json::from_str(ARENA).unwrap()
+}
+
+/// Returns the level codes of the Arena campaign, in the order of its portals.
+pub fn arena_codes() -> Vec<String> {
+    arena()
+        .portals
+        .into_iter()
+        .map(|portal| portal.code)
+        .collect()
+}
+
+/// Builds a level on a board of the given width and height, numbering the mages in order and letting [`Team::Red`] start.
+pub fn level(
+    (width, height): (usize, usize),
+    mages: &[(Team, MageSort, Position)],
+    powerups: &[(Position, PowerUp)],
+) -> Level {
+    Level::new(
+        Board::new(width, height).unwrap(),
+        mages
+            .iter()
+            .enumerate()
+            .map(|(index, (team, sort, position))| Mage::new(index, *team, *sort, *position))
+            .collect(),
+        powerups.iter().copied().collect::<BTreeMap<_, _>>(),
+        Team::Red,
+    )
+}
+
+/// Two diamonds in opposite corners of an 8 by 8 board, far out of each other's reach.
+pub fn distant_level() -> Level {
+    level(
+        (8, 8),
+        &[
+            (Team::Red, MageSort::Diamond, Position(0, 0)),
+            (Team::Blue, MageSort::Diamond, Position(7, 7)),
+        ],
+        &[],
+    )
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use shared::{
+    BoulderStyle, Deployment, DeploymentError, Level, LoadoutMethod, Lobby, LobbySettings,
+    MageSort, Mages, Position, PowerUp, Team,
+};
+
+fn level() -> Level {
+    common::level(
+        (6, 6),
+        &[
+            (Team::Red, MageSort::Diamond, Position(1, 5)),
+            (Team::Red, MageSort::Cross, Position(2, 2)),
+            (Team::Blue, MageSort::Diamond, Position(4, 0)),
+            (Team::Blue, MageSort::Cross, Position(3, 1)),
+        ],
+        &[(Position(0, 4), PowerUp::Boulder(BoulderStyle::Rock))],
+    )
+}
+
+#[test]
+fn defaults_move_mages_into_home_rows() {
+    let deployment = Deployment::new(level()).unwrap();
+
+    assert_eq!(
+        deployment.default_positions(Team::Red),
+        vec![Position(1, 5), Position(1, 4)]
+    );
+    assert_eq!(
+        deployment.default_positions(Team::Blue),
+        vec![Position(4, 0), Position(3, 1)]
+    );
+}
+
+#[test]
+fn rejects_invalid_arrangements() {
+    let mut deployment = Deployment::new(level()).unwrap();
+
+    assert_eq!(
+        deployment.check(Team::Red, &[Position(1, 5)]),
+        Err(DeploymentError::WrongCount)
+    );
+    assert_eq!(
+        deployment.check(Team::Red, &[Position(1, 5), Position(1, 3)]),
+        Err(DeploymentError::NotHomeRow)
+    );
+    assert_eq!(
+        deployment.check(Team::Red, &[Position(1, 5), Position(1, 5)]),
+        Err(DeploymentError::Occupied)
+    );
+    assert_eq!(
+        deployment.check(Team::Red, &[Position(1, 5), Position(0, 4)]),
+        Err(DeploymentError::Occupied)
+    );
+
+    deployment
+        .commit(Team::Red, vec![Position(5, 5), Position(5, 4)])
+        .unwrap();
+
+    assert_eq!(
+        deployment.commit(Team::Red, vec![Position(5, 5), Position(5, 4)]),
+        Err(DeploymentError::AlreadyDeployed)
+    );
+    assert_eq!(deployment.turn_for(), Some(Team::Blue));
+}
+
+#[test]
+fn hides_arrangements_until_both_commit() {
+    let mut lobby = Lobby::new(
+        LobbySettings {
+            loadout_method: LoadoutMethod::Prefab(level()),
+            deployment: true,
+            ..Default::default()
+        },
+        Default::default(),
+    );
+
+    assert!(lobby.is_deploying());
+    assert!(!lobby.finished());
+
+    lobby
+        .deploy(Team::Red, vec![Position(5, 5), Position(4, 4)])
+        .unwrap();
+
+    let masked = lobby.deployment().unwrap().masked(Some(Team::Blue));
+    assert!(masked.has_deployed(Team::Red));
+
+    let view = masked.level_for(Some(Team::Blue), None);
+    assert!(view.mages.iter().all(|mage| mage.team == Team::Blue));
+
+    assert!(masked.level_for(Some(Team::Red), None).mages.is_empty());
+
+    lobby
+        .deploy(Team::Blue, vec![Position(0, 0), Position(1, 1)])
+        .unwrap();
+
+    assert!(!lobby.is_deploying());
+    assert!(lobby.game.live_occupied_by(&Position(5, 5), Team::Red));
+    assert!(lobby.game.live_occupied_by(&Position(4, 4), Team::Red));
+    assert!(lobby.game.live_occupied_by(&Position(1, 1), Team::Blue));
+    assert!(!lobby.game.live_occupied(&Position(2, 2)));
+}
@@ -0,0 +1,144 @@
+use shared::{
+    Board, Draft, DraftError, LoadoutMethod, Lobby, LobbySettings, MageSort, Position, Team,
+};
+
+fn draft() -> Draft {
+    Draft::new(
+        Board::new(6, 6).unwrap(),
+        2,
+        vec![
+            MageSort::Diamond,
+            MageSort::Cross,
+            MageSort::Knight,
+            MageSort::Spike,
+            MageSort::Diamond,
+            MageSort::Cross,
+        ],
+        Team::Red,
+    )
+}
+
+#[test]
+fn picks_alternate() {
+    let mut draft = draft();
+
+    assert_eq!(
+        draft.pick(Team::Blue, MageSort::Diamond),
+        Err(DraftError::NotYourTurn)
+    );
+    assert_eq!(
+        draft.pick(Team::Red, MageSort::Plus),
+        Err(DraftError::NotInPool)
+    );
+
+    draft.pick(Team::Red, MageSort::Knight).unwrap();
+    assert_eq!(draft.turn_for(), Some(Team::Blue));
+    assert_eq!(
+        draft.pick(Team::Blue, MageSort::Knight),
+        Err(DraftError::NotInPool)
+    );
+
+    draft.pick(Team::Blue, MageSort::Diamond).unwrap();
+    draft.pick(Team::Red, MageSort::Diamond).unwrap();
+    draft.pick(Team::Blue, MageSort::Spike).unwrap();
+
+    assert!(!draft.is_picking());
+    assert_eq!(draft.pool().len(), 2);
+    assert_eq!(
+        draft.picks(Team::Red),
+        vec![MageSort::Knight, MageSort::Diamond]
+    );
+    assert_eq!(
+        draft.pick(Team::Red, MageSort::Cross),
+        Err(DraftError::PickingOver)
+    );
+}
+
+#[test]
+fn places_in_home_rows() {
+    let mut draft = draft();
+
+    assert_eq!(
+        draft.place(Team::Red, MageSort::Knight, Position(0, 5)),
+        Err(DraftError::StillPicking)
+    );
+
+    for (team, sort) in [
+        (Team::Red, MageSort::Knight),
+        (Team::Blue, MageSort::Diamond),
+        (Team::Red, MageSort::Diamond),
+        (Team::Blue, MageSort::Spike),
+    ] {
+        draft.pick(team, sort).unwrap();
+    }
+
+    assert_eq!(
+        draft.place(Team::Red, MageSort::Spike, Position(0, 5)),
+        Err(DraftError::NotPicked)
+    );
+    assert_eq!(
+        draft.place(Team::Red, MageSort::Knight, Position(0, 3)),
+        Err(DraftError::NotHomeRow)
+    );
+
+    draft
+        .place(Team::Red, MageSort::Knight, Position(0, 4))
+        .unwrap();
+    assert_eq!(
+        draft.place(Team::Blue, MageSort::Spike, Position(0, 4)),
+        Err(DraftError::NotHomeRow)
+    );
+    draft
+        .place(Team::Blue, MageSort::Spike, Position(5, 0))
+        .unwrap();
+    assert_eq!(
+        draft.place(Team::Red, MageSort::Diamond, Position(0, 4)),
+        Err(DraftError::Occupied)
+    );
+    draft
+        .place(Team::Red, MageSort::Diamond, Position(1, 5))
+        .unwrap();
+    assert_eq!(draft.unplaced(Team::Red), vec![]);
+    draft
+        .place(Team::Blue, MageSort::Diamond, Position(2, 1))
+        .unwrap();
+
+    assert!(draft.is_complete());
+    assert_eq!(draft.turn_for(), None);
+    assert_eq!(draft.level().mages.len(), 4);
+}
+
+#[test]
+fn lobbies_start_after_draft() {
+    let mut lobby = Lobby::new(
+        LobbySettings {
+            loadout_method: LoadoutMethod::Draft {
+                board: Board::new(6, 6).unwrap(),
+                mages: 3,
+            },
+            seed: 7,
+            ..Default::default()
+        },
+        Default::default(),
+    );
+
+    assert!(lobby.is_drafting());
+    assert!(!lobby.finished());
+    assert_eq!(lobby.draft().unwrap().pool().len(), 8);
+
+    let mut seed = 0;
+
+    while lobby.is_drafting() {
+        lobby.auto_draft(seed).unwrap();
+        seed += 1;
+    }
+
+    assert_eq!(seed, 12);
+    assert_eq!(lobby.game.iter_mages().count(), 6);
+    assert!(lobby.game.iter_mages().all(|mage| lobby
+        .game
+        .board()
+        .is_home_position(mage.position, mage.team)));
+    assert!(!lobby.finished());
+    assert!(lobby.auto_draft(seed).is_err());
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use shared::{Game, GameEvent, MageSort, Position, PowerUp, Team};
+
+#[test]
+fn beam_fells_last_mage() {
+    let mut level = common::level(
+        (8, 8),
+        &[
+            (Team::Red, MageSort::Diamond, Position(0, 3)),
+            (Team::Blue, MageSort::Diamond, Position(3, 3)),
+        ],
+        &[(Position(1, 3), PowerUp::Beam)],
+    );
+    level.mages[1].mana.0 = 1;
+
+    let mut game = Game::new(&level, false).unwrap();
+
+    let events = game.take_move(Position(0, 3), Position(1, 3)).unwrap();
+
+    assert_eq!(
+        events,
+        vec![
+            GameEvent::Moved {
+                mage: 0,
+                from: Position(0, 3),
+                to: Position(1, 3),
+            },
+            GameEvent::PickedUp {
+                mage: 0,
+                at: Position(1, 3),
+                powerup: PowerUp::Beam,
+            },
+            GameEvent::BeamFired {
+                mage: 0,
+                at: Position(1, 3),
+            },
+            GameEvent::Hit {
+                mage: 1,
+                at: Position(3, 3),
+                damage: 1,
+            },
+            GameEvent::MageFell {
+                mage: 1,
+                at: Position(3, 3),
+            },
+            GameEvent::GameEnded {
+                winner: Some(Team::Red),
+            },
+        ]
+    );
+}
+
+#[test]
+fn shield_turns_spell_back() {
+    let mut level = common::level(
+        (8, 8),
+        &[
+            (Team::Red, MageSort::Diamond, Position(2, 3)),
+            (Team::Blue, MageSort::Cross, Position(4, 4)),
+        ],
+        &[],
+    );
+    level.mages[1].powerup = Some(PowerUp::Shield);
+
+    let mut game = Game::new(&level, false).unwrap();
+
+    let events = game.take_move(Position(2, 3), Position(3, 3)).unwrap();
+
+    assert_eq!(
+        &events[1..],
+        [
+            GameEvent::Hit {
+                mage: 1,
+                at: Position(4, 4),
+                damage: 1,
+            },
+            GameEvent::ShieldBlocked {
+                mage: 0,
+                at: Position(3, 3),
+            },
+            GameEvent::Hit {
+                mage: 0,
+                at: Position(3, 3),
+                damage: 1,
+            },
+        ]
+    );
+}
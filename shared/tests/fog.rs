@@ -0,0 +1,43 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use shared::{Game, Level, Position, PowerUp, Team};
+
+fn distant_level() -> Level {
+    let mut level = common::distant_level();
+    level.powerups = BTreeMap::from([
+        (Position(1, 1), PowerUp::Diagonal),
+        (Position(6, 6), PowerUp::Beam),
+    ]);
+
+    level
+}
+
+#[test]
+fn hides_distant_enemies() {
+    let mut game = Game::new(&distant_level(), false).unwrap();
+
+    game.take_move(Position(0, 0), Position(1, 0)).unwrap();
+    game.take_move(Position(7, 7), Position(6, 7)).unwrap();
+
+    let fogged = game.fogged(Some(Team::Red));
+
+    assert!(fogged.is_fogged());
+    assert!(fogged.iter_mages().all(|mage| mage.team == Team::Red));
+    assert!(fogged.powerups().contains_key(&Position(1, 1)));
+    assert!(!fogged.powerups().contains_key(&Position(6, 6)));
+    assert_eq!(fogged.turns(), game.turns());
+    assert_eq!(fogged.turn_for(), game.turn_for());
+    assert_eq!(fogged.turns_since(1)[0].1, Position(0, 0));
+    assert!(fogged.result().is_none());
+}
+
+#[test]
+fn outsiders_see_nothing() {
+    let game = Game::new(&distant_level(), false).unwrap();
+    let fogged = game.fogged(None);
+
+    assert_eq!(fogged.iter_mages().count(), 0);
+    assert!(fogged.powerups().is_empty());
+}
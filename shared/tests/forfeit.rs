@@ -0,0 +1,89 @@
+mod common;
+
+use std::time::Duration;
+
+use common::distant_level;
+use shared::{
+    timestamp, Game, GameResult, LoadoutMethod, Lobby, LobbySettings, MoveError, Position, Team,
+};
+
+fn full_lobby() -> Lobby {
+    let mut lobby = Lobby::new(
+        LobbySettings {
+            loadout_method: LoadoutMethod::Prefab(distant_level()),
+            ..Default::default()
+        },
+        Duration::ZERO,
+    );
+
+    lobby
+        .join_player("red".to_string(), "r".to_string())
+        .unwrap();
+    lobby
+        .join_player("blue".to_string(), "b".to_string())
+        .unwrap();
+
+    lobby
+}
+
+#[test]
+fn forfeit_ends_the_game() {
+    let mut game = Game::new(&distant_level(), false).unwrap();
+
+    game.forfeit(Team::Red);
+
+    assert_eq!(game.forfeited(), Some(Team::Red));
+    assert!(game.result() == Some(GameResult::Win(Team::Blue)));
+    assert_eq!(
+        game.take_move_checked(Position(0, 0), Position(0, 1)),
+        Err(MoveError::GameOver)
+    );
+}
+
+#[test]
+fn disconnected_player_forfeits() {
+    let mut lobby = full_lobby();
+    let timeout = Duration::from_secs(60);
+
+    assert_eq!(lobby.forfeit_disconnected(timestamp(), timeout), None);
+
+    lobby.beat_heart("blue".to_string());
+
+    assert_eq!(
+        lobby.forfeit_disconnected(timestamp(), timeout),
+        Some(Team::Red)
+    );
+    assert!(lobby.finished());
+    assert!(lobby.game.result() == Some(GameResult::Win(Team::Blue)));
+    assert_eq!(lobby.forfeit_disconnected(timestamp(), timeout), None);
+}
+
+#[test]
+fn leaving_frees_slots_until_the_game_starts() {
+    let mut lobby = Lobby::new(
+        LobbySettings {
+            loadout_method: LoadoutMethod::Prefab(distant_level()),
+            ..Default::default()
+        },
+        Duration::ZERO,
+    );
+
+    lobby
+        .join_player("red".to_string(), "r".to_string())
+        .unwrap();
+
+    assert_eq!(lobby.leave_player("red").unwrap(), Team::Red);
+    assert!(lobby.players().is_empty());
+    assert_eq!(
+        lobby
+            .join_player("other".to_string(), "o".to_string())
+            .unwrap(),
+        Team::Red
+    );
+
+    let mut lobby = full_lobby();
+
+    assert_eq!(lobby.leave_player("blue").unwrap(), Team::Blue);
+    assert_eq!(lobby.players().len(), 2);
+    assert!(lobby.leave_player("nobody").is_err());
+}
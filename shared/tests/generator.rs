@@ -0,0 +1,127 @@
+use shared::{
+    GeneratorParams, Level, LevelIssue, LoadoutMethod, Lobby, LobbySettings, Symmetry, Team,
+};
+
+fn params(symmetry: Symmetry) -> GeneratorParams {
+    GeneratorParams {
+        width: 8,
+        height: 8,
+        mages: 4,
+        powerup_density: 30,
+        symmetry,
+    }
+}
+
+#[test]
+fn generates_reproducibly() {
+    for seed in 0..8 {
+        let level = Level::generate(&params(Symmetry::Point), seed).unwrap();
+
+        assert_eq!(
+            level.as_code().unwrap(),
+            Level::generate(&params(Symmetry::Point), seed)
+                .unwrap()
+                .as_code()
+                .unwrap()
+        );
+    }
+
+    assert_ne!(
+        Level::generate(&params(Symmetry::Point), 0)
+            .unwrap()
+            .as_code()
+            .unwrap(),
+        Level::generate(&params(Symmetry::Point), 1)
+            .unwrap()
+            .as_code()
+            .unwrap()
+    );
+}
+
+#[test]
+fn generates_fair_levels() {
+    for symmetry in [Symmetry::Point, Symmetry::Mirror, Symmetry::None] {
+        for seed in 0..32 {
+            let level = Level::generate(&params(symmetry), seed).unwrap();
+            let issues = level.validate();
+
+            assert!(
+                !issues
+                    .iter()
+                    .any(|issue| issue.is_error()
+                        || matches!(issue, LevelIssue::UnreachableTiles(_))),
+                "{symmetry:?} {seed}: {issues:?}"
+            );
+
+            for team in [Team::Red, Team::Blue] {
+                assert_eq!(
+                    level.mages.iter().filter(|mage| mage.team == team).count(),
+                    4
+                );
+            }
+
+            if let Some(position) = symmetry.counterpart(&level.board, level.mages[0].position) {
+                assert!(level
+                    .mages
+                    .iter()
+                    .any(|mage| mage.position == position && mage.team == Team::Blue));
+
+                for (position, powerup) in &level.powerups {
+                    assert_eq!(
+                        level
+                            .powerups
+                            .get(&symmetry.counterpart(&level.board, *position).unwrap()),
+                        Some(powerup)
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn point_symmetric_levels_pass_lint() {
+    for seed in 0..8 {
+        assert_eq!(
+            Level::generate(&params(Symmetry::Point), seed)
+                .unwrap()
+                .validate(),
+            vec![]
+        );
+    }
+}
+
+#[test]
+fn rejects_invalid_params() {
+    let mut invalid = params(Symmetry::Point);
+    invalid.mages = 0;
+    assert!(Level::generate(&invalid, 0).is_err());
+
+    invalid.mages = 25;
+    assert!(Level::generate(&invalid, 0).is_err());
+
+    invalid.mages = 4;
+    invalid.width = 17;
+    assert!(invalid.validate().is_err());
+
+    invalid.width = 8;
+    invalid.powerup_density = 101;
+    assert!(invalid.validate().is_err());
+}
+
+#[test]
+fn lobbies_generate_from_seed() {
+    let settings = LobbySettings {
+        loadout_method: LoadoutMethod::Generated {
+            params: params(Symmetry::Mirror),
+        },
+        seed: 42,
+        ..Default::default()
+    };
+
+    let lobby = Lobby::new(settings.clone(), Default::default());
+    let other = Lobby::new(settings, Default::default());
+
+    assert_eq!(lobby.game.board_size(), (8, 8));
+    assert_eq!(lobby.game.prototype_code(), other.game.prototype_code());
+}
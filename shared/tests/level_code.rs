@@ -0,0 +1,193 @@
+mod common;
+
+use shared::{
+    Board, BoardStyle, Difficulty, Level, LevelCodeError, LevelMetadata, MageSort, Mana, Position,
+    PowerUp, Team, BASE32,
+};
+
+#[test]
+fn decodes_arena_codes() {
+    let codes = common::arena_codes();
+
+    assert_eq!(codes.len(), 28);
+
+    for code in codes {
+        let level = Level::from_code(&code).unwrap();
+        let legacy_code = level.as_legacy_code().unwrap();
+
+        assert_eq!(
+            Level::from_code(&legacy_code)
+                .unwrap()
+                .as_legacy_code()
+                .unwrap(),
+            legacy_code
+        );
+        assert_eq!(
+            Level::from_code(&level.as_code().unwrap())
+                .unwrap()
+                .as_legacy_code()
+                .unwrap(),
+            legacy_code
+        );
+    }
+}
+
+#[test]
+fn detects_corrupted_codes() {
+    let code = Level::from_code(&common::arena_codes()[0])
+        .unwrap()
+        .as_code()
+        .unwrap();
+
+    let mut corrupted = code.clone().into_bytes();
+    let index = corrupted.len() / 2;
+    corrupted[index] = if corrupted[index] == b'0' { b'1' } else { b'0' };
+
+    assert_eq!(
+        Level::from_code(std::str::from_utf8(&corrupted).unwrap()).unwrap_err(),
+        LevelCodeError::ChecksumMismatch
+    );
+    assert_eq!(
+        Level::from_code("not a level!").unwrap_err(),
+        LevelCodeError::InvalidEncoding
+    );
+}
+
+#[test]
+fn rejects_truncated_codes() {
+    for code in common::arena_codes() {
+        let bytes = BASE32.decode(code.as_bytes()).unwrap();
+
+        for length in 0..bytes.len() {
+            assert!(Level::from_code(&BASE32.encode(&bytes[..length])).is_err());
+        }
+    }
+}
+
+#[test]
+fn rejects_unknown_versions() {
+    assert_eq!(
+        Level::from_code(&BASE32.encode(&[4, 0, 0, 0])).unwrap_err(),
+        LevelCodeError::UnsupportedVersion(4)
+    );
+}
+
+#[test]
+fn encodes_large_styled_boards() {
+    let mut level = common::level(
+        (16, 12),
+        &[
+            (Team::Red, MageSort::Diamond, Position(15, 11)),
+            (Team::Blue, MageSort::Knight, Position(0, 0)),
+        ],
+        &[(Position(9, 10), PowerUp::Beam)],
+    );
+    level.board.style = BoardStyle::Flesh;
+    level.starting_team = Team::Blue;
+
+    let decoded = Level::from_code(&level.as_code().unwrap()).unwrap();
+
+    assert_eq!((decoded.board.width, decoded.board.height), (16, 12));
+    assert_eq!(decoded.board.style, BoardStyle::Flesh);
+    assert_eq!(decoded.starting_team, Team::Blue);
+    assert_eq!(decoded.mages[0].position, Position(15, 11));
+    assert!(matches!(decoded.mages[1].sort, MageSort::Knight));
+    assert_eq!(decoded.powerups.get(&Position(9, 10)), Some(&PowerUp::Beam));
+    assert_eq!(decoded.as_code().unwrap(), level.as_code().unwrap());
+
+    assert!(Board::new(17, 8).is_err());
+}
+
+#[test]
+fn refuses_levels_that_do_not_fit() {
+    let large = common::level(
+        (12, 8),
+        &[
+            (Team::Red, MageSort::Diamond, Position(10, 1)),
+            (Team::Blue, MageSort::Diamond, Position(1, 6)),
+        ],
+        &[],
+    );
+
+    assert_eq!(large.as_legacy_code(), Err(LevelCodeError::DoesNotFit));
+    assert!(large.as_code().is_ok());
+
+    let mut displaced = common::distant_level();
+    displaced.powerups.insert(Position(3, 9), PowerUp::Beam);
+
+    assert_eq!(displaced.as_legacy_code(), Err(LevelCodeError::DoesNotFit));
+
+    displaced.powerups.clear();
+    displaced.mages[1].position = Position(-1, 20);
+
+    assert_eq!(displaced.as_code(), Err(LevelCodeError::DoesNotFit));
+
+    let mut crowded = common::distant_level();
+    let mage = crowded.mages[0].clone();
+    crowded.mages.resize(256, mage);
+
+    assert_eq!(crowded.as_code(), Err(LevelCodeError::DoesNotFit));
+
+    let mut overcharged = common::distant_level();
+    overcharged.mages[0].mana.1 = 16;
+
+    assert_eq!(overcharged.as_code(), Err(LevelCodeError::DoesNotFit));
+    assert_eq!(
+        overcharged.as_legacy_code(),
+        Err(LevelCodeError::DoesNotFit)
+    );
+
+    overcharged.mages[0].mana = Mana(16, 3);
+
+    assert_eq!(overcharged.as_code(), Err(LevelCodeError::DoesNotFit));
+
+    overcharged.mages[0].mana = Mana(15, 15);
+    let code = overcharged.as_code().unwrap();
+    let decoded = Level::from_code(&code).unwrap();
+
+    assert_eq!(decoded.mages[0].mana.0, 15);
+    assert_eq!(decoded.mages[0].mana.1, 15);
+}
+
+#[test]
+fn round_trips_metadata() {
+    let metadata = LevelMetadata {
+        title: Some("Basics I".to_string()),
+        author: Some("Ævar".to_string()),
+        description: None,
+        difficulty: Some(Difficulty::Hard),
+        par: Some(12),
+    };
+    let level = Level::from_code(&common::arena_codes()[0])
+        .unwrap()
+        .with_metadata(metadata.clone());
+
+    let decoded = Level::from_code(&level.as_code().unwrap()).unwrap();
+
+    assert_eq!(decoded.metadata, metadata);
+    assert_eq!(decoded.as_code().unwrap(), level.as_code().unwrap());
+
+    let deserialized: Level =
+        serde_json::from_str(&serde_json::to_string(&level).unwrap()).unwrap();
+
+    assert_eq!(deserialized.metadata, metadata);
+
+    let plain = Level::from_code(&common::arena_codes()[0]).unwrap();
+
+    assert!(plain.as_code().unwrap().len() < level.as_code().unwrap().len());
+}
+
+#[test]
+fn truncates_long_metadata() {
+    let level = Level::default().with_metadata(LevelMetadata {
+        description: Some("é".repeat(200)),
+        ..Default::default()
+    });
+
+    let decoded = Level::from_code(&level.as_code().unwrap()).unwrap();
+
+    assert_eq!(
+        decoded.metadata.description,
+        Some("é".repeat(LevelMetadata::MAX_TEXT_LENGTH / 2))
+    );
+}
@@ -0,0 +1,142 @@
+mod common;
+
+use shared::{
+    BoardStyle, BoulderStyle, Difficulty, Level, LevelMetadata, LevelTextError, Position, PowerUp,
+    Team,
+};
+
+#[test]
+fn round_trips_arena_levels() {
+    for code in common::arena_codes() {
+        let level = Level::from_code(&code).unwrap();
+        let text = level.as_text();
+        let parsed = Level::from_text(&text).unwrap();
+
+        assert_eq!(
+            parsed.as_code().unwrap(),
+            level.as_code().unwrap(),
+            "{text}"
+        );
+        assert_eq!(parsed.as_text(), text);
+        assert_eq!(
+            Level::from_text(&level.as_text_line())
+                .unwrap()
+                .as_code()
+                .unwrap(),
+            level.as_code().unwrap()
+        );
+    }
+}
+
+#[test]
+fn parses_hand_written_text() {
+    let level = Level::from_text(
+        "team: blue
+style: desert
+title: Two lines\\nof title
+difficulty: hard
+par: 6
+mage 1: knight
+mage 2: plus 2/4
+powerup: 1 0 beam
+powerup: 0 2 rock
+
+B2 .  .  #t
+.  #  .  .
+R1 .  .  .
+.  .  .  .
+",
+    )
+    .unwrap();
+
+    assert_eq!(level.starting_team, Team::Blue);
+    assert_eq!(level.board.style, BoardStyle::Desert);
+    assert_eq!((level.board.width, level.board.height), (4, 4));
+    assert_eq!(
+        level.metadata,
+        LevelMetadata {
+            title: Some("Two lines\nof title".to_string()),
+            difficulty: Some(Difficulty::Hard),
+            par: Some(6),
+            ..Default::default()
+        }
+    );
+    assert_eq!(level.mages[0].team, Team::Red);
+    assert_eq!(level.mages[0].position, Position(0, 2));
+    assert_eq!(level.mages[1].position, Position(0, 0));
+    assert_eq!((level.mages[1].mana.0, level.mages[1].mana.1), (2, 4));
+    assert_eq!(level.powerups.get(&Position(1, 0)), Some(&PowerUp::Beam));
+    assert_eq!(
+        level.powerups.get(&Position(0, 2)),
+        Some(&PowerUp::Boulder(BoulderStyle::Rock))
+    );
+    assert_eq!(
+        level.powerups.get(&Position(3, 0)),
+        Some(&PowerUp::Boulder(BoulderStyle::Tentacle))
+    );
+    assert_eq!(
+        Level::from_text(&level.as_text())
+            .unwrap()
+            .as_code()
+            .unwrap(),
+        level.as_code().unwrap()
+    );
+}
+
+#[test]
+fn escapes_metadata_on_a_single_line() {
+    let level = Level::from_code(&common::arena_codes()[0])
+        .unwrap()
+        .with_metadata(LevelMetadata {
+            title: Some("Pipes | and \\ slashes".to_string()),
+            description: Some("First line\nsecond line".to_string()),
+            ..Default::default()
+        });
+
+    let parsed = Level::from_text(&level.as_text_line()).unwrap();
+
+    assert_eq!(parsed.metadata, level.metadata);
+    assert_eq!(parsed.as_code().unwrap(), level.as_code().unwrap());
+}
+
+#[test]
+fn rejects_malformed_text() {
+    let grid = "\nR1 .  .  .\n.  .  .  .\n.  .  .  .\n.  .  .  B2\n";
+
+    for (text, error) in [
+        (
+            format!("colour: red\nmage 1: cross\nmage 2: cross{grid}"),
+            LevelTextError::InvalidHeader(1),
+        ),
+        (
+            format!("mage 1: cross\nmage 2: wizard{grid}"),
+            LevelTextError::InvalidHeader(2),
+        ),
+        (
+            "mage 1: cross\n\nR1 . .\n. .\n. . .\n. . .".to_string(),
+            LevelTextError::RaggedRow(4),
+        ),
+        (
+            "mage 1: cross\n\nR1 . .\n. ? .\n. . .".to_string(),
+            LevelTextError::InvalidCell(4),
+        ),
+        (
+            "mage 1: cross\n\nR1 .\n. .".to_string(),
+            LevelTextError::InvalidBoard,
+        ),
+        (
+            format!("mage 1: cross{grid}"),
+            LevelTextError::MissingMage(2),
+        ),
+        (
+            format!("mage 1: cross\nmage 1: spike{grid}"),
+            LevelTextError::DuplicateMage(1),
+        ),
+        (
+            format!("mage 1: cross\nmage 2: cross\npowerup: 4 0 shield{grid}"),
+            LevelTextError::OutOfBounds,
+        ),
+    ] {
+        assert_eq!(Level::from_text(&text).err(), Some(error), "{text}");
+    }
+}
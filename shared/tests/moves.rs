@@ -0,0 +1,75 @@
+mod common;
+
+use shared::{BoulderStyle, Game, GameEvent, Level, MageSort, MoveError, Position, PowerUp, Team};
+
+fn level() -> Level {
+    let mut level = common::level(
+        (8, 8),
+        &[
+            (Team::Red, MageSort::Diamond, Position(0, 0)),
+            (Team::Blue, MageSort::Diamond, Position(7, 7)),
+            (Team::Red, MageSort::Knight, Position(3, 3)),
+        ],
+        &[(Position(1, 0), PowerUp::Boulder(BoulderStyle::Rock))],
+    );
+    level.mages[2].mana.0 = 0;
+
+    level
+}
+
+#[test]
+fn explains_illegal_moves() {
+    let game = Game::new(&level(), false).unwrap();
+
+    let cases = [
+        (Position(5, 5), Position(5, 6), MoveError::NoMage),
+        (Position(3, 3), Position(3, 4), MoveError::MageAsleep),
+        (Position(7, 7), Position(7, 6), MoveError::NotYourTurn),
+        (Position(0, 0), Position(0, 2), MoveError::OutOfReach),
+        (Position(0, 0), Position(-1, 0), MoveError::OutOfReach),
+        (
+            Position(0, 0),
+            Position(1, 0),
+            MoveError::DestinationBlocked,
+        ),
+        (
+            Position(0, 0),
+            Position(1, 1),
+            MoveError::DiagonalNotAllowed,
+        ),
+    ];
+
+    for (from, to, error) in cases {
+        assert_eq!(game.check_move(from, to), Err(error));
+    }
+
+    assert_eq!(game.check_move(Position(0, 0), Position(0, 1)), Ok(()));
+}
+
+#[test]
+fn checked_moves_change_the_game() {
+    let mut game = Game::new(&level(), false).unwrap();
+
+    assert_eq!(
+        game.take_move_checked(Position(7, 7), Position(7, 6)),
+        Err(MoveError::NotYourTurn)
+    );
+    assert_eq!(game.turns(), 0);
+
+    assert_eq!(
+        game.take_move_checked(Position(0, 0), Position(0, 1)),
+        Ok(vec![GameEvent::Moved {
+            mage: 0,
+            from: Position(0, 0),
+            to: Position(0, 1)
+        }])
+    );
+    assert_eq!(game.turns(), 1);
+
+    game.agree_draw();
+
+    assert_eq!(
+        game.take_move_checked(Position(7, 7), Position(7, 6)),
+        Err(MoveError::GameOver)
+    );
+}
@@ -0,0 +1,141 @@
+mod common;
+
+use common::distant_level;
+use shared::{
+    Game, GameResult, LoadoutMethod, Lobby, LobbySettings, MageSort, Message, Position, PowerUp,
+    Team, Turn,
+};
+
+fn oscillate(game: &mut Game) {
+    game.take_move(Position(0, 0), Position(1, 0)).unwrap();
+    game.take_move(Position(7, 7), Position(6, 7)).unwrap();
+    game.take_move(Position(1, 0), Position(0, 0)).unwrap();
+    game.take_move(Position(6, 7), Position(7, 7)).unwrap();
+}
+
+#[test]
+fn threefold_repetition_draws() {
+    let mut game = Game::new(&distant_level(), true).unwrap();
+    let initial_hash = game.position_hash();
+
+    oscillate(&mut game);
+
+    assert_eq!(game.position_hash(), initial_hash);
+    assert_eq!(game.repetitions(), 2);
+    assert!(game.result().is_none());
+
+    oscillate(&mut game);
+
+    assert_eq!(game.repetitions(), 3);
+    assert!(game.result() == Some(GameResult::Stalemate));
+    assert!(game.take_move(Position(0, 0), Position(1, 0)).is_none());
+}
+
+#[test]
+fn repetition_ignored_without_stalemates() {
+    let mut game = Game::new(&distant_level(), false).unwrap();
+
+    oscillate(&mut game);
+    oscillate(&mut game);
+
+    assert_eq!(game.repetitions(), 3);
+    assert!(game.result().is_none());
+}
+
+#[test]
+fn hash_ignores_mage_order() {
+    let level = common::level(
+        (8, 8),
+        &[
+            (Team::Blue, MageSort::Diamond, Position(7, 7)),
+            (Team::Red, MageSort::Diamond, Position(0, 0)),
+        ],
+        &[],
+    );
+    let mut game = Game::new(&level, true).unwrap();
+    let hash = game.position_hash();
+
+    game.sort_mages();
+
+    assert_eq!(game.iter_mages().next().unwrap().team, Team::Red);
+    assert_eq!(game.position_hash(), hash);
+}
+
+#[test]
+fn agreed_draw_ends_game() {
+    let mut game = Game::new(&distant_level(), false).unwrap();
+
+    game.agree_draw();
+
+    assert!(game.is_drawn());
+    assert!(game.result() == Some(GameResult::Stalemate));
+}
+
+#[test]
+fn rewinding_past_agreed_draw_clears_it() {
+    let mut game = Game::new(&distant_level(), false).unwrap();
+
+    game.take_move(Position(0, 0), Position(1, 0)).unwrap();
+    game.take_move(Position(7, 7), Position(6, 7)).unwrap();
+    game.agree_draw();
+
+    assert!(game.rewind(0).is_drawn());
+    assert!(game.rewind(0).result() == Some(GameResult::Stalemate));
+
+    let rewound = game.rewind(1);
+
+    assert!(!rewound.is_drawn());
+    assert!(rewound.result().is_none());
+    assert_eq!(rewound.turns(), 1);
+}
+
+#[test]
+fn draw_cannot_be_agreed_after_a_win() {
+    let mut level = common::level(
+        (8, 8),
+        &[
+            (Team::Red, MageSort::Diamond, Position(0, 3)),
+            (Team::Blue, MageSort::Diamond, Position(3, 3)),
+        ],
+        &[(Position(1, 3), PowerUp::Beam)],
+    );
+    level.mages[1].mana.0 = 1;
+
+    let mut lobby = Lobby::new(
+        LobbySettings {
+            loadout_method: LoadoutMethod::Prefab(level),
+            ..Default::default()
+        },
+        Default::default(),
+    );
+
+    lobby
+        .join_player("red".to_string(), "r".to_string())
+        .unwrap();
+    lobby
+        .join_player("blue".to_string(), "b".to_string())
+        .unwrap();
+
+    lobby
+        .act_player("red".to_string(), Message::OfferDraw)
+        .unwrap();
+    lobby
+        .act_player(
+            "red".to_string(),
+            Message::Turn(Turn(Position(0, 3), Position(1, 3))),
+        )
+        .unwrap();
+
+    assert_eq!(lobby.draw_offer(), None);
+    assert!(lobby
+        .act_player("blue".to_string(), Message::AcceptDraw)
+        .is_err());
+    assert!(lobby
+        .act_player("blue".to_string(), Message::DeclineDraw)
+        .is_err());
+    assert!(lobby.game.result() == Some(GameResult::Win(Team::Red)));
+
+    lobby.game.agree_draw();
+
+    assert!(lobby.game.result() == Some(GameResult::Win(Team::Red)));
+}
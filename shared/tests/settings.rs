@@ -0,0 +1,177 @@
+mod common;
+
+use serde_json::{json, Value};
+use shared::{
+    Board, BoulderStyle, GeneratorParams, LevelIssue, LoadoutMethod, LobbySettings, MageSort,
+    Position, PowerUp, SettingsError, Spell, Team,
+};
+
+fn prefab() -> LobbySettings {
+    LobbySettings {
+        loadout_method: LoadoutMethod::Prefab(common::level(
+            (6, 6),
+            &[
+                (Team::Red, MageSort::Diamond, Position(1, 4)),
+                (Team::Blue, MageSort::Diamond, Position(4, 1)),
+            ],
+            &[(Position(2, 2), PowerUp::Boulder(BoulderStyle::Rock))],
+        )),
+        ..Default::default()
+    }
+}
+
+/// Serialises settings, lets a payload be tampered with, and deserialises it like the server does.
+fn tampered(settings: LobbySettings, tamper: impl FnOnce(&mut Value)) -> LobbySettings {
+    let mut value = serde_json::to_value(settings).unwrap();
+    tamper(&mut value);
+
+    serde_json::from_value(value).unwrap()
+}
+
+#[test]
+fn accepts_valid_settings() {
+    assert!(LobbySettings::default().normalized(0).is_ok());
+    assert!(prefab().normalized(0).is_ok());
+}
+
+#[test]
+fn rejects_boards_beyond_limits() {
+    let settings = tampered(
+        LobbySettings {
+            loadout_method: LoadoutMethod::DefaultBoard(Board::new(8, 8).unwrap()),
+            ..Default::default()
+        },
+        |value| value["loadout_method"]["DefaultBoard"]["width"] = json!(1000),
+    );
+
+    assert!(matches!(
+        settings.normalized(0),
+        Err(SettingsError::InvalidBoard(_))
+    ));
+
+    let settings = tampered(prefab(), |value| {
+        value["loadout_method"]["Prefab"]["board"]["height"] = json!(2)
+    });
+
+    assert!(matches!(
+        settings.normalized(0),
+        Err(SettingsError::InvalidBoard(_))
+    ));
+}
+
+#[test]
+fn rejects_unplayable_levels() {
+    let settings = tampered(prefab(), |value| {
+        value["loadout_method"]["Prefab"]["mages"][0]["position"] = json!([40, 2])
+    });
+
+    assert_eq!(
+        settings.normalized(0).unwrap_err(),
+        SettingsError::InvalidLevel(LevelIssue::OutOfBounds(Position(40, 2)))
+    );
+
+    let settings = tampered(prefab(), |value| {
+        value["loadout_method"]["Prefab"]["mages"][0]["position"] = json!([2, 2])
+    });
+
+    assert_eq!(
+        settings.normalized(0).unwrap_err(),
+        SettingsError::InvalidLevel(LevelIssue::Overlap(Position(2, 2)))
+    );
+}
+
+#[test]
+fn rejects_too_many_mages() {
+    let settings = tampered(prefab(), |value| {
+        let mages = &mut value["loadout_method"]["Prefab"]["mages"];
+        let red = mages[0].clone();
+
+        for _ in 0..20 {
+            mages.as_array_mut().unwrap().push(red.clone());
+        }
+    });
+
+    assert_eq!(
+        settings.normalized(0).unwrap_err(),
+        SettingsError::TooManyMages(Team::Red)
+    );
+}
+
+#[test]
+fn rejects_invalid_mana_and_powerups() {
+    for mana in [json!([9, 4]), json!([200, 200])] {
+        let settings = tampered(prefab(), |value| {
+            value["loadout_method"]["Prefab"]["mages"][1]["mana"] = mana
+        });
+
+        assert_eq!(
+            settings.normalized(0).unwrap_err(),
+            SettingsError::InvalidMana(Position(4, 1))
+        );
+    }
+
+    let settings = tampered(prefab(), |value| {
+        value["loadout_method"]["Prefab"]["mages"][0]["powerup"] = json!({ "Boulder": "Rock" })
+    });
+
+    assert_eq!(
+        settings.normalized(0).unwrap_err(),
+        SettingsError::InvalidPowerUp(Position(1, 4))
+    );
+}
+
+#[test]
+fn rejects_invalid_generator_parameters() {
+    let settings = LobbySettings {
+        loadout_method: LoadoutMethod::Generated {
+            params: GeneratorParams {
+                powerup_density: 250,
+                ..Default::default()
+            },
+        },
+        ..Default::default()
+    };
+
+    assert!(matches!(
+        settings.normalized(0),
+        Err(SettingsError::InvalidGenerator(_))
+    ));
+}
+
+#[test]
+fn normalizes_mages_drafts_and_seed() {
+    let settings = tampered(prefab(), |value| {
+        value["seed"] = json!(7);
+        value["loadout_method"]["Prefab"]["mages"][1]["index"] = json!(0);
+        value["loadout_method"]["Prefab"]["mages"][1]["spell"]["pattern"] = json!([[0, 1]]);
+    })
+    .normalized(42)
+    .unwrap();
+
+    assert_eq!(settings.seed, 42);
+
+    let LoadoutMethod::Prefab(level) = settings.loadout_method else {
+        panic!("loadout method changed");
+    };
+
+    assert_eq!(level.mage_index, 2);
+    assert_eq!(level.mages[1].index, 1);
+    assert_eq!(
+        level.mages[1].spell.pattern,
+        Spell::select(MageSort::Diamond).pattern
+    );
+
+    let settings = LobbySettings {
+        loadout_method: LoadoutMethod::Draft {
+            board: Board::new(8, 8).unwrap(),
+            mages: 1000,
+        },
+        ..Default::default()
+    };
+
+    let LoadoutMethod::Draft { mages, .. } = settings.normalized(0).unwrap().loadout_method else {
+        panic!("loadout method changed");
+    };
+
+    assert_eq!(mages, 8 * 3);
+}
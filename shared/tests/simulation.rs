@@ -0,0 +1,123 @@
+mod common;
+
+use shared::{Level, MageSort, Position, PowerUp, Simulation, Team};
+
+fn lopsided_level() -> Level {
+    common::level(
+        (4, 4),
+        &[
+            (Team::Red, MageSort::Diamond, Position(0, 3)),
+            (Team::Red, MageSort::Cross, Position(3, 3)),
+            (Team::Blue, MageSort::Diamond, Position(1, 0)),
+        ],
+        &[(Position(1, 1), PowerUp::Beam)],
+    )
+}
+
+#[test]
+fn steps_through_games() {
+    let level = lopsided_level();
+    let mut simulation = Simulation::new(&level, 2, 0);
+
+    assert_eq!(simulation.progress(), (0, 2));
+
+    while !simulation.is_finished() {
+        simulation.step();
+    }
+
+    assert_eq!(simulation.progress(), (2, 2));
+
+    let report = simulation.report();
+
+    let rerun = level.simulation_report(2, 0);
+
+    assert_eq!(report.games, 2);
+    assert_eq!(report.red_wins, rerun.red_wins);
+    assert_eq!(report.average_length, rerun.average_length);
+
+    // Seeds near the maximum wrap around instead of overflowing.
+    assert_eq!(level.simulation_report(2, u64::MAX).games, 2);
+}
+
+#[test]
+fn merges_separate_simulations() {
+    let level = lopsided_level();
+    let mut first = Simulation::new(&level, 1, 0);
+    let mut second = Simulation::new(&level, 2, 1);
+
+    first.play_out();
+    second.play_out();
+    first.merge(second);
+
+    assert_eq!(first.progress(), (3, 3));
+    assert!(first.is_finished());
+
+    let report = first.report();
+
+    assert_eq!(report.games, 3);
+    assert_eq!(report.red_wins + report.blue_wins + report.draws, 3);
+    assert_eq!(report.decisive_mages.len(), 3);
+}
+
+#[test]
+fn reports_lopsided_level() {
+    let report = lopsided_level().simulation_report(4, 0);
+
+    assert_eq!(report.red_wins + report.blue_wins + report.draws, 4);
+    assert!((report.red_rate() + report.blue_rate() + report.draw_rate() - 1.0).abs() < 1e-9);
+    assert!(report.red_wins > report.blue_wins);
+    assert!(report.first_move_advantage > 0.0);
+    assert!(report.average_length > 0.0);
+
+    let (low, high) = report.red_win_interval;
+    assert!(low <= report.red_rate() && report.red_rate() <= high);
+    assert!(0.0 <= low && high <= 1.0);
+
+    assert_eq!(report.decisive_mages.len(), 3);
+    assert!(report
+        .decisive_mages
+        .windows(2)
+        .all(|pair| pair[0].damage >= pair[1].damage));
+    assert!(report.decisive_mages[0].damage > 0.0);
+
+    for impact in &report.decisive_powerups {
+        assert_eq!(impact.position, Position(1, 1));
+        assert!(0.0 < impact.pickup_rate && impact.pickup_rate <= 1.0);
+    }
+}
+
+#[test]
+fn credits_mages_after_one_is_removed() {
+    let mut level = common::level(
+        (4, 4),
+        &[
+            (Team::Red, MageSort::Diamond, Position(0, 3)),
+            (Team::Blue, MageSort::Cross, Position(2, 1)),
+            (Team::Red, MageSort::Cross, Position(3, 3)),
+            (Team::Blue, MageSort::Diamond, Position(1, 0)),
+        ],
+        &[(Position(1, 1), PowerUp::Beam)],
+    );
+
+    // The editor removes mages without renumbering the rest.
+    level.mages.retain(|mage| mage.index != 1);
+
+    let report = level.simulation_report(4, 0);
+    let expected = lopsided_level().simulation_report(4, 0);
+
+    assert_eq!(report.decisive_mages.len(), 3);
+
+    for (impact, expected) in report.decisive_mages.iter().zip(&expected.decisive_mages) {
+        assert_eq!(impact.position, expected.position);
+        assert_eq!(impact.damage, expected.damage);
+    }
+}
+
+#[test]
+fn reports_nothing_without_games() {
+    let report = lopsided_level().simulation_report(0, 0);
+
+    assert_eq!(report.games, 0);
+    assert_eq!(report.red_rate(), 0.0);
+    assert!(report.decisive_mages.is_empty());
+}
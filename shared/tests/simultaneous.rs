@@ -0,0 +1,137 @@
+mod common;
+
+use shared::{
+    Game, GameEvent, GameResult, LoadoutMethod, Lobby, LobbySettings, MageSort, Mages, Message,
+    Position, Team, Turn,
+};
+
+fn duel(red: Position, blue: Position) -> Game {
+    let level = common::level(
+        (8, 8),
+        &[
+            (Team::Red, MageSort::Diamond, red),
+            (Team::Blue, MageSort::Diamond, blue),
+        ],
+        &[],
+    );
+
+    Game::new_simultaneous(&level, false).unwrap()
+}
+
+fn mana(game: &Game, index: usize) -> u8 {
+    game.get_mage(index).unwrap().mana.0
+}
+
+#[test]
+fn pending_turn_is_not_applied() {
+    let mut game = duel(Position(1, 3), Position(5, 3));
+
+    assert_eq!(
+        game.take_move(Position(1, 3), Position(2, 3)),
+        Some(Vec::new())
+    );
+    assert!(game.live_occupied(&Position(1, 3)));
+    assert!(!game.live_occupied(&Position(2, 3)));
+    assert_eq!(game.turn_for(), Team::Blue);
+}
+
+#[test]
+fn same_destination_bounces() {
+    let mut game = duel(Position(2, 3), Position(4, 3));
+
+    game.take_move(Position(2, 3), Position(3, 3)).unwrap();
+    let events = game.take_move(Position(4, 3), Position(3, 3)).unwrap();
+
+    assert!(events.is_empty());
+    assert!(game.live_occupied_by(&Position(2, 3), Team::Red));
+    assert!(game.live_occupied_by(&Position(4, 3), Team::Blue));
+    assert!(!game.live_occupied(&Position(3, 3)));
+    assert_eq!(game.turns(), 2);
+}
+
+#[test]
+fn mutual_attacks_hit_both() {
+    let mut game = duel(Position(1, 3), Position(5, 3));
+    let (red_mana, blue_mana) = (mana(&game, 0), mana(&game, 1));
+
+    game.take_move(Position(1, 3), Position(2, 3)).unwrap();
+    let events = game.take_move(Position(5, 3), Position(4, 3)).unwrap();
+
+    assert_eq!(events.iter().filter_map(GameEvent::hit).count(), 2);
+    assert_eq!(mana(&game, 0), red_mana - 1);
+    assert_eq!(mana(&game, 1), blue_mana - 1);
+
+    let replayed = game.rewind(0);
+
+    assert_eq!(mana(&replayed, 0), red_mana - 1);
+    assert_eq!(mana(&replayed, 1), blue_mana - 1);
+}
+
+/// Turns of eight rounds in which the red mage walks down the left edge and the blue one up the right edge, never meeting or repeating a position.
+fn quiet_rounds() -> Vec<(Turn, Turn)> {
+    let red: Vec<Position> = (0..8)
+        .map(|y| Position(0, y))
+        .chain([Position(1, 7)])
+        .collect();
+    let blue: Vec<Position> = (0..8)
+        .rev()
+        .map(|y| Position(7, y))
+        .chain([Position(6, 0)])
+        .collect();
+
+    red.windows(2)
+        .zip(blue.windows(2))
+        .map(|(red, blue)| (Turn(red[0], red[1]), Turn(blue[0], blue[1])))
+        .collect()
+}
+
+#[test]
+fn stalemate_waits_for_the_round_to_resolve() {
+    let level = common::distant_level();
+    let mut game = Game::new_simultaneous(&level, true).unwrap();
+
+    for (Turn(red_from, red_to), Turn(blue_from, blue_to)) in quiet_rounds() {
+        game.take_move_checked(red_from, red_to).unwrap();
+        assert!(game.result().is_none());
+        game.take_move_checked(blue_from, blue_to).unwrap();
+    }
+
+    assert_eq!(game.turns(), 16);
+    assert!(game.result() == Some(GameResult::Stalemate));
+}
+
+#[test]
+fn lobby_resolves_whole_rounds() {
+    let mut lobby = Lobby::new(
+        LobbySettings {
+            loadout_method: LoadoutMethod::Prefab(common::distant_level()),
+            can_stalemate: true,
+            simultaneous: true,
+            ..Default::default()
+        },
+        Default::default(),
+    );
+
+    lobby
+        .join_player("red".to_string(), "r".to_string())
+        .unwrap();
+    lobby
+        .join_player("blue".to_string(), "b".to_string())
+        .unwrap();
+
+    for (red, blue) in quiet_rounds() {
+        assert!(!lobby.finished());
+
+        lobby
+            .act_player("blue".to_string(), Message::Turn(blue))
+            .unwrap();
+        lobby
+            .act_player("red".to_string(), Message::Turn(red))
+            .unwrap();
+
+        assert!(lobby.game.turns().is_multiple_of(2));
+    }
+
+    assert_eq!(lobby.game.turns(), 16);
+    assert!(lobby.finished());
+}
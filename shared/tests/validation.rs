@@ -0,0 +1,124 @@
+mod common;
+
+use std::collections::BTreeMap;
+
+use shared::{
+    Board, BoulderStyle, IssueSeverity, Level, LevelIssue, Mage, MageSort, Position, PowerUp, Team,
+};
+
+fn symmetric_level() -> Level {
+    common::level(
+        (6, 6),
+        &[
+            (Team::Red, MageSort::Diamond, Position(1, 4)),
+            (Team::Blue, MageSort::Diamond, Position(4, 1)),
+        ],
+        &[
+            (Position(2, 2), PowerUp::Boulder(BoulderStyle::Rock)),
+            (Position(3, 3), PowerUp::Boulder(BoulderStyle::Rock)),
+        ],
+    )
+}
+
+#[test]
+fn accepts_symmetric_level() {
+    assert_eq!(symmetric_level().validate(), vec![]);
+}
+
+#[test]
+fn accepts_arena_levels() {
+    for portal in &common::arena().portals {
+        let issues = portal.level().unwrap().validate();
+
+        assert!(
+            !issues.iter().any(LevelIssue::is_error),
+            "{}: {issues:?}",
+            portal.title
+        );
+    }
+}
+
+#[test]
+fn reports_overlaps_and_duplicates() {
+    let mut level = symmetric_level();
+    level.mages[0].position = Position(2, 2);
+    level
+        .mages
+        .push(Mage::new(2, Team::Red, MageSort::Cross, Position(4, 1)));
+
+    let issues = level.validate();
+
+    assert!(issues.contains(&LevelIssue::Overlap(Position(2, 2))));
+    assert!(issues.contains(&LevelIssue::DuplicatePosition(Position(4, 1))));
+    assert_eq!(issues[0].severity(), IssueSeverity::Error);
+}
+
+#[test]
+fn reports_pieces_off_the_board() {
+    let mut level = symmetric_level();
+    level.board = Board::new(4, 4).unwrap();
+
+    let issues = level.validate();
+
+    assert!(issues.contains(&LevelIssue::OutOfBounds(Position(4, 1))));
+    assert!(!issues.contains(&LevelIssue::OutOfBounds(Position(3, 3))));
+}
+
+#[test]
+fn reports_missing_team() {
+    let mut level = symmetric_level();
+    level.mages.retain(|mage| mage.team == Team::Red);
+
+    assert!(level
+        .validate()
+        .contains(&LevelIssue::MissingTeam(Team::Blue)));
+}
+
+#[test]
+fn reports_starting_team_without_moves() {
+    let level = common::level(
+        (4, 4),
+        &[
+            (Team::Red, MageSort::Diamond, Position(0, 0)),
+            (Team::Blue, MageSort::Diamond, Position(3, 3)),
+        ],
+        &[
+            (Position(1, 0), PowerUp::Boulder(BoulderStyle::Rock)),
+            (Position(0, 1), PowerUp::Boulder(BoulderStyle::Rock)),
+        ],
+    );
+
+    let issues = level.validate();
+
+    assert!(issues.contains(&LevelIssue::NoLegalMoves(Team::Red)));
+    assert!(issues.contains(&LevelIssue::Asymmetry(vec![Position(0, 1), Position(1, 0)])));
+}
+
+#[test]
+fn warns_about_unreachable_tiles() {
+    let mut level = symmetric_level();
+    level.mages[0].position = Position(1, 1);
+    level.mages[1].position = Position(4, 4);
+    level.powerups = BTreeMap::from([
+        (Position(1, 0), PowerUp::Boulder(BoulderStyle::Rock)),
+        (Position(0, 1), PowerUp::Boulder(BoulderStyle::Rock)),
+        (Position(4, 5), PowerUp::Boulder(BoulderStyle::Rock)),
+        (Position(5, 4), PowerUp::Boulder(BoulderStyle::Rock)),
+    ]);
+
+    let issues = level.validate();
+
+    assert_eq!(
+        issues,
+        vec![LevelIssue::UnreachableTiles(vec![
+            Position(0, 0),
+            Position(5, 5)
+        ])]
+    );
+    assert!(!issues[0].is_error());
+
+    level.powerups.insert(Position(2, 3), PowerUp::Diagonal);
+    level.powerups.insert(Position(3, 2), PowerUp::Diagonal);
+
+    assert_eq!(level.validate(), vec![]);
+}
@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
-use shared::{Board, Level, LobbyError, SessionRequest};
+use shared::{Board, Campaign, Level, LobbyError, SessionToken};
 use wasm_bindgen::JsValue;
 use web_sys::{
     console, CanvasRenderingContext2d, DomRectReadOnly, FocusEvent, HtmlCanvasElement,
@@ -15,7 +15,7 @@ use super::{
 use crate::{
     app::State,
     draw::{draw_board, draw_sprite},
-    net::get_session_id,
+    net::{get_session_id, get_session_token, load_rejoin_token},
     storage, window,
 };
 
@@ -43,11 +43,14 @@ pub enum StateSort {
 
 pub struct AppContext {
     pub session_id: Option<String>,
+    /// Signed token authenticating the session with the server.
+    pub session_token: Option<String>,
     pub pointer: Pointer,
     pub frame: u64,
     pub canvas_settings: CanvasSettings,
     pub text_input: Option<(String, String)>,
     pub audio_system: AudioSystem,
+    pub campaign: Campaign,
 }
 
 pub struct App {
@@ -57,17 +60,29 @@ pub struct App {
 }
 
 impl App {
-    pub fn new(canvas_settings: &CanvasSettings, audio_system: AudioSystem) -> App {
+    pub fn new(
+        canvas_settings: &CanvasSettings,
+        audio_system: AudioSystem,
+        campaign: Campaign,
+    ) -> App {
         App {
             app_context: AppContext {
                 session_id: get_session_id(),
+                session_token: get_session_token(),
                 pointer: Pointer::new(canvas_settings),
                 frame: 0,
                 canvas_settings: canvas_settings.clone(),
                 text_input: None,
                 audio_system,
+                campaign,
+            },
+            // An online game left in progress, for example by reloading the page, is restored.
+            state_sort: match load_rejoin_token() {
+                Some((lobby_id, rejoin_token)) => {
+                    StateSort::Game(Game::rejoin(lobby_id, rejoin_token))
+                }
+                None => StateSort::MainMenu(MainMenu::default()),
             },
-            state_sort: StateSort::MainMenu(MainMenu::default()),
             atlas_complete: false,
         }
     }
@@ -220,12 +235,13 @@ impl App {
         }
     }
 
-    pub fn session_id(&self) -> Option<&String> {
-        self.app_context.session_id.as_ref()
+    pub fn session_token(&self) -> Option<&String> {
+        self.app_context.session_token.as_ref()
     }
 
-    pub fn set_session_id(&mut self, session_id: String) {
+    pub fn set_session(&mut self, session_id: String, session_token: String) {
         self.app_context.session_id = Some(session_id);
+        self.app_context.session_token = Some(session_token);
     }
 
     pub fn on_input_submit(&mut self, text_input: &HtmlInputElement) {
@@ -376,12 +392,19 @@ impl App {
     }
 
     pub fn on_session_response(&mut self, value: JsValue) {
-        let session_request: SessionRequest = serde_wasm_bindgen::from_value(value).unwrap();
-        let session_id = session_request.session_id;
+        let Ok(SessionToken {
+            session_id, token, ..
+        }) = serde_wasm_bindgen::from_value(value)
+        else {
+            return;
+        };
 
-        self.set_session_id(session_id.clone());
+        storage().map(|storage| {
+            let _ = storage.set_item("session_id", &session_id);
+            storage.set_item("session_token", &token)
+        });
 
-        storage().map(|storage| storage.set_item("session_id", session_id.as_str()));
+        self.set_session(session_id, token);
     }
 
     fn load_levels() -> HashMap<usize, Level> {
@@ -13,3 +13,35 @@ pub use state::*;
 pub use ui::*;
 
 pub const BOARD_SCALE: (i32, i32) = (32, 32);
+
+/// Number of tiles along each axis of the board area when drawn at [`BOARD_SCALE`].
+pub const BOARD_TILES: usize = 8;
+
+/// Factor by which a board of the given size is shrunk to fit `tiles` tiles on each axis.
+pub fn board_fit(board_size: (usize, usize), tiles: usize) -> f64 {
+    tiles as f64 / board_size.0.max(board_size.1).max(tiles) as f64
+}
+
+/// Offset that centres a board of the given size within the board area.
+pub fn board_offset(board_size: (usize, usize)) -> (i32, i32) {
+    let fit = board_fit(board_size, BOARD_TILES);
+
+    (
+        ((BOARD_TILES as f64 - board_size.0 as f64 * fit) * BOARD_SCALE.0 as f64 / 2.0) as i32,
+        ((BOARD_TILES as f64 - board_size.1 as f64 * fit) * BOARD_SCALE.1 as f64 / 2.0) as i32,
+    )
+}
+
+/// Converts a canvas location over a shrunk board to the location it would have at [`BOARD_SCALE`].
+pub fn unfit_location(
+    location: (i32, i32),
+    offset: (i32, i32),
+    board_size: (usize, usize),
+) -> (i32, i32) {
+    let fit = board_fit(board_size, BOARD_TILES);
+
+    (
+        offset.0 + ((location.0 - offset.0) as f64 / fit).floor() as i32,
+        offset.1 + ((location.1 - offset.1) as f64 / fit).floor() as i32,
+    )
+}
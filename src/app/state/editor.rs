@@ -1,19 +1,20 @@
 use std::mem;
 
-use shared::{Board, Level, Mage, Mages, Position, PowerUp, Team};
+use shared::{Board, Level, LevelIssue, Mage, Mages, Position, PowerUp, Simulation, Team};
 use wasm_bindgen::JsValue;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlInputElement};
 
 use super::{EditorPreview, MainMenu, State};
 use crate::{
     app::{
-        Alignment, App, AppContext, ButtonElement, ConfirmButtonElement, Interface, LabelTheme,
-        LabelTrim, Particle, ParticleSort, ParticleSystem, StateSort, ToggleButtonElement,
-        UIElement, UIEvent, BOARD_SCALE,
+        board_fit, board_offset, unfit_location, Alignment, App, AppContext, ButtonElement,
+        ConfirmButtonElement, Interface, LabelTheme, LabelTrim, Particle, ParticleSort,
+        ParticleSystem, StateSort, ToggleButtonElement, UIElement, UIEvent, BOARD_SCALE,
+        BOARD_TILES,
     },
     draw::{
         draw_board, draw_crosshair, draw_mage, draw_mana, draw_powerup, draw_spell_pattern,
-        draw_sprite,
+        draw_sprite, draw_text, draw_text_centered,
     },
     tuple_as,
 };
@@ -37,6 +38,8 @@ pub struct Editor {
     level: Level,
     particle_system: ParticleSystem,
     selection: EditorSelection,
+    error_message: Option<(u64, String)>,
+    simulation: Option<Simulation>,
 }
 
 const BUTTON_MENU: usize = 0;
@@ -60,10 +63,15 @@ const BUTTON_ADD_MAGE: usize = 40;
 const BUTTON_ADD_PROP: usize = 41;
 
 const BUTTON_LOAD: usize = 12;
+const BUTTON_IMPORT: usize = 13;
+const BUTTON_EXPORT: usize = 14;
 const BUTTON_SIMULATE: usize = 50;
 const BUTTON_RESET: usize = 51;
 const BUTTON_LEAVE: usize = 100;
 
+/// Number of games played when simulating a level.
+const SIMULATION_RUNS: usize = 8;
+
 impl Editor {
     pub fn new(level: Level) -> Editor {
         let button_menu = ToggleButtonElement::new(
@@ -256,12 +264,30 @@ impl Editor {
             crate::app::ContentElement::Text("Load".to_string(), Alignment::Center),
         );
 
+        let button_import = ButtonElement::new(
+            (96 - 44, 128 - 52),
+            (42, 16),
+            BUTTON_IMPORT,
+            LabelTrim::Round,
+            LabelTheme::Default,
+            crate::app::ContentElement::Text("Import".to_string(), Alignment::Center),
+        );
+
+        let button_export = ButtonElement::new(
+            (96 + 2, 128 - 52),
+            (42, 16),
+            BUTTON_EXPORT,
+            LabelTrim::Round,
+            LabelTheme::Default,
+            crate::app::ContentElement::Text("Export".to_string(), Alignment::Center),
+        );
+
         let button_simulate = ButtonElement::new(
             (96 - 44, 128),
             (88, 16),
             BUTTON_SIMULATE,
             LabelTrim::Round,
-            LabelTheme::Disabled,
+            LabelTheme::Default,
             crate::app::ContentElement::Text("Simulate".to_string(), Alignment::Center),
         );
 
@@ -285,6 +311,8 @@ impl Editor {
 
         let menu_interface = Interface::new(vec![
             button_load.boxed(),
+            button_import.boxed(),
+            button_export.boxed(),
             button_simulate.boxed(),
             button_reset.boxed(),
             button_leave.boxed(),
@@ -301,16 +329,39 @@ impl Editor {
             particle_system: ParticleSystem::default(),
             selection: EditorSelection::None,
             board_dirty: true,
+            error_message: None,
+            simulation: None,
         }
     }
 
     pub fn board_offset(&self) -> (i32, i32) {
-        (
-            ((8 - self.level.board.width) as i32 * BOARD_SCALE.0) / 2,
-            ((8 - self.level.board.height) as i32 * BOARD_SCALE.1) / 2,
+        board_offset(self.board_size())
+    }
+
+    fn board_size(&self) -> (usize, usize) {
+        (self.level.board.width, self.level.board.height)
+    }
+
+    /// Converts a canvas location to the [`Position`] of the tile beneath it.
+    fn location_as_position(&self, location: (i32, i32)) -> Option<Position> {
+        let board_offset = self.board_offset();
+        let offset = (board_offset.0 - 32, board_offset.1);
+
+        self.level.board.location_as_position(
+            unfit_location(location, offset, self.board_size()),
+            offset,
+            BOARD_SCALE,
         )
     }
 
+    /// Resizes the board while keeping its style.
+    fn resize_board(&mut self, width: usize, height: usize) {
+        if let Ok(board) = Board::with_style(width, height, self.level.board.style.clone()) {
+            self.level.board = board;
+            self.board_dirty = true;
+        }
+    }
+
     pub fn is_interface_active(&self) -> bool {
         self.button_menu.selected()
     }
@@ -331,6 +382,74 @@ impl Editor {
     fn occupied(&self, position: &Position) -> bool {
         self.level.mages.occupied(position)
     }
+
+    /// Draws the progress of a [`Simulation`], or its report once finished.
+    fn draw_simulation(
+        &self,
+        context: &CanvasRenderingContext2d,
+        atlas: &HtmlCanvasElement,
+        simulation: &Simulation,
+    ) -> Result<(), JsValue> {
+        context.save();
+        context.set_fill_style_str("#1f0f2fe0");
+        context.fill_rect(0.0, 56.0, 192.0, 144.0);
+
+        if !simulation.is_finished() {
+            let (played, runs) = simulation.progress();
+
+            draw_text_centered(
+                context,
+                atlas,
+                96.0,
+                128.0,
+                &format!("Simulating {}/{}", played + 1, runs),
+            )?;
+        } else {
+            let report = simulation.report();
+            let percent = |rate: f64| (rate * 100.0).round() as isize;
+
+            let mut lines = vec![
+                format!(
+                    "Red {}% Blue {}% Draw {}%",
+                    percent(report.red_rate()),
+                    percent(report.blue_rate()),
+                    percent(report.draw_rate())
+                ),
+                format!("Length {:.1} moves", report.average_length),
+                format!("First move {:+}%", percent(report.first_move_advantage)),
+                format!(
+                    "Red wins {}-{}%",
+                    percent(report.red_win_interval.0),
+                    percent(report.red_win_interval.1)
+                ),
+            ];
+
+            if let Some(impact) = report.decisive_mages.first() {
+                lines.push(format!(
+                    "Top mage {:?} {:?} {:.1}",
+                    impact.team, impact.sort, impact.damage
+                ));
+            }
+
+            if let Some(impact) = report.decisive_powerups.first() {
+                lines.push(format!(
+                    "Top power-up {:?} {}%",
+                    impact.powerup,
+                    percent(impact.win_rate)
+                ));
+            }
+
+            for (i, line) in lines.iter().enumerate() {
+                draw_text(context, atlas, 8.0, 64.0 + i as f64 * 16.0, line)?;
+            }
+
+            draw_text_centered(context, atlas, 96.0, 192.0, "Click to close")?;
+        }
+
+        context.restore();
+
+        Ok(())
+    }
 }
 
 impl State for Editor {
@@ -343,6 +462,7 @@ impl State for Editor {
     ) -> Result<(), JsValue> {
         let board_scale = tuple_as!(BOARD_SCALE, f64);
         let board_offset = self.board_offset();
+        let board_fit = board_fit(self.board_size(), BOARD_TILES);
 
         let frame = app_context.frame;
         let pointer = &app_context.pointer;
@@ -360,6 +480,7 @@ impl State for Editor {
         draw_sprite(context, atlas, 256.0, 256.0, 64.0, 64.0, 276.0, 8.0)?;
 
         context.translate(board_offset.0 as f64, board_offset.1 as f64)?;
+        context.scale(board_fit, board_fit)?;
 
         self.particle_system.tick_and_draw(context, atlas, frame)?;
 
@@ -408,11 +529,7 @@ impl State for Editor {
             context.restore();
         }
 
-        let selected_tile = self.level.board.location_as_position(
-            pointer.location,
-            (board_offset.0 - 32, board_offset.1),
-            BOARD_SCALE,
-        );
+        let selected_tile = self.location_as_position(pointer.location);
 
         if let Some(selected_tile) = selected_tile {
             draw_crosshair(context, atlas, &selected_tile, (32.0, 32.0), frame)?;
@@ -442,11 +559,13 @@ impl State for Editor {
                 interface_context.translate(
                     (pointer.location.0 as f64).clamp(
                         board_offset.0 - 16.0,
-                        board_offset.0 - 48.0 + board_scale.0 * self.level.board.width as f64,
+                        board_offset.0 - 48.0
+                            + board_scale.0 * board_fit * self.level.board.width as f64,
                     ),
                     (pointer.location.1 as f64).clamp(
                         board_offset.1 + 16.0,
-                        board_offset.1 - 16.0 + board_scale.1 * self.level.board.height as f64,
+                        board_offset.1 - 16.0
+                            + board_scale.1 * board_fit * self.level.board.height as f64,
                     ),
                 )?;
                 draw_mage(interface_context, atlas, mage, frame, mage.team, true, None)?;
@@ -463,11 +582,13 @@ impl State for Editor {
                 interface_context.translate(
                     (pointer.location.0 as f64).clamp(
                         board_offset.0 - 16.0,
-                        board_offset.0 - 48.0 + board_scale.0 * self.level.board.width as f64,
+                        board_offset.0 - 48.0
+                            + board_scale.0 * board_fit * self.level.board.width as f64,
                     ),
                     (pointer.location.1 as f64).clamp(
                         board_offset.1 + 16.0,
-                        board_offset.1 - 16.0 + board_scale.1 * self.level.board.height as f64,
+                        board_offset.1 - 16.0
+                            + board_scale.1 * board_fit * self.level.board.height as f64,
                     ),
                 )?;
                 draw_powerup(interface_context, atlas, &Position(0, 0), powerup, frame)?;
@@ -545,6 +666,16 @@ impl State for Editor {
                 .draw(interface_context, atlas, pointer, frame)?;
         }
 
+        if let Some((error_frame, error)) = &self.error_message {
+            if frame - error_frame < 90 {
+                draw_text_centered(interface_context, atlas, 96.0, 128.0, error)?;
+            }
+        }
+
+        if let Some(simulation) = &self.simulation {
+            self.draw_simulation(interface_context, atlas, simulation)?;
+        }
+
         if pointer.location.0 >= 244 + 16
             && pointer.location.0 < 308 - 16
             && pointer.location.1 >= 8 + 16
@@ -567,16 +698,34 @@ impl State for Editor {
         text_input: &HtmlInputElement,
         app_context: &AppContext,
     ) -> Option<StateSort> {
-        let board_offset = self.board_offset();
         let pointer = &app_context.pointer;
 
         if text_input.dataset().get("field").is_some() {
             return None;
         }
 
+        // The simulation plays a move per tick, and its report is dismissed with a click.
+        if let Some(simulation) = &mut self.simulation {
+            if !simulation.is_finished() {
+                simulation.step();
+            } else if pointer.clicked() {
+                self.simulation = None;
+            }
+
+            return None;
+        }
+
         if let Some((field, value)) = &app_context.text_input {
             if field == "level_code" {
-                return Some(StateSort::Editor(Editor::new(value.as_str().into())));
+                // Invalid codes leave the level being edited untouched.
+                return Level::from_code(value)
+                    .ok()
+                    .map(|level| StateSort::Editor(Editor::new(level)));
+            } else if field == "level_text" {
+                match Level::from_text(value) {
+                    Ok(level) => return Some(StateSort::Editor(Editor::new(level))),
+                    Err(error) => self.error_message = Some((app_context.frame, error.to_string())),
+                }
             }
         }
 
@@ -586,45 +735,44 @@ impl State for Editor {
 
                 match value {
                     BUTTON_LOAD => {
-                        text_input.set_value(self.level.as_code().as_str());
+                        // Levels that do not fit in a code leave the input empty.
+                        text_input.set_value(&self.level.as_code().unwrap_or_default());
                         text_input.set_placeholder("Enter level code");
                         text_input.dataset().set("field", "level_code").unwrap();
                         text_input.focus().unwrap();
                         self.button_menu.set_selected(false);
                     }
+                    BUTTON_IMPORT => {
+                        text_input.set_value("");
+                        text_input.set_placeholder("Enter level text");
+                        text_input.dataset().set("field", "level_text").unwrap();
+                        text_input.focus().unwrap();
+                        self.button_menu.set_selected(false);
+                    }
+                    BUTTON_EXPORT => {
+                        // The text is shown on a single line, since the text input cannot hold line breaks.
+                        text_input.set_value(self.level.as_text_line().as_str());
+                        text_input
+                            .dataset()
+                            .set("field", "export_level_text")
+                            .unwrap();
+                        text_input.focus().unwrap();
+                        self.button_menu.set_selected(false);
+                    }
                     BUTTON_SIMULATE => {
-                        // let simulations = Level::simulate(
-                        //     Level::new(
-                        //         self.level.board.clone(),
-                        //         self.level.mages.clone(),
-                        //         Team::Red,
-                        //     ),
-                        //     5,
-                        //     window().performance().unwrap().now() as u64,
-                        // );
-
-                        // console::log_1(
-                        //     &format!(
-                        //         "{}",
-                        //         simulations
-                        //             .iter()
-                        //             .map(|game| {
-                        //                 console::log_1(
-                        //                     &format!(
-                        //                         "{} {} {:?}",
-                        //                         game.turns(),
-                        //                         game.evaluate(),
-                        //                         game
-                        //                     )
-                        //                     .into(),
-                        //                 );
-                        //                 game.evaluate()
-                        //             })
-                        //             .sum::<isize>()
-                        //             / 5
-                        //     )
-                        //     .into(),
-                        // );
+                        self.button_menu.set_selected(false);
+
+                        if let Some(issue) =
+                            self.level.validate().into_iter().find(LevelIssue::is_error)
+                        {
+                            self.error_message = Some((app_context.frame, issue.to_string()));
+                        } else {
+                            self.simulation = Some(Simulation::new(
+                                &self.level,
+                                SIMULATION_RUNS,
+                                app_context.frame,
+                            ));
+                        }
                     }
                     BUTTON_RESET => {
                         return Some(StateSort::Editor(Editor::new(Level::default())));
@@ -641,9 +789,25 @@ impl State for Editor {
 
                 match value {
                     BUTTON_SAVE => {
+                        // Unplayable levels are not saved; the first error is shown instead.
+                        if let Some(issue) =
+                            self.level.validate().into_iter().find(LevelIssue::is_error)
+                        {
+                            self.error_message = Some((app_context.frame, issue.to_string()));
+                            return None;
+                        }
+
+                        let code = match self.level.as_code() {
+                            Ok(code) => code,
+                            Err(error) => {
+                                self.error_message = Some((app_context.frame, error.to_string()));
+                                return None;
+                            }
+                        };
+
                         App::save_level(0, self.level.clone());
 
-                        text_input.set_value(self.level.as_code().as_str());
+                        text_input.set_value(&code);
                         text_input
                             .dataset()
                             .set("field", "save_level_code")
@@ -694,20 +858,12 @@ impl State for Editor {
                                     ParticleSort::Diagonals,
                                 ));
                             }
-                        } else if let Ok(board) =
-                            Board::new(self.level.board.width - 1, self.level.board.height)
-                        {
-                            self.level.board = board;
-                            self.board_dirty = true;
+                        } else {
+                            self.resize_board(self.level.board.width - 1, self.level.board.height);
                         }
                     }
                     BUTTON_WIDTH_PLUS => {
-                        if let Ok(board) =
-                            Board::new(self.level.board.width + 1, self.level.board.height)
-                        {
-                            self.level.board = board;
-                            self.board_dirty = true;
-                        }
+                        self.resize_board(self.level.board.width + 1, self.level.board.height);
                     }
                     BUTTON_HEIGHT_MINUS => {
                         let min_height = self
@@ -742,20 +898,12 @@ impl State for Editor {
                                     ParticleSort::Diagonals,
                                 ));
                             }
-                        } else if let Ok(board) =
-                            Board::new(self.level.board.width, self.level.board.height - 1)
-                        {
-                            self.level.board = board;
-                            self.board_dirty = true;
+                        } else {
+                            self.resize_board(self.level.board.width, self.level.board.height - 1);
                         }
                     }
                     BUTTON_HEIGHT_PLUS => {
-                        if let Ok(board) =
-                            Board::new(self.level.board.width, self.level.board.height + 1)
-                        {
-                            self.level.board = board;
-                            self.board_dirty = true;
-                        }
+                        self.resize_board(self.level.board.width, self.level.board.height + 1);
                     }
 
                     _ => (),
@@ -935,11 +1083,7 @@ impl State for Editor {
             }
         }
 
-        if let Some(selected_tile) = self.level.board.location_as_position(
-            pointer.location,
-            (board_offset.0 - 32, board_offset.1),
-            BOARD_SCALE,
-        ) {
+        if let Some(selected_tile) = self.location_as_position(pointer.location) {
             if pointer.clicked() {
                 for _ in 0..10 {
                     let d = js_sys::Math::random() * std::f64::consts::TAU;
@@ -5,8 +5,9 @@ use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlInputElement};
 use super::{Editor, Game, State};
 use crate::{
     app::{
-        Alignment, App, AppContext, ButtonElement, Interface, LabelTheme, LabelTrim, Particle,
-        ParticleSort, ParticleSystem, StateSort, UIElement, UIEvent, BOARD_SCALE,
+        board_fit, board_offset, Alignment, App, AppContext, ButtonElement, Interface, LabelTheme,
+        LabelTrim, Particle, ParticleSort, ParticleSystem, StateSort, UIElement, UIEvent,
+        BOARD_SCALE, BOARD_TILES,
     },
     draw::{draw_board, draw_mage, draw_mana, draw_powerup, draw_sprite},
     tuple_as,
@@ -88,10 +89,7 @@ impl EditorPreview {
     }
 
     pub fn board_offset(&self) -> (i32, i32) {
-        (
-            ((8 - self.level.board.width) as i32 * BOARD_SCALE.0) / 2,
-            ((8 - self.level.board.height) as i32 * BOARD_SCALE.1) / 2,
-        )
+        board_offset((self.level.board.width, self.level.board.height))
     }
 }
 
@@ -105,6 +103,10 @@ impl State for EditorPreview {
     ) -> Result<(), JsValue> {
         let board_scale = tuple_as!(BOARD_SCALE, f64);
         let board_offset = self.board_offset();
+        let board_fit = board_fit(
+            (self.level.board.width, self.level.board.height),
+            BOARD_TILES,
+        );
 
         let frame = app_context.frame;
         let pointer = &app_context.pointer;
@@ -125,6 +127,7 @@ impl State for EditorPreview {
                 draw_sprite(context, atlas, 256.0, 0.0, 256.0, 256.0, 0.0, 0.0)?;
 
                 context.translate(board_offset.0 as f64, board_offset.1 as f64)?;
+                context.scale(board_fit, board_fit)?;
 
                 // DRAW particles
 
@@ -1,39 +1,48 @@
-use std::{cell::RefCell, f64::consts::PI, rc::Rc};
+use std::{cell::RefCell, collections::HashSet, f64::consts::PI, rc::Rc};
 
 use shared::{
-    Board, BoardStyle, GameResult, LoadoutMethod, Lobby, LobbyError, LobbyID, LobbySettings,
-    LobbySort, Mage, Mages, Message, Position, PowerUp, Team, Turn, TurnLeaf,
+    Board, BoardStyle, Deployment, Draft, GameEvent, GameResult, LoadoutMethod, Lobby, LobbyError,
+    LobbyID, LobbySettings, LobbySort, Mage, MageSort, Mages, Message, Position, PowerUp, Presence,
+    Team, Turn, TurnLeaf,
 };
 use wasm_bindgen::{prelude::Closure, JsValue};
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlInputElement};
 
-use super::{ArenaMenu, Editor, SkirmishMenu, State};
+use super::{ArenaMenu, Editor, MainMenu, SkirmishMenu, State};
 use crate::{
     app::{
-        Alignment, App, AppContext, ButtonElement, ClipId, ConfirmButtonElement, Interface,
-        LabelTheme, LabelTrim, Particle, ParticleSort, ParticleSystem, Pointer, StateSort,
-        ToggleButtonElement, UIElement, UIEvent, BOARD_SCALE,
+        board_fit, board_offset, unfit_location, Alignment, App, AppContext, ButtonElement, ClipId,
+        ConfirmButtonElement, Interface, LabelTheme, LabelTrim, Particle, ParticleSort,
+        ParticleSystem, Pointer, StateSort, ToggleButtonElement, UIElement, UIEvent, BOARD_SCALE,
+        BOARD_TILES,
     },
     draw::{
         draw_board, draw_crosshair, draw_label, draw_mage, draw_mana, draw_powerup, draw_sprite,
-        draw_text, rotation_from_position, text_length,
+        draw_text, draw_text_centered, rotation_from_position, text_length,
     },
     net::{
-        client_timestamp, create_new_lobby, fetch, request_state, request_turns_since,
-        send_message, send_ready, send_rematch, MessagePool,
+        clear_rejoin_token, client_timestamp, create_new_lobby, get_session_token, rejoin_code,
+        request_state, request_turns_since, save_rejoin_token, send_leave, send_message,
+        send_ready, send_rejoin, send_rematch, LobbySocket, LobbyStream, MessagePool,
     },
     tuple_as, window,
 };
 
 const BUTTON_REMATCH: usize = 1;
 const BUTTON_LEAVE: usize = 2;
+const BUTTON_DRAW: usize = 3;
 const BUTTON_MENU: usize = 10;
 const BUTTON_UNDO: usize = 20;
+const BUTTON_DEPLOY: usize = 30;
+
+/// Number of mages per row of the draft pool.
+const DRAFT_COLUMNS: usize = 5;
 
 pub struct Game {
     interface: Interface,
     button_menu: ToggleButtonElement,
     button_undo: ButtonElement,
+    button_deploy: ButtonElement,
     lobby: Lobby,
     last_move_frame: u64,
     last_hits: Vec<Position>,
@@ -41,13 +50,60 @@ pub struct Game {
     particle_system: ParticleSystem,
     message_pool: Rc<RefCell<MessagePool>>,
     message_closure: Closure<dyn FnMut(JsValue)>,
+    socket: Option<LobbySocket>,
+    /// Event stream of a spectated lobby, which stays open for as long as it is kept.
+    _stream: Option<LobbyStream>,
+    spectating: bool,
+    /// Token with which the player can take their slot in the online lobby to another session.
+    rejoin_token: Option<String>,
+    /// Whether the slot is still being reclaimed with the rejoin token, before the lobby is followed.
+    rejoining: bool,
+    rejoin_failed: bool,
     board_dirty: bool,
     shake_frame: (u64, usize),
     recorded_result: bool,
+    visible_positions: Option<HashSet<Position>>,
+    committed_round: Option<usize>,
+    error_message: Option<(u64, String)>,
+    /// Announcement of the other player joining or leaving, shown like an error message.
+    presence_message: Option<(u64, String)>,
+    drafted_sort: Option<MageSort>,
+    arrangement: Option<(Team, Vec<Position>)>,
+    arrangement_sent: bool,
 }
 
 impl Game {
     pub fn new(lobby_settings: LobbySettings) -> Game {
+        Game::with_role(lobby_settings, false)
+    }
+
+    /// Watches an online lobby as a read-only spectator, following it over server-sent events.
+    pub fn spectate(lobby_id: LobbyID) -> Game {
+        Game::with_role(
+            LobbySettings {
+                lobby_sort: LobbySort::Online(lobby_id),
+                ..Default::default()
+            },
+            true,
+        )
+    }
+
+    /// Reclaims the player's slot in an online lobby with a rejoin token, restoring a game in progress.
+    pub fn rejoin(lobby_id: LobbyID, rejoin_token: String) -> Game {
+        Game {
+            rejoin_token: Some(rejoin_token),
+            rejoining: true,
+            ..Game::with_role(
+                LobbySettings {
+                    lobby_sort: LobbySort::Online(lobby_id),
+                    ..Default::default()
+                },
+                false,
+            )
+        }
+    }
+
+    fn with_role(lobby_settings: LobbySettings, spectating: bool) -> Game {
         let message_pool = Rc::new(RefCell::new(MessagePool::new()));
 
         let message_closure = {
@@ -61,7 +117,8 @@ impl Game {
         };
 
         if let shared::LobbySort::Online(0) = lobby_settings.lobby_sort {
-            let _ = create_new_lobby(lobby_settings.clone())
+            let session_token = get_session_token().unwrap_or_default();
+            let _ = create_new_lobby(lobby_settings.clone(), session_token)
                 .unwrap()
                 .then(&message_closure);
         }
@@ -84,6 +141,15 @@ impl Game {
             crate::app::ContentElement::Sprite((144, 16), (16, 16)),
         );
 
+        let button_deploy = ButtonElement::new(
+            (-192, 36),
+            (56, 16),
+            BUTTON_DEPLOY,
+            LabelTrim::Round,
+            LabelTheme::Action,
+            crate::app::ContentElement::Text("Deploy".to_string(), Alignment::Center),
+        );
+
         let button_rematch = ButtonElement::new(
             (-44, -24),
             (88, 24),
@@ -102,12 +168,39 @@ impl Game {
             crate::app::ContentElement::Text("Leave".to_string(), Alignment::Center),
         );
 
-        let root_element = Interface::new(vec![button_rematch.boxed(), button_leave.boxed()]);
+        // Spectators can only leave.
+        let mut root_elements = if spectating {
+            vec![button_leave.boxed()]
+        } else {
+            vec![button_rematch.boxed(), button_leave.boxed()]
+        };
+
+        if lobby_settings.lobby_sort.lobby_id().is_some() && !spectating {
+            let button_draw = ButtonElement::new(
+                (-36, 28),
+                (72, 16),
+                BUTTON_DRAW,
+                LabelTrim::Round,
+                LabelTheme::Default,
+                crate::app::ContentElement::Text("Draw".to_string(), Alignment::Center),
+            );
+
+            root_elements.push(button_draw.boxed());
+        }
+
+        let root_element = Interface::new(root_elements);
+
+        let stream = lobby_settings
+            .lobby_sort
+            .lobby_id()
+            .filter(|_| spectating)
+            .and_then(|lobby_id| LobbyStream::connect(lobby_id, message_pool.clone()));
 
         Game {
             interface: root_element,
             button_menu,
             button_undo,
+            button_deploy,
             lobby: Lobby::new(lobby_settings, client_timestamp()),
             last_move_frame: 0,
             last_hits: Vec::new(),
@@ -115,9 +208,22 @@ impl Game {
             particle_system: ParticleSystem::default(),
             message_pool,
             message_closure,
+            socket: None,
+            _stream: stream,
+            spectating,
+            rejoin_token: None,
+            rejoining: false,
+            rejoin_failed: false,
             board_dirty: true,
             recorded_result: false,
             shake_frame: (0, 0),
+            visible_positions: None,
+            committed_round: None,
+            error_message: None,
+            presence_message: None,
+            drafted_sort: None,
+            arrangement: None,
+            arrangement_sent: false,
         }
     }
 
@@ -144,6 +250,8 @@ impl Game {
         offset: (i32, i32),
         scale: (i32, i32),
     ) -> Option<Position> {
+        let location = unfit_location(location, offset, self.lobby.game.board_size());
+
         let position = Position(
             ((location.0 - offset.0) / scale.0) as i8,
             ((location.1 - offset.1) / scale.1) as i8,
@@ -183,11 +291,21 @@ impl Game {
         None
     }
 
+    fn has_committed(&self) -> bool {
+        self.committed_round == Some(self.lobby.game.turns())
+    }
+
     pub fn select_mage_at(&mut self, session_id: Option<&String>, selected_tile: &Position) {
-        if self.lobby.is_active_player(session_id) {
+        if self.lobby.is_active_player(session_id) && !self.has_committed() {
+            let acting_team = if self.lobby.game.is_simultaneous() && !self.lobby.is_local() {
+                self.lobby.player_team(session_id)
+            } else {
+                Some(self.lobby.game.turn_for())
+            };
+
             self.active_mage = if let Some(occupant) = self.lobby.game.live_occupant(selected_tile)
             {
-                if occupant.team == self.lobby.game.turn_for() {
+                if Some(occupant.team) == acting_team {
                     Some(occupant.index)
                 } else {
                     None
@@ -202,6 +320,301 @@ impl Game {
         self.active_mage = None;
     }
 
+    /// Returns the [`Team`] whose drafted mages are shown, which is the local player's in online lobbies.
+    fn drafting_team(&self, session_id: Option<&String>) -> Option<Team> {
+        if self.lobby.is_local() {
+            self.lobby.draft().and_then(Draft::turn_for)
+        } else {
+            self.lobby.player_team(session_id)
+        }
+    }
+
+    /// Centre of a slot in the draft pool, on the interface.
+    fn draft_pool_slot(index: usize) -> (i32, i32) {
+        (
+            -64 + (index % DRAFT_COLUMNS) as i32 * 32,
+            -40 + (index / DRAFT_COLUMNS) as i32 * 32,
+        )
+    }
+
+    /// Centre of a slot in the column of unplaced picks, on the interface.
+    fn draft_unplaced_slot(index: usize) -> (i32, i32) {
+        (176, -112 + index as i32 * 32)
+    }
+
+    /// Finds the slot under a location on the interface.
+    fn draft_slot_at(
+        location: (i32, i32),
+        count: usize,
+        slot: fn(usize) -> (i32, i32),
+    ) -> Option<usize> {
+        (0..count).find(|index| {
+            let (x, y) = slot(*index);
+
+            (location.0 - x).abs() < 16 && (location.1 - y).abs() < 16
+        })
+    }
+
+    /// Handles clicks while drafting: picking from the pool, selecting an unplaced pick, and placing it on the board.
+    fn tick_draft(&mut self, app_context: &AppContext, interface_pointer: &Pointer) {
+        let frame = app_context.frame;
+        let pointer = &app_context.pointer;
+        let session_id = app_context.session_id.as_ref();
+
+        let Some(draft) = self.lobby.draft() else {
+            return;
+        };
+
+        if !pointer.clicked() || !self.lobby.is_active_player(session_id) {
+            return;
+        }
+
+        let team = match draft.turn_for() {
+            Some(team) => team,
+            None => return,
+        };
+
+        let message = |(sort, position): (MageSort, Option<Position>)| match position {
+            Some(position) => Message::Place(sort, position),
+            None => Message::Pick(sort),
+        };
+
+        let action = if draft.is_picking() {
+            Self::draft_slot_at(
+                interface_pointer.location,
+                draft.pool().len(),
+                Self::draft_pool_slot,
+            )
+            .map(|index| (draft.pool()[index], None))
+        } else {
+            let unplaced = draft.unplaced(team);
+
+            if let Some(index) = Self::draft_slot_at(
+                interface_pointer.location,
+                unplaced.len(),
+                Self::draft_unplaced_slot,
+            ) {
+                self.drafted_sort = Some(unplaced[index]);
+                app_context.audio_system.play_clip(ClipId::MageSelect);
+
+                None
+            } else {
+                let sort = self
+                    .drafted_sort
+                    .filter(|sort| unplaced.contains(sort))
+                    .or(unplaced.first().copied());
+
+                self.location_as_position(pointer.location, self.board_offset(), BOARD_SCALE)
+                    .zip(sort)
+                    .map(|(position, sort)| (sort, Some(position)))
+            }
+        };
+
+        if let Some(action) = action {
+            if !self.lobby.is_local() {
+                if let (Ok(lobby_id), Some(session_token)) =
+                    (self.lobby_id(), &app_context.session_token)
+                {
+                    send_message(lobby_id, session_token.clone(), message(action))
+                        .map(|promise| promise.then(&self.message_closure));
+                }
+            }
+
+            self.message_pool.borrow_mut().push(message(action));
+            self.last_move_frame = frame;
+
+            app_context.audio_system.play_clip(ClipId::MageMove);
+        }
+    }
+
+    /// Determines if the local player is arranging their mages for a deployment that has not been committed yet.
+    fn is_arranging(&self, session_id: Option<&String>) -> bool {
+        self.lobby.is_deploying()
+            && self.arrangement.is_some()
+            && !self.arrangement_sent
+            && self.lobby.is_active_player(session_id)
+    }
+
+    /// Handles clicks while deploying: selecting a mage, moving it onto a free home tile, and committing the arrangement.
+    fn tick_deployment(&mut self, app_context: &AppContext, interface_pointer: &Pointer) {
+        let frame = app_context.frame;
+        let pointer = &app_context.pointer;
+        let session_id = app_context.session_id.as_ref();
+
+        let Some(deployment) = self.lobby.deployment() else {
+            return;
+        };
+
+        let team = if self.lobby.is_local() {
+            deployment.turn_for()
+        } else {
+            self.lobby
+                .player_team(session_id)
+                .filter(|team| !deployment.has_deployed(*team))
+        };
+
+        let team = match team {
+            Some(team) if !self.arrangement_sent && self.lobby.is_active_player(session_id) => team,
+            _ => return,
+        };
+
+        // Each team starts from its default arrangement, which also hides the other team's mages in local lobbies.
+        if self.arrangement.as_ref().map(|(arranging, _)| *arranging) != Some(team) {
+            let positions = deployment.default_positions(team);
+
+            let _ = self.lobby.arrange(team, &positions);
+            self.arrangement = Some((team, positions));
+            self.active_mage = None;
+        }
+
+        let Some((_, positions)) = self.arrangement.clone() else {
+            return;
+        };
+
+        if self.button_deploy.tick(interface_pointer).is_some() {
+            if self.lobby.is_local() {
+                self.message_pool
+                    .borrow_mut()
+                    .push(Message::Deploy(positions));
+            } else if let (Ok(lobby_id), Some(session_token)) =
+                (self.lobby_id(), &app_context.session_token)
+            {
+                send_message(lobby_id, session_token.clone(), Message::Deploy(positions))
+                    .map(|promise| promise.then(&self.message_closure));
+
+                self.arrangement_sent = true;
+            }
+
+            self.active_mage = None;
+            self.last_move_frame = frame;
+
+            return;
+        }
+
+        if pointer.alt_clicked() {
+            self.deselect_mage();
+        }
+
+        if pointer.clicked() {
+            if let Some(selected_tile) =
+                self.location_as_position(pointer.location, self.board_offset(), BOARD_SCALE)
+            {
+                if let Some(index) = positions
+                    .iter()
+                    .position(|position| *position == selected_tile)
+                {
+                    self.active_mage = Some(index);
+                    self.play_mage_selection_sound(app_context);
+                } else if let Some(index) = self.active_mage {
+                    let mut arranged = positions;
+                    arranged[index] = selected_tile;
+
+                    match self.lobby.arrange(team, &arranged) {
+                        Ok(()) => {
+                            self.arrangement = Some((team, arranged));
+                            self.active_mage = None;
+
+                            app_context.audio_system.play_clip(ClipId::MageMove);
+                        }
+                        Err(LobbyError(error)) => {
+                            self.error_message = Some((frame, error));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draws the draft pool and picks while picking, or the unplaced picks while placing.
+    fn draw_draft(
+        &self,
+        context: &CanvasRenderingContext2d,
+        atlas: &HtmlCanvasElement,
+        frame: u64,
+        session_id: Option<&String>,
+    ) -> Result<(), JsValue> {
+        let Some(draft) = self.lobby.draft() else {
+            return Ok(());
+        };
+
+        let turn_for = draft.turn_for().unwrap_or_default();
+
+        let draw_sort = |sort: MageSort, team: Team, (x, y): (i32, i32)| -> Result<(), JsValue> {
+            context.save();
+            context.translate(x as f64, y as f64)?;
+            draw_mage(
+                context,
+                atlas,
+                &Mage::new(0, team, sort, Position(0, 0)),
+                frame,
+                turn_for,
+                true,
+                None,
+            )?;
+            context.restore();
+
+            Ok(())
+        };
+
+        if draft.is_picking() {
+            context.save();
+            context.set_fill_style_str("#1f0f2fe0");
+            context.fill_rect(-96.0, -80.0, 192.0, 160.0);
+            context.restore();
+
+            draw_text_centered(
+                context,
+                atlas,
+                0.0,
+                -68.0,
+                &if self.lobby.is_active_player(session_id) {
+                    "Pick a mage".to_string()
+                } else {
+                    format!("{turn_for:?} is picking")
+                },
+            )?;
+
+            for (index, sort) in draft.pool().iter().enumerate() {
+                draw_sort(*sort, turn_for, Self::draft_pool_slot(index))?;
+            }
+
+            for (row, team) in [Team::Red, Team::Blue].into_iter().enumerate() {
+                for (index, sort) in draft.picks(team).into_iter().enumerate() {
+                    draw_sort(sort, team, (-64 + index as i32 * 24, 32 + row as i32 * 32))?;
+                }
+            }
+        } else if let Some(team) = self.drafting_team(session_id) {
+            let unplaced = draft.unplaced(team);
+            let selected = self
+                .drafted_sort
+                .filter(|sort| unplaced.contains(sort))
+                .or(unplaced.first().copied());
+            let selected_index =
+                selected.and_then(|selected| unplaced.iter().position(|sort| *sort == selected));
+
+            for (index, sort) in unplaced.iter().enumerate() {
+                let (x, y) = Self::draft_unplaced_slot(index);
+
+                draw_sort(*sort, team, (x, y))?;
+
+                if Some(index) == selected_index {
+                    draw_sprite(
+                        context,
+                        atlas,
+                        72.0,
+                        0.0,
+                        8.0,
+                        5.0,
+                        x as f64 - 3.0,
+                        y as f64 - 17.0 - (frame / 6 % 6) as f64,
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn play_mage_selection_sound(&self, app_context: &AppContext) {
         match self.active_mage {
             Some(_) => app_context.audio_system.play_clip(ClipId::MageSelect),
@@ -232,12 +645,7 @@ impl Game {
     }
 
     pub fn board_offset(&self) -> (i32, i32) {
-        let board_size = self.lobby().game.board_size();
-
-        (
-            ((8 - board_size.0) as i32 * BOARD_SCALE.0) / 2,
-            ((8 - board_size.1) as i32 * BOARD_SCALE.1) / 2,
-        )
+        board_offset(self.lobby().game.board_size())
     }
 
     pub fn frames_since_last_move(&self, frame: u64) -> u64 {
@@ -254,6 +662,7 @@ impl Game {
     ) -> Result<(), JsValue> {
         let board_scale = tuple_as!(BOARD_SCALE, f64);
         let board_offset = tuple_as!(self.board_offset(), f64);
+        let board_fit = board_fit(self.lobby.game.board_size(), BOARD_TILES);
 
         // let (board_width, board_height) = self.lobby.game.board_size();
 
@@ -291,6 +700,67 @@ impl Game {
             draw_sprite(context, atlas, 256.0, 0.0, 256.0, 256.0, 0.0, 0.0)?;
 
             context.translate(board_offset.0, board_offset.1)?;
+            context.scale(board_fit, board_fit)?;
+
+            // DRAW fog
+            if let Some(visible_positions) = &self.visible_positions {
+                let (board_width, board_height) = self.lobby.game.board_size();
+
+                context.save();
+                context.set_fill_style_str("#1f0f2fa0");
+
+                for x in 0..board_width {
+                    for y in 0..board_height {
+                        if !visible_positions.contains(&Position(x as i8, y as i8)) {
+                            context.fill_rect(
+                                x as f64 * board_scale.0,
+                                y as f64 * board_scale.1,
+                                board_scale.0,
+                                board_scale.1,
+                            );
+                        }
+                    }
+                }
+
+                context.restore();
+            }
+
+            // DRAW free home tiles while placing drafted mages or arranging a deployment
+            let home_team = match self.lobby.draft() {
+                Some(draft) if !draft.is_picking() => draft.turn_for(),
+                Some(_) => None,
+                None => self
+                    .arrangement
+                    .as_ref()
+                    .filter(|_| self.lobby.is_deploying() && !self.arrangement_sent)
+                    .map(|(team, _)| *team),
+            };
+
+            if let Some(team) = home_team {
+                let (board_width, board_height) = self.lobby.game.board_size();
+
+                for x in 0..board_width {
+                    for y in 0..board_height {
+                        let position = Position(x as i8, y as i8);
+
+                        if self.lobby.game.board().is_home_position(position, team)
+                            && !self.lobby.game.live_occupied(&position)
+                            && !self.lobby.game.powerups().contains_key(&position)
+                        {
+                            draw_sprite(
+                                context,
+                                atlas,
+                                64.0,
+                                256.0,
+                                32.0,
+                                32.0,
+                                x as f64 * board_scale.0,
+                                y as f64 * board_scale.1,
+                            )?;
+                        }
+                    }
+                }
+            }
 
             // DRAW particles
 
@@ -560,6 +1030,7 @@ impl Game {
                 }
             }
 
+            context.scale(1.0 / board_fit, 1.0 / board_fit)?;
             context.translate(-board_offset.0, -board_offset.1)?;
 
             if !self.lobby.all_ready() && !self.lobby.is_local() {
@@ -662,137 +1133,285 @@ impl Game {
         }
 
         let session_id = &app_context.session_id;
+        let session_token = &app_context.session_token;
 
         let mut target_positions = Vec::new();
 
         let all_ready = self.lobby.all_ready();
 
+        // Online lobbies are followed over a WebSocket once created, and polled while it is not open.
+        if self.socket.is_none() && !self.spectating && !self.rejoining {
+            if let (Ok(lobby_id @ 1..), Some(session_token)) = (self.lobby_id(), session_token) {
+                self.socket =
+                    LobbySocket::connect(lobby_id, session_token, self.message_pool.clone());
+            }
+        }
+
         let mut message_pool = self.message_pool.borrow_mut();
 
-        if let Some(lobby_id) = self.lobby.settings.lobby_sort.lobby_id() {
-            if message_pool.available(frame) {
-                if all_ready {
-                    if self.is_interface_active() {
-                        let _ = fetch(&request_state(lobby_id)).then(&self.message_closure);
+        // Spectators are only sent the lobby over their event stream.
+        if let Some(lobby_id) = self
+            .lobby
+            .settings
+            .lobby_sort
+            .lobby_id()
+            .filter(|_| !self.spectating)
+        {
+            if message_pool.available(frame)
+                && !self.socket.as_ref().is_some_and(LobbySocket::is_open)
+            {
+                // A dropped connection is attempted again on the next tick.
+                if self.socket.as_ref().is_some_and(LobbySocket::is_closed) {
+                    self.socket = None;
+                }
+
+                if self.rejoining {
+                    if let (Some(session_token), Some(rejoin_token)) =
+                        (session_token, &self.rejoin_token)
+                    {
+                        send_rejoin(lobby_id, session_token.clone(), rejoin_token.clone())
+                            .map(|promise| promise.then(&self.message_closure));
+                    }
+                } else if all_ready {
+                    // Draw offers, drafts and deployments progress without turns, so the whole lobby is followed until they are resolved.
+                    if self.is_interface_active()
+                        || self.lobby.draw_offer().is_some()
+                        || self.lobby.in_setup()
+                    {
+                        request_state(lobby_id, session_token.clone().unwrap())
+                            .map(|promise| promise.then(&self.message_closure));
                     } else {
                         // let _ = fetch(&request_turns_since(lobby_id, self.lobby.game.turns()))
                         //     .then(&self.message_closure);
 
                         request_turns_since(
                             lobby_id,
-                            session_id.clone().unwrap(),
+                            session_token.clone().unwrap(),
                             self.lobby.game.turns(),
                         )
                         .map(|promise| promise.then(&self.message_closure));
                     }
                 } else if self.lobby.settings.lobby_sort != LobbySort::Online(0) {
-                    let _ = fetch(&request_state(lobby_id)).then(&self.message_closure);
+                    if let Some(session_token) = session_token {
+                        request_state(lobby_id, session_token.clone())
+                            .map(|promise| promise.then(&self.message_closure));
+                    }
                 }
 
                 message_pool.block(frame);
             }
         }
 
+        if self.lobby.has_ai()
+            && self.lobby.draft().and_then(Draft::turn_for) == Some(Team::Blue)
+            && frame - self.last_move_frame > 45
+        {
+            let _ = self
+                .lobby
+                .auto_draft(window().performance().unwrap().now().to_bits());
+
+            self.last_move_frame = frame;
+        }
+
+        if let Some(deployment) = self
+            .lobby
+            .deployment()
+            .filter(|deployment| self.lobby.has_ai() && deployment.turn_for() == Some(Team::Blue))
+        {
+            let _ = self
+                .lobby
+                .deploy(Team::Blue, deployment.default_positions(Team::Blue));
+
+            self.last_move_frame = frame;
+        }
+
         if self.lobby.has_ai()
             && self.lobby.game.turn_for() == Team::Blue
             && frame - self.last_move_frame > 45
             && !self.lobby.finished()
+            && !self.lobby.in_setup()
         {
             let turn = self
                 .lobby
                 .game
-                .best_turn_auto(window().performance().unwrap().now().to_bits());
+                .best_turn_recommended(window().performance().unwrap().now().to_bits());
 
             if let Some(TurnLeaf(turn, _)) = turn {
                 message_pool.messages.append(&mut vec![Message::Turn(turn)]);
             }
         }
 
+        let mut events = Vec::new();
+
         for message in &message_pool.messages {
             match message {
                 Message::Turns(turns) => {
                     for Turn(from, to) in turns {
-                        if let Some(move_targets) = self.lobby.game.take_move(*from, *to) {
-                            target_positions.append(&mut move_targets.clone());
-
+                        if let Some(mut turn_events) = self.lobby.game.take_move(*from, *to) {
                             self.last_move_frame = frame;
-                            self.last_hits = move_targets;
+                            self.last_hits =
+                                turn_events.iter().filter_map(GameEvent::hit).collect();
+
+                            events.append(&mut turn_events);
                         }
                     }
                 }
                 Message::Turn(Turn(from, to)) => {
-                    let to_powerup = self.lobby.game.powerups().get(to).cloned();
-
-                    if let Some(move_targets) = self.lobby.game.take_move(*from, *to) {
-                        app_context.audio_system.play_clip(ClipId::MageMove);
-
-                        target_positions.append(&mut move_targets.clone());
-
-                        if let Some(moved_mage) = self.lobby.game.occupant(to) {
-                            if let Some(to_powerup) = to_powerup {
-                                app_context.audio_system.play_powerup(to_powerup);
-
-                                if to_powerup == PowerUp::Beam {
-                                    let particle_sort = match moved_mage.team {
-                                        Team::Red => ParticleSort::RedWin,
-                                        Team::Blue => ParticleSort::BlueWin,
-                                    };
-
-                                    for x in 0..self.lobby.game.board_size().0 {
-                                        for _ in 0..40 {
-                                            let d = js_sys::Math::random() * std::f64::consts::TAU;
-                                            let v = (js_sys::Math::random()
-                                                + js_sys::Math::random())
-                                                * 0.1;
-
-                                            self.particle_system.add(Particle::new(
-                                                (x as f64, to.1 as f64),
-                                                (d.cos() * v * 2.0, d.sin() * v * 0.5),
-                                                (js_sys::Math::random() * 50.0) as u64,
-                                                particle_sort,
-                                            ));
-                                        }
-                                    }
-
-                                    for y in 0..self.lobby.game.board_size().1 {
-                                        for _ in 0..40 {
-                                            let d = js_sys::Math::random() * std::f64::consts::TAU;
-                                            let v = (js_sys::Math::random()
-                                                + js_sys::Math::random())
-                                                * 0.1;
-
-                                            self.particle_system.add(Particle::new(
-                                                (to.0 as f64, y as f64),
-                                                (d.cos() * v * 0.5, d.sin() * v * 2.0),
-                                                (js_sys::Math::random() * 50.0) as u64,
-                                                particle_sort,
-                                            ));
-                                        }
-                                    }
-                                }
-                            }
-                        }
-
+                    if let Some(mut turn_events) = self.lobby.game.take_move(*from, *to) {
                         self.last_move_frame = frame;
-                        self.last_hits = move_targets;
+                        self.last_hits = turn_events.iter().filter_map(GameEvent::hit).collect();
+
+                        events.append(&mut turn_events);
                     }
                 }
                 Message::Lobby(lobby) => {
                     self.lobby = *lobby.clone();
                     self.board_dirty = true;
+                    self.rejoining = false;
 
+                    // The server only shows the default arrangement, so the one in progress is shown again.
+                    if let Some((team, positions)) =
+                        self.arrangement.as_ref().filter(|_| !self.arrangement_sent)
+                    {
+                        let _ = self.lobby.arrange(*team, positions);
+                    }
+
+                    if let Ok(lobby_id) = self.lobby_id() {
+                        if !lobby.all_ready()
+                            && !self.spectating
+                            && !lobby.has_session_id(session_id.as_ref())
+                        {
+                            send_ready(lobby_id, session_token.clone().unwrap())
+                                .map(|promise| promise.then(&self.message_closure));
+                        }
+                    }
+                }
+                Message::Joined(rejoin_token) => {
                     if let Ok(lobby_id) = self.lobby_id() {
-                        if !lobby.all_ready() {
-                            send_ready(lobby_id, session_id.clone().unwrap());
+                        save_rejoin_token(lobby_id, rejoin_token);
+                    }
+
+                    self.rejoin_token = Some(rejoin_token.clone());
+                }
+                Message::Presence(presence) => {
+                    let (team, action) = match presence {
+                        Presence::Joined(team) => (team, "joined"),
+                        Presence::Left(team) => (team, "left"),
+                        Presence::Forfeited(team) => (team, "forfeited"),
+                    };
+
+                    // Players are only told about their opponent, whose own slot they already know of.
+                    if self.lobby.player_team(session_id.as_ref()) != Some(*team) {
+                        let team = match team {
+                            Team::Red => "Red",
+                            Team::Blue => "Blue",
+                        };
+
+                        self.presence_message = Some((frame, format!("{team} {action}")));
+                    }
+                }
+                Message::Pick(sort) => {
+                    if let Some(team) = self.lobby.draft().and_then(Draft::turn_for) {
+                        if let Err(LobbyError(error)) = self.lobby.pick(team, *sort) {
+                            self.error_message = Some((frame, error));
+                        }
+                    }
+                }
+                Message::Place(sort, position) => {
+                    if let Some(team) = self.lobby.draft().and_then(Draft::turn_for) {
+                        if let Err(LobbyError(error)) = self.lobby.place(team, *sort, *position) {
+                            self.error_message = Some((frame, error));
+                        }
+                    }
+                }
+                Message::Deploy(positions) => {
+                    if let Some(team) = self.lobby.deployment().and_then(Deployment::turn_for) {
+                        if let Err(LobbyError(error)) = self.lobby.deploy(team, positions.clone()) {
+                            self.error_message = Some((frame, error));
                         }
                     }
                 }
+                Message::LobbyError(LobbyError(error)) => {
+                    // A game that cannot be restored is forgotten, rather than attempted again.
+                    if self.rejoining {
+                        if let Ok(lobby_id) = self.lobby_id() {
+                            clear_rejoin_token(lobby_id);
+                        }
+
+                        self.rejoin_failed = true;
+                    }
+
+                    self.error_message = Some((frame, error.clone()));
+                }
                 _ => (),
             }
         }
 
         message_pool.clear();
 
+        if !self.lobby.is_deploying() {
+            self.arrangement = None;
+            self.arrangement_sent = false;
+        }
+
+        for event in &events {
+            match event {
+                GameEvent::Moved { .. } => {
+                    app_context.audio_system.play_clip(ClipId::MageMove);
+                }
+                GameEvent::PickedUp { powerup, .. } => {
+                    app_context.audio_system.play_powerup(*powerup);
+                }
+                GameEvent::BeamFired { mage, at } => {
+                    let particle_sort = match self.lobby.game.get_mage(*mage).map(|mage| mage.team)
+                    {
+                        Some(Team::Blue) => ParticleSort::BlueWin,
+                        _ => ParticleSort::RedWin,
+                    };
+
+                    for x in 0..self.lobby.game.board_size().0 {
+                        for _ in 0..40 {
+                            let d = js_sys::Math::random() * std::f64::consts::TAU;
+                            let v = (js_sys::Math::random() + js_sys::Math::random()) * 0.1;
+
+                            self.particle_system.add(Particle::new(
+                                (x as f64, at.1 as f64),
+                                (d.cos() * v * 2.0, d.sin() * v * 0.5),
+                                (js_sys::Math::random() * 50.0) as u64,
+                                particle_sort,
+                            ));
+                        }
+                    }
+
+                    for y in 0..self.lobby.game.board_size().1 {
+                        for _ in 0..40 {
+                            let d = js_sys::Math::random() * std::f64::consts::TAU;
+                            let v = (js_sys::Math::random() + js_sys::Math::random()) * 0.1;
+
+                            self.particle_system.add(Particle::new(
+                                (at.0 as f64, y as f64),
+                                (d.cos() * v * 0.5, d.sin() * v * 2.0),
+                                (js_sys::Math::random() * 50.0) as u64,
+                                particle_sort,
+                            ));
+                        }
+                    }
+                }
+                GameEvent::Hit { at, .. } => {
+                    target_positions.push(*at);
+                }
+                _ => (),
+            }
+        }
+
+        self.visible_positions = if self.lobby.settings.fog_of_war && !self.lobby.finished() {
+            self.lobby
+                .player_team(session_id.as_ref())
+                .map(|team| self.lobby.game.visible_positions(team))
+        } else {
+            None
+        };
+
         for tile in &target_positions {
             for _ in 0..40 {
                 let d = js_sys::Math::random() * std::f64::consts::TAU;
@@ -851,10 +1470,32 @@ impl State for Game {
                     .draw(interface_context, atlas, &interface_pointer, frame)?;
             }
 
+            if self.is_arranging(app_context.session_id.as_ref()) {
+                self.button_deploy
+                    .draw(interface_context, atlas, &interface_pointer, frame)?;
+            }
+
+            self.draw_draft(
+                interface_context,
+                atlas,
+                frame,
+                app_context.session_id.as_ref(),
+            )?;
+
             if self.is_interface_active() {
                 self.interface
                     .draw(interface_context, atlas, &interface_pointer, frame)?;
 
+                if let (Ok(lobby_id), Some(rejoin_token)) = (self.lobby_id(), &self.rejoin_token) {
+                    draw_text_centered(
+                        interface_context,
+                        atlas,
+                        0.0,
+                        56.0,
+                        &format!("Rejoin code {}", rejoin_code(lobby_id, rejoin_token)),
+                    )?;
+                }
+
                 for player in self
                     .lobby
                     .players()
@@ -896,7 +1537,46 @@ impl State for Game {
 
             let session_id = app_context.session_id.as_ref();
 
-            if self.lobby.is_active_player(session_id) {
+            if let Some((error_frame, error)) = &self.error_message {
+                if frame - error_frame < 90 {
+                    draw_text_centered(interface_context, atlas, 0.0, 120.0, error)?;
+                }
+            }
+
+            if let Some((presence_frame, presence)) = &self.presence_message {
+                if frame - presence_frame < 90 {
+                    draw_text_centered(interface_context, atlas, 0.0, -120.0, presence)?;
+                }
+            }
+
+            if let Some(team) = self.lobby.draw_offer() {
+                if self.lobby.player_team(session_id) == Some(team.enemy()) {
+                    interface_context.save();
+                    interface_context.translate(
+                        -28.0 + self.board_offset().0 as f64 - 128.0,
+                        text_length("Draw offered") as f64 / 2.0,
+                    )?;
+                    interface_context.rotate(-PI / 2.0)?;
+                    draw_text(interface_context, atlas, 0.0, 0.0, "Draw offered")?;
+                    interface_context.restore();
+                }
+            }
+
+            if self.spectating {
+                draw_text_centered(interface_context, atlas, -164.0, 64.0, "Spectating")?;
+            }
+
+            if self.lobby.spectators() > 0 {
+                draw_text_centered(
+                    interface_context,
+                    atlas,
+                    -164.0,
+                    76.0,
+                    &format!("{} watching", self.lobby.spectators()),
+                )?;
+            }
+
+            if self.lobby.is_active_player(session_id) && !self.has_committed() {
                 interface_context.translate(
                     28.0 - self.board_offset().0 as f64 + 128.0,
                     0.0 - text_length("Your turn") as f64 / 2.0,
@@ -936,16 +1616,23 @@ impl State for Game {
         }
 
         if self.lobby.finished() {
+            // Finished games are not restored on startup.
+            if let (Some(_), Ok(lobby_id)) = (self.rejoin_token.take(), self.lobby_id()) {
+                clear_rejoin_token(lobby_id);
+            }
+
             if let Some(GameResult::Win(team)) = self.lobby.game.result() {
                 // Did not record the result in the KV-store yet...
                 if !self.recorded_result {
-                    App::kv_set(
-                        &self.lobby.game.prototype_code(),
-                        match team {
-                            Team::Red => "win",
-                            Team::Blue => "loss",
-                        },
-                    );
+                    if let Ok(level_code) = self.lobby.game.prototype_code() {
+                        App::kv_set(
+                            &level_code,
+                            match team {
+                                Team::Red => "win",
+                                Team::Blue => "loss",
+                            },
+                        );
+                    }
 
                     match team {
                         Team::Red => app_context.audio_system.play_clip(ClipId::LevelSuccess),
@@ -1028,30 +1715,74 @@ impl State for Game {
                         if self.lobby.is_local() {
                             return Some(StateSort::Game(Game::new(self.lobby.settings.clone())));
                         } else if let Ok(lobby_id) = self.lobby_id() {
-                            let session_id = app_context.session_id.clone().unwrap();
-                            let _ = send_rematch(lobby_id, session_id)
+                            let session_token = app_context.session_token.clone().unwrap();
+                            let _ = send_rematch(lobby_id, session_token)
                                 .unwrap()
                                 .then(&self.message_closure);
                         }
                     }
-                    BUTTON_LEAVE => match &self.lobby.settings {
-                        LobbySettings {
-                            loadout_method: LoadoutMethod::EditorPrefab(level),
-                            ..
-                        } => {
-                            return Some(StateSort::Editor(Editor::new(level.clone())));
+                    BUTTON_DRAW => {
+                        if let (Ok(lobby_id), Some(session_token)) =
+                            (self.lobby_id(), app_context.session_token.clone())
+                        {
+                            let offered_by_enemy = self
+                                .lobby
+                                .player_team(session_id.as_ref())
+                                .map(|team| self.lobby.draw_offer() == Some(team.enemy()))
+                                .unwrap_or(false);
+
+                            send_message(
+                                lobby_id,
+                                session_token,
+                                if offered_by_enemy {
+                                    Message::AcceptDraw
+                                } else {
+                                    Message::OfferDraw
+                                },
+                            )
+                            .map(|promise| promise.then(&self.message_closure));
+                        }
+                    }
+                    BUTTON_LEAVE => {
+                        if let Ok(lobby_id) = self.lobby_id() {
+                            clear_rejoin_token(lobby_id);
+
+                            // The slot is freed before the game starts, and forfeited if not rejoined after.
+                            if let Some(session_token) = app_context
+                                .session_token
+                                .clone()
+                                .filter(|_| !self.spectating)
+                            {
+                                let _ = send_leave(lobby_id, session_token);
+                            }
                         }
-                        LobbySettings {
-                            loadout_method: LoadoutMethod::Arena(_, position),
-                            ..
-                        } => {
-                            return Some(StateSort::ArenaMenu(ArenaMenu::at_position(*position)));
+
+                        match &self.lobby.settings {
+                            LobbySettings {
+                                loadout_method: LoadoutMethod::EditorPrefab(level),
+                                ..
+                            } => {
+                                return Some(StateSort::Editor(Editor::new(level.clone())));
+                            }
+                            LobbySettings {
+                                loadout_method: LoadoutMethod::Arena(_, position),
+                                ..
+                            } => {
+                                return Some(StateSort::ArenaMenu(ArenaMenu::at_position(
+                                    &app_context.campaign,
+                                    *position,
+                                )));
+                            }
+                            _ => return Some(StateSort::SkirmishMenu(SkirmishMenu::default())),
                         }
-                        _ => return Some(StateSort::SkirmishMenu(SkirmishMenu::default())),
-                    },
+                    }
                     _ => (),
                 }
             }
+        } else if self.lobby.is_drafting() {
+            self.tick_draft(app_context, &interface_pointer);
+        } else if self.lobby.is_deploying() {
+            self.tick_deployment(app_context, &interface_pointer);
         } else {
             if pointer.alt_clicked() {
                 self.deselect_mage();
@@ -1065,27 +1796,39 @@ impl State for Game {
                 ) {
                     if let Some(active_mage) = self.get_active_mage() {
                         let from = active_mage.position;
+                        let team = active_mage.team;
+
+                        if let Err(error) = self.lobby.game.check_move(from, selected_tile) {
+                            // Clicking another friendly mage selects it instead.
+                            if !self.lobby.game.live_occupied_by(&selected_tile, team) {
+                                self.error_message = Some((frame, error.to_string()));
+                            }
 
-                        if self.lobby.game.try_move(from, selected_tile) {
-                            if !self.lobby.is_local() && session_id.is_some() {
+                            self.select_mage_at(session_id.as_ref(), &selected_tile);
+                            self.play_mage_selection_sound(app_context);
+                        } else {
+                            if !self.lobby.is_local() && app_context.session_token.is_some() {
                                 send_message(
                                     self.lobby_id().unwrap(),
-                                    session_id.clone().unwrap(),
+                                    app_context.session_token.clone().unwrap(),
                                     Message::Turn(Turn(from, selected_tile)),
-                                );
+                                )
+                                .map(|promise| promise.then(&self.message_closure));
                             }
 
-                            let mut message_pool = message_pool.borrow_mut();
+                            if self.lobby.game.is_simultaneous() && !self.lobby.is_local() {
+                                // The turn is only played once the server has resolved the round.
+                                self.committed_round = Some(self.lobby.game.turns());
+                            } else {
+                                let mut message_pool = message_pool.borrow_mut();
 
-                            message_pool
-                                .messages
-                                .push(Message::Turn(Turn(from, selected_tile)));
+                                message_pool
+                                    .messages
+                                    .push(Message::Turn(Turn(from, selected_tile)));
+                            }
 
                             self.active_mage = None;
                             self.last_move_frame = frame;
-                        } else {
-                            self.select_mage_at(session_id.as_ref(), &selected_tile);
-                            self.play_mage_selection_sound(app_context);
                         }
                     } else {
                         self.select_mage_at(session_id.as_ref(), &selected_tile);
@@ -1097,6 +1840,10 @@ impl State for Game {
 
         self.tick_game(frame, app_context);
 
+        if self.rejoin_failed {
+            return Some(StateSort::MainMenu(MainMenu::default()));
+        }
+
         None
     }
 }
@@ -11,7 +11,7 @@ use crate::{
         UIElement, UIEvent,
     },
     draw::{draw_label, draw_mage, draw_powerup, draw_text, draw_text_centered},
-    net::{fetch, request_lobbies, MessagePool},
+    net::{fetch, parse_rejoin_code, request_lobbies, MessagePool},
 };
 
 pub struct LobbyList {
@@ -34,6 +34,9 @@ const BUTTON_ARENA: usize = 20;
 const BUTTON_BACK: usize = 21;
 const BUTTON_SEARCH: usize = 22;
 
+/// Watch buttons are identified by the lobby ID offset by this value, as join buttons use the lobby ID itself.
+const BUTTON_WATCH: usize = 1 << 16;
+
 const LOBBY_PAGE_SIZE: usize = 3;
 
 impl State for LobbyList {
@@ -185,7 +188,15 @@ impl State for LobbyList {
                     atlas,
                     72.0,
                     4.0,
-                    &format!("{}", lobby.settings.loadout_method),
+                    &if lobby.spectators() > 0 {
+                        format!(
+                            "{}, {} watching",
+                            lobby.settings.loadout_method,
+                            lobby.spectators()
+                        )
+                    } else {
+                        format!("{}", lobby.settings.loadout_method)
+                    },
                 )?;
 
                 context.restore();
@@ -211,6 +222,11 @@ impl State for LobbyList {
 
         if let Some((field, value)) = &app_context.text_input {
             if field == "lobby_code" {
+                // Rejoin codes from another device take over the player's slot in a game in progress.
+                if let Some((lobby_id, rejoin_token)) = parse_rejoin_code(value) {
+                    return Some(StateSort::Game(Game::rejoin(lobby_id, rejoin_token)));
+                }
+
                 if let Ok(lobby_code_input) = value.parse::<u16>() {
                     if self
                         .lobbies
@@ -232,7 +248,7 @@ impl State for LobbyList {
 
             if let BUTTON_SEARCH = value {
                 text_input.set_value("");
-                text_input.set_placeholder("Enter lobby code");
+                text_input.set_placeholder("Enter lobby or rejoin code");
                 text_input.dataset().set("field", "lobby_code").unwrap();
                 text_input.focus().unwrap();
             } else if let BUTTON_PAGE_PREVIOUS = value {
@@ -248,7 +264,13 @@ impl State for LobbyList {
 
         if let Some(UIEvent::ButtonClick(value, clip_id)) = self.lobby_list_interface.tick(pointer)
         {
-            if let Some(session_id) = &app_context.session_id {
+            if value >= BUTTON_WATCH {
+                app_context.audio_system.play_clip_option(clip_id);
+
+                return Some(StateSort::Game(Game::spectate(
+                    (value - BUTTON_WATCH) as u16,
+                )));
+            } else if let Some(session_id) = &app_context.session_id {
                 app_context.audio_system.play_clip_option(clip_id);
 
                 // console::log_1(&format!("{}", value).into());
@@ -307,18 +329,32 @@ impl State for LobbyList {
             self.lobby_list_interface = Interface::new(
                 self.displayed_lobbies
                     .iter()
-                    .map(|(i, (key, _lobby))| {
+                    .flat_map(|(i, (key, _lobby))| {
                         // console::log_1(&format!("INTERP {}", key).into());
                         let ir: usize = i - self.lobby_page * LOBBY_PAGE_SIZE;
-                        ButtonElement::new(
-                            (256 - 44, 44 + ir as i32 * 64),
-                            (24, 24),
-                            *key as usize,
-                            LabelTrim::Return,
-                            LabelTheme::Action,
-                            crate::app::ContentElement::Sprite((96, 32), (16, 16)),
-                        )
-                        .boxed()
+                        [
+                            ButtonElement::new(
+                                (256 - 44 - 48, 48 + ir as i32 * 64),
+                                (44, 16),
+                                BUTTON_WATCH + *key as usize,
+                                LabelTrim::Round,
+                                LabelTheme::Default,
+                                crate::app::ContentElement::Text(
+                                    "Watch".to_string(),
+                                    Alignment::Center,
+                                ),
+                            )
+                            .boxed(),
+                            ButtonElement::new(
+                                (256 - 44, 44 + ir as i32 * 64),
+                                (24, 24),
+                                *key as usize,
+                                LabelTrim::Return,
+                                LabelTheme::Action,
+                                crate::app::ContentElement::Sprite((96, 32), (16, 16)),
+                            )
+                            .boxed(),
+                        ]
                     })
                     .collect(),
             );
@@ -1,6 +1,9 @@
-use std::{collections::HashMap, f64::consts::TAU};
+use std::{
+    collections::{HashMap, HashSet},
+    f64::consts::TAU,
+};
 
-use shared::{Board, GameResult, Level, LobbySettings, Mage, Position, PowerUp, Team};
+use shared::{Board, Campaign, GameResult, Level, LobbySettings, Mage, Position, PowerUp, Team};
 use wasm_bindgen::JsValue;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlInputElement};
 
@@ -61,6 +64,9 @@ impl LevelPortal {
             }
         }
 
+        // Titles carried by the level itself take precedence.
+        let title = level.metadata.title.clone().unwrap_or(title);
+
         LevelPortal {
             level,
             title,
@@ -205,21 +211,104 @@ pub struct ArenaMenu {
 }
 
 impl ArenaMenu {
-    pub fn at_position(position: (isize, isize)) -> ArenaMenu {
-        let mut arena_menu = ArenaMenu {
-            pan_offset: (-position.0 as f64 * 128.0, -position.1 as f64 * 128.0),
-            ..Default::default()
-        };
+    pub fn new(campaign: &Campaign) -> ArenaMenu {
+        let button_battle = ButtonElement::new(
+            (64, 192),
+            (128, 24),
+            BUTTON_BATTLE,
+            LabelTrim::Glorious,
+            LabelTheme::Action,
+            crate::app::ContentElement::Text("Battle".to_string(), Alignment::Center),
+        );
 
-        let sparkle_due = if let Some(portal) = arena_menu.level_portals.get(&(0, 0)) {
+        let button_locked = ButtonElement::new(
+            (68, 192),
+            (120, 24),
+            BUTTON_BATTLE,
+            LabelTrim::Round,
+            LabelTheme::Disabled,
+            crate::app::ContentElement::Text("Locked".to_string(), Alignment::Center),
+        );
+
+        let button_back = ButtonElement::new(
+            (84, 224),
+            (88, 16),
+            BUTTON_BACK,
+            LabelTrim::Return,
+            LabelTheme::Default,
+            crate::app::ContentElement::Text("Back".to_string(), Alignment::Center),
+        );
+
+        let root_element = Interface::new(vec![button_back.boxed()]);
+
+        #[cfg(feature = "demo")]
+        let campaign = &campaign.clone().demo();
+
+        let mut level_portals = HashMap::new();
+        let mut won = HashSet::new();
+
+        // Mark portals as won based on the KV-store.
+
+        for portal in &campaign.portals {
+            // Portals with undecodable levels are left off the map.
+            let Ok(level) = portal.level() else {
+                continue;
+            };
+
+            // Results recorded before level codes were versioned are kept under the legacy code.
+            let status = if [level.as_code(), level.as_legacy_code()]
+                .iter()
+                .flatten()
+                .any(|level_code| App::kv_get(level_code) == "win")
+            {
+                won.insert(portal.position);
+                PortalStatus::Won
+            } else {
+                PortalStatus::Locked
+            };
+
+            level_portals.insert(
+                portal.position,
+                LevelPortal::from_level(level, portal.title.clone(), status),
+            );
+        }
+
+        // Unlock portals according to the campaign's rules.
+
+        for portal in &campaign.portals {
+            if let Some(level_portal) = level_portals.get_mut(&portal.position) {
+                if level_portal.status == PortalStatus::Locked && campaign.is_unlocked(portal, &won)
+                {
+                    level_portal.status = PortalStatus::Unlocked;
+                }
+            }
+        }
+
+        let sparkle_due = if let Some(portal) = level_portals.get(&(0, 0)) {
             portal.status == PortalStatus::Won
         } else {
             false
         };
 
-        arena_menu.sparkle_due = sparkle_due;
+        ArenaMenu {
+            interface: root_element,
+            button_locked,
+            button_battle,
+            particle_system: ParticleSystem::default(),
+            pan_offset: (0.0, 0.0),
+            pan_target: None,
+            pan_start: None,
+            board_dirty: true,
+            sparkle_due,
+            level_portals,
+        }
+    }
 
-        arena_menu
+    pub fn at_position(campaign: &Campaign, position: (isize, isize)) -> ArenaMenu {
+        ArenaMenu {
+            pan_offset: (-position.0 as f64 * 128.0, -position.1 as f64 * 128.0),
+            ..ArenaMenu::new(campaign)
+        }
     }
 
     fn drag_offset(&self, pointer: &Pointer) -> (f64, f64) {
@@ -418,353 +507,3 @@ impl State for ArenaMenu {
         None
     }
 }
-
-impl Default for ArenaMenu {
-    fn default() -> ArenaMenu {
-        let button_battle = ButtonElement::new(
-            (64, 192),
-            (128, 24),
-            BUTTON_BATTLE,
-            LabelTrim::Glorious,
-            LabelTheme::Action,
-            crate::app::ContentElement::Text("Battle".to_string(), Alignment::Center),
-        );
-
-        let button_locked = ButtonElement::new(
-            (68, 192),
-            (120, 24),
-            BUTTON_BATTLE,
-            LabelTrim::Round,
-            LabelTheme::Disabled,
-            crate::app::ContentElement::Text("Locked".to_string(), Alignment::Center),
-        );
-
-        let button_back = ButtonElement::new(
-            (84, 224),
-            (88, 16),
-            BUTTON_BACK,
-            LabelTrim::Return,
-            LabelTheme::Default,
-            crate::app::ContentElement::Text("Back".to_string(), Alignment::Center),
-        );
-
-        let root_element = Interface::new(vec![button_back.boxed()]);
-
-        let mut level_portals = HashMap::new();
-
-        level_portals.insert(
-            (0, 0),
-            LevelPortal::from_level(
-                "hg12g014cm0j800".into(),
-                "Basics I".to_string(),
-                PortalStatus::Unlocked,
-            ),
-        );
-        // 1v1 basic
-
-        level_portals.insert(
-            (1, 0),
-            LevelPortal::from_level(
-                "e01jg1148m0j8k834g00".into(),
-                "Basics II".to_string(),
-                PortalStatus::Locked,
-            ),
-        );
-        // 1v2 basic
-
-        level_portals.insert(
-            (2, 0),
-            LevelPortal::from_level(
-                "j0228014cm0j8v804gp04900".into(),
-                "Basics III".to_string(),
-                PortalStatus::Locked,
-            ),
-        );
-        // 2v2 easy
-
-        level_portals.insert(
-            (2, -1),
-            LevelPortal::from_level(
-                "j0228014cm0j8v804gp04906201g00s80dm07403g01g".into(),
-                "Basics IV".to_string(),
-                PortalStatus::Locked,
-            ),
-        );
-
-        #[cfg(not(feature = "demo"))]
-        {
-            level_portals.insert(
-                (3, -1),
-                LevelPortal::from_level(
-                    "pg32a0j4gm148t818h602h1g092900j409r06h03".into(),
-                    "Patterns I".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (4, -1),
-                LevelPortal::from_level(
-                    "pg2620a48m1m8c038ht02h04gg1jr0wg0d406".into(),
-                    "Patterns II".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (5, -1),
-                LevelPortal::from_level(
-                    "pg3220j4g41m8h818gr06h4g052780j400".into(),
-                    "Patterns III".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (4, 0),
-                LevelPortal::from_level(
-                    "h0120124d42480t40e204102".into(),
-                    "Diagonals I".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (4, 1),
-                LevelPortal::from_level(
-                    "f02220t4840m8e018hc06h04a014g0sg0cm04".into(),
-                    "Diagonals II".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (4, 2),
-                LevelPortal::from_level(
-                    "bg3200240g248h038gcg6h2s0h23t02408r04b02".into(),
-                    "Diagonals III".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (4, 3),
-                LevelPortal::from_level(
-                    "k036202444148h818ha02h1r0127g0j40m604k01dg1jr0wc08".into(),
-                    "Diagonals IV".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (5, -2),
-                LevelPortal::from_level(
-                    "j02620t4441m8c038hr06h055g1g00wg0dj06j01".into(),
-                    "Beams I".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (5, -3),
-                LevelPortal::from_level(
-                    "eg3020t4c40489818gr02h0m0d2780240gp06a03d00pr08".into(),
-                    "Beams II".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (6, -3),
-                LevelPortal::from_level(
-                    "qg22j0t4h41m8d038ja06h04gg13g0mr04j02".into(),
-                    "Beams III".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (5, 2),
-                LevelPortal::from_level(
-                    "x01420a4900m81a402204903rg1680r".into(),
-                    "Shields I".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (6, 2),
-                LevelPortal::from_level(
-                    "xg2420a4r40m9b018gp02h06x00080140f406t02gg10".into(),
-                    "Shields II".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (7, 2),
-                LevelPortal::from_level(
-                    "j02280j4500m8t818hpg4h025g06800".into(),
-                    "Shields III".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (2, 1),
-                LevelPortal::from_level(
-                    "hg2280a4d40490008g6g2h02cg12g00".into(),
-                    "Challenge I".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (3, 1),
-                LevelPortal::from_level(
-                    "hg18a09m4g0m81g00c4068035g14r0v008".into(),
-                    "Tutorial".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-            level_portals.insert(
-                (6, -1),
-                LevelPortal::from_level(
-                    "hg2680t44m048a028hmg2h04000gr0mc06004".into(),
-                    "Challenge II".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (7, -3),
-                LevelPortal::from_level(
-                    "q03220t4840m98828gw02h2r0d2bg0j40x804j03dg0k00s80a807200".into(),
-                    "Challenge III".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (7, 1),
-                LevelPortal::from_level(
-                    "qg3200t4000m90048jeg6h5x0523t1241e606c03701s00wm02206j015g1k80h802404".into(),
-                    "Challenge IV".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (7, -2),
-                LevelPortal::from_level(
-                    "t04420a4041m90818k0g6h2g052900a4t01m84038g2tr0n80cm06902d00g".into(),
-                    "Rite I".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (7, -1),
-                LevelPortal::from_level(
-                    "zg2220t4r4048f008ke06h0chg1pr0wr0b406w03j01qg0340ba06d03gg02g0r".into(),
-                    "Rite II".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (7, 0),
-                LevelPortal::from_level(
-                    "pg3820a44m2482808jp00h4g0h2380a410r04000m01r80nm00a06a03hg1g".into(),
-                    "Rite III".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (8, 0),
-                LevelPortal::from_level(
-                    "pg3820a44m2482808jp00h4g0h2380a410r04000m01r80nm00a06a03hg1g".into(),
-                    "Rite IV".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (8, -1),
-                LevelPortal::from_level(
-                    "zg322024w42499828hw04h6w0h25r02410t05j02n01j80vg0et00k01v01g".into(),
-                    "Ascension I".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-
-            level_portals.insert(
-                (9, -1),
-                LevelPortal::from_level(
-                    "zg4200t4000m90048kg00h4x0d2bt0a47m249z808g78r0sg0cw07403jg0f80w40d403m025g1k80h802405b03".into(),
-                    "Ascension II".to_string(),
-                    PortalStatus::Locked,
-                ),
-            );
-        }
-
-        // Update portal locking statuses based on the KV-store.
-
-        for portal in level_portals.values_mut() {
-            let level_code = portal.level.as_code();
-            let level_result = App::kv_get(&level_code);
-
-            if level_result == "win" {
-                portal.status = PortalStatus::Won;
-            }
-        }
-
-        // For each portal with an adjacent won portal, unlock it.
-
-        const DIRS: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
-
-        let mut to_unlock = Vec::new();
-
-        for position in level_portals.keys() {
-            for dir in DIRS {
-                let neighbour = level_portals.get(&(position.0 + dir.0, position.1 + dir.1));
-
-                if let Some(neighbour) = neighbour {
-                    if neighbour.status == PortalStatus::Won {
-                        to_unlock.push(*position);
-                    }
-                }
-            }
-        }
-
-        for position in to_unlock {
-            let portal = level_portals.get_mut(&position);
-
-            if let Some(portal) = portal {
-                if portal.status == PortalStatus::Locked {
-                    portal.status = PortalStatus::Unlocked;
-                }
-            }
-        }
-
-        let sparkle_due = if let Some(portal) = level_portals.get(&(0, 0)) {
-            portal.status == PortalStatus::Won
-        } else {
-            false
-        };
-
-        ArenaMenu {
-            interface: root_element,
-            button_locked,
-            button_battle,
-            particle_system: ParticleSystem::default(),
-            pan_offset: (0.0, 0.0),
-            pan_target: None,
-            pan_start: None,
-            board_dirty: true,
-            sparkle_due,
-            level_portals,
-        }
-    }
-}
@@ -79,7 +79,7 @@ impl State for MainMenu {
 
             match value {
                 BUTTON_ARENA => {
-                    return Some(StateSort::ArenaMenu(ArenaMenu::default()));
+                    return Some(StateSort::ArenaMenu(ArenaMenu::new(&app_context.campaign)));
                 }
                 BUTTON_EDITOR => {
                     return Some(StateSort::Editor(Editor::default()));
@@ -177,6 +177,7 @@ impl Default for MainMenu {
                 loadout_method: LoadoutMethod::DefaultBoard(Board::new(6, 7).unwrap()),
                 seed: window().performance().unwrap().now() as u64,
                 can_stalemate: true,
+                ..Default::default()
             }),
         }
     }
@@ -1,4 +1,4 @@
-use shared::{LoadoutMethod, Lobby, LobbySettings, LobbySort, Team};
+use shared::{Board, GeneratorParams, LoadoutMethod, Lobby, LobbySettings, LobbySort, Team};
 use wasm_bindgen::JsValue;
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, HtmlInputElement};
 
@@ -6,7 +6,7 @@ use super::{Game, LobbyList, MainMenu, State};
 use crate::{
     app::{
         Alignment, AppContext, ButtonElement, ButtonGroupElement, Interface, LabelTheme, LabelTrim,
-        StateSort, UIElement, UIEvent,
+        StateSort, ToggleButtonElement, UIElement, UIEvent,
     }, draw::{draw_mage, draw_mana, draw_sprite}, net::client_timestamp, window
 };
 
@@ -22,7 +22,11 @@ const BUTTON_ONLINE: usize = 3;
 const BUTTON_DEFAULT: usize = 10;
 const BUTTON_RANDOM: usize = 11;
 const BUTTON_SYMMETRIC_RANDOM: usize = 12;
-// const BUTTON_ROUND_ROBIN: usize = 13;
+const BUTTON_DRAFT: usize = 13;
+const BUTTON_FOG: usize = 14;
+const BUTTON_SIMULTANEOUS: usize = 15;
+const BUTTON_GENERATED: usize = 16;
+const BUTTON_DEPLOYMENT: usize = 17;
 const BUTTON_BATTLE: usize = 20;
 const BUTTON_BACK: usize = 21;
 const BUTTON_TELEPORT: usize = 30;
@@ -126,6 +130,28 @@ impl State for SkirmishMenu {
                     self.lobby_settings.loadout_method = LoadoutMethod::Random { symmetric: true };
                     self.refresh_lobby();
                 }
+                BUTTON_GENERATED => {
+                    self.lobby_settings.loadout_method = LoadoutMethod::Generated {
+                        params: GeneratorParams::default(),
+                    };
+                    self.refresh_lobby();
+                }
+                BUTTON_DRAFT => {
+                    self.lobby_settings.loadout_method = LoadoutMethod::Draft {
+                        board: Board::default(),
+                        mages: 4,
+                    };
+                    self.refresh_lobby();
+                }
+                BUTTON_FOG => {
+                    self.lobby_settings.fog_of_war ^= true;
+                }
+                BUTTON_SIMULTANEOUS => {
+                    self.lobby_settings.simultaneous ^= true;
+                }
+                BUTTON_DEPLOYMENT => {
+                    self.lobby_settings.deployment ^= true;
+                }
                 BUTTON_BATTLE => {
                     return Some(StateSort::Game(Game::new(self.lobby_settings.clone())));
                 }
@@ -190,15 +216,15 @@ impl Default for SkirmishMenu {
 
         let button_default = ButtonElement::new(
             (0, 0),
-            (80, 18),
+            (80, 16),
             BUTTON_DEFAULT,
             LabelTrim::Round,
             LabelTheme::Default,
             crate::app::ContentElement::Text("Default".to_string(), Alignment::Center),
         );
         let button_random = ButtonElement::new(
-            (0, 22),
-            (80, 18),
+            (0, 18),
+            (80, 16),
             BUTTON_SYMMETRIC_RANDOM,
             LabelTrim::Round,
             LabelTheme::Default,
@@ -206,34 +232,71 @@ impl Default for SkirmishMenu {
         );
 
         let button_symmetric_random = ButtonElement::new(
-            (0, 22 * 2),
-            (80, 18),
+            (0, 18 * 2),
+            (80, 16),
             BUTTON_RANDOM,
             LabelTrim::Round,
             LabelTheme::Default,
             crate::app::ContentElement::Text("Chaos".to_string(), Alignment::Center),
         );
 
-        // let button_round_robin = ButtonElement::new(
-        //     (0, 22 * 3),
-        //     (80, 18),
-        //     BUTTON_ROUND_ROBIN,
-        //     ButtonTrim::Round,
-        //     ButtonClass::Default,
-        //     crate::app::ContentElement::Text("Draft".to_string(), Alignment::Center),
-        // );
+        let button_generated = ButtonElement::new(
+            (0, 18 * 3),
+            (80, 16),
+            BUTTON_GENERATED,
+            LabelTrim::Round,
+            LabelTheme::Default,
+            crate::app::ContentElement::Text("Generated".to_string(), Alignment::Center),
+        );
+
+        let button_draft = ButtonElement::new(
+            (0, 18 * 4),
+            (80, 16),
+            BUTTON_DRAFT,
+            LabelTrim::Round,
+            LabelTheme::Default,
+            crate::app::ContentElement::Text("Draft".to_string(), Alignment::Center),
+        );
 
         let group_loadout_type = ButtonGroupElement::new(
-            (16, 112),
+            (16, 100),
             vec![
                 button_default,
                 button_random,
                 button_symmetric_random,
-                // button_round_robin,
+                button_generated,
+                button_draft,
             ],
             BUTTON_DEFAULT,
         );
 
+        let button_fog = ToggleButtonElement::new(
+            (16, 190),
+            (40, 18),
+            BUTTON_FOG,
+            LabelTrim::Round,
+            LabelTheme::Default,
+            crate::app::ContentElement::Text("Fog".to_string(), Alignment::Center),
+        );
+
+        let button_simultaneous = ToggleButtonElement::new(
+            (200, 190),
+            (40, 18),
+            BUTTON_SIMULTANEOUS,
+            LabelTrim::Round,
+            LabelTheme::Default,
+            crate::app::ContentElement::Text("Sim.".to_string(), Alignment::Center),
+        );
+
+        let button_deployment = ToggleButtonElement::new(
+            (218, 220),
+            (36, 16),
+            BUTTON_DEPLOYMENT,
+            LabelTrim::Round,
+            LabelTheme::Default,
+            crate::app::ContentElement::Text("Hide".to_string(), Alignment::Center),
+        );
+
         let button_battle = ButtonElement::new(
             (64, 188),
             (128, 24),
@@ -264,6 +327,9 @@ impl Default for SkirmishMenu {
         let root_element = Interface::new(vec![
             group_lobby_type.boxed(),
             group_loadout_type.boxed(),
+            button_fog.boxed(),
+            button_simultaneous.boxed(),
+            button_deployment.boxed(),
             button_battle.boxed(),
             button_teleport.boxed(),
             button_back.boxed(),
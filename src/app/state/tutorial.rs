@@ -247,6 +247,7 @@ impl Default for Tutorial {
                 loadout_method: LoadoutMethod::Prefab(level),
                 seed: window().performance().unwrap().now() as u64,
                 can_stalemate: false,
+                ..Default::default()
             }),
             tutorial_stage: TutorialStage::Movement,
         }
@@ -5,7 +5,10 @@ use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
 
 use crate::{
-    app::{ContentElement, LabelTrim, Particle, ParticleSort, Pointer, UIElement, BOARD_SCALE},
+    app::{
+        board_fit, ContentElement, LabelTrim, Particle, ParticleSort, Pointer, UIElement,
+        BOARD_SCALE,
+    },
     tuple_as,
 };
 
@@ -541,10 +544,14 @@ pub fn draw_board(
         clear_height as f64 * board_scale.1,
     );
 
+    // Boards larger than the cleared area are shrunk to fit it.
+    let fit = board_fit((board.width, board.height), clear_width.min(clear_height));
+
     atlas_context.translate(
-        dx + ((clear_width - board.width) as f64 * board_scale.0) / 2.0,
-        dy + ((clear_height - board.height) as f64 * board_scale.1) / 2.0,
+        dx + ((clear_width as f64 - board.width as f64 * fit) * board_scale.0 / 2.0).floor(),
+        dy + ((clear_height as f64 - board.height as f64 * fit) * board_scale.1 / 2.0).floor(),
     )?;
+    atlas_context.scale(fit, fit)?;
 
     let sprite_offset = board.style.sprite_offset();
 
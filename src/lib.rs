@@ -11,7 +11,7 @@ use std::{
 
 use app::{App, AudioSystem, CanvasSettings};
 use futures::Future;
-use net::{fetch, request_session};
+use net::{fetch, fetch_campaign, renew_session, request_session};
 use wasm_bindgen::{prelude::*, JsCast};
 use web_sys::{
     CanvasRenderingContext2d, Document, DomRect, FocusEvent, HtmlCanvasElement, HtmlImageElement,
@@ -95,6 +95,13 @@ async fn start() -> Result<(), JsValue> {
 
     audio_system.play_music(app::ClipId::MusicI);
 
+    // The campaign is fetched so that it can be edited without recompiling, with a bundled fallback.
+    let campaign =
+        match fetch_campaign(&format!("{RESOURCE_BASE_URL}/static/campaign/arena.json")).await {
+            Some(campaign) => campaign,
+            None => serde_json::from_str(include_str!("../static/campaign/arena.json")).unwrap(),
+        };
+
     {
         let atlas_img = atlas_img.clone();
 
@@ -123,7 +130,7 @@ async fn start() -> Result<(), JsValue> {
 
         atlas_context.draw_image_with_html_image_element(&atlas_img, 0.0, 0.0)?;
 
-        let app = App::new(&canvas_settings, audio_system.clone());
+        let app = App::new(&canvas_settings, audio_system.clone(), campaign);
 
         let app = Rc::new(RefCell::new(app));
 
@@ -146,8 +153,14 @@ async fn start() -> Result<(), JsValue> {
             {
                 let app = app.borrow();
 
-                if app.session_id().is_none() {
-                    let _ = fetch(&request_session()).then(&session_closure);
+                // Sessions are refreshed on every start, so only those unused for their whole lifetime expire.
+                match app.session_token() {
+                    Some(session_token) => {
+                        let _ = renew_session(session_token.clone()).then(&session_closure);
+                    }
+                    None => {
+                        let _ = fetch(&request_session()).then(&session_closure);
+                    }
                 }
             }
 
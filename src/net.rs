@@ -1,11 +1,11 @@
-use std::time::Duration;
+use std::{cell::RefCell, rc::Rc, time::Duration};
 
 use futures::TryFutureExt;
 use js_sys::Promise;
-use shared::{LobbyID, LobbySettings, Message, SessionMessage, SessionNewLobby, SessionRequest};
-use wasm_bindgen::{JsCast, JsValue};
+use shared::{Campaign, LobbyID, LobbySettings, Message, SessionNewLobby, SessionRejoin};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
 use wasm_bindgen_futures::{future_to_promise, JsFuture};
-use web_sys::{Request, RequestInit, Response};
+use web_sys::{EventSource, MessageEvent, Request, RequestInit, Response, WebSocket};
 
 use crate::{storage, window};
 
@@ -46,6 +46,106 @@ impl MessagePool {
     }
 }
 
+/// A WebSocket connection to an online lobby, over which the server pushes turns, lobby states and presence changes.
+/// Pushed messages land in the same [`MessagePool`] as polled ones; polling is only needed while the socket is not open.
+pub struct LobbySocket {
+    socket: WebSocket,
+    _message_closure: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl LobbySocket {
+    pub fn connect(
+        lobby_id: LobbyID,
+        session_token: &str,
+        message_pool: Rc<RefCell<MessagePool>>,
+    ) -> Option<LobbySocket> {
+        // Browsers cannot set headers on WebSocket connections, so the token is sent in the query.
+        let url = format!(
+            "{}/lobby/{lobby_id}/ws?token={session_token}",
+            API_URL.replacen("http", "ws", 1)
+        );
+        let socket = WebSocket::new(&url).ok()?;
+
+        let message_closure =
+            Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                if let Some(message) = event
+                    .data()
+                    .as_string()
+                    .and_then(|text| serde_json::from_str::<Message>(&text).ok())
+                {
+                    message_pool.borrow_mut().push(message);
+                }
+            });
+
+        socket.set_onmessage(Some(message_closure.as_ref().unchecked_ref()));
+
+        Some(LobbySocket {
+            socket,
+            _message_closure: message_closure,
+        })
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.socket.ready_state() == WebSocket::OPEN
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.socket.ready_state() == WebSocket::CLOSED
+    }
+}
+
+impl Drop for LobbySocket {
+    fn drop(&mut self) {
+        self.socket.set_onmessage(None);
+        let _ = self.socket.close();
+    }
+}
+
+/// A read-only stream of server-sent events from an online lobby, used to watch it as a spectator.
+pub struct LobbyStream {
+    source: EventSource,
+    _message_closure: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl LobbyStream {
+    pub fn connect(
+        lobby_id: LobbyID,
+        message_pool: Rc<RefCell<MessagePool>>,
+    ) -> Option<LobbyStream> {
+        let source = EventSource::new(&format!("{API_URL}/lobby/{lobby_id}/watch")).ok()?;
+        let message_closure = pooled_message_closure(message_pool);
+
+        source.set_onmessage(Some(message_closure.as_ref().unchecked_ref()));
+
+        Some(LobbyStream {
+            source,
+            _message_closure: message_closure,
+        })
+    }
+}
+
+impl Drop for LobbyStream {
+    fn drop(&mut self) {
+        self.source.set_onmessage(None);
+        self.source.close();
+    }
+}
+
+/// Creates a closure that parses the JSON text of a [`MessageEvent`] into a [`Message`] and adds it to the pool.
+fn pooled_message_closure(
+    message_pool: Rc<RefCell<MessagePool>>,
+) -> Closure<dyn FnMut(MessageEvent)> {
+    Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+        if let Some(message) = event
+            .data()
+            .as_string()
+            .and_then(|text| serde_json::from_str::<Message>(&text).ok())
+        {
+            message_pool.borrow_mut().push(message);
+        }
+    })
+}
+
 fn wrap_response_into_json(value: JsValue) -> JsFuture {
     assert!(value.is_instance_of::<Response>());
     let resp: Response = value.dyn_into().unwrap();
@@ -66,18 +166,52 @@ fn request_url(method: &str, url: &str) -> Request {
     Request::new_with_str_and_init(url, &opts).unwrap()
 }
 
+/// Fetches and validates the [`Campaign`] at the given URL.
+pub async fn fetch_campaign(url: &str) -> Option<Campaign> {
+    let value = JsFuture::from(fetch(&request_url("GET", url))).await.ok()?;
+    let campaign: Campaign = serde_wasm_bindgen::from_value(value).ok()?;
+
+    campaign.validate().is_ok().then_some(campaign)
+}
+
 pub fn request_session() -> Request {
     request_url("GET", &format!("{API_URL}/session"))
 }
 
-pub fn request_state(lobby_id: LobbyID) -> Request {
-    request_url("GET", &format!("{API_URL}/lobby/{lobby_id}/state"))
+/// Exchanges a session token for a fresh one, or obtains a new session if the token is no longer valid.
+/// Either way the promise resolves to a [`shared::SessionToken`].
+pub fn renew_session(session_token: String) -> Promise {
+    future_to_promise(async move {
+        let refresh = request_url("POST", &format!("{API_URL}/session/refresh"));
+        authorize(&refresh, &session_token);
+
+        let window = web_sys::window().unwrap();
+        let mut response: Response = JsFuture::from(window.fetch_with_request(&refresh))
+            .await?
+            .dyn_into()?;
+
+        if !response.ok() {
+            response = JsFuture::from(window.fetch_with_request(&request_session()))
+                .await?
+                .dyn_into()?;
+        }
+
+        JsFuture::from(response.json()?).await
+    })
+}
+
+pub fn request_state(lobby_id: LobbyID, session_token: String) -> Option<Promise> {
+    post_probe(format!("{API_URL}/lobby/{lobby_id}/state"), session_token)
 }
 
-pub fn request_turns_since(lobby_id: LobbyID, session_id: String, since: usize) -> Option<Promise> {
+pub fn request_turns_since(
+    lobby_id: LobbyID,
+    session_token: String,
+    since: usize,
+) -> Option<Promise> {
     post_probe(
         format!("{API_URL}/lobby/{lobby_id}/turns/{since}"),
-        session_id,
+        session_token,
     )
 }
 
@@ -85,7 +219,7 @@ pub fn request_lobbies() -> Request {
     request_url("GET", &format!("{API_URL}/lobbies"))
 }
 
-pub fn create_new_lobby(lobby_settings: LobbySettings) -> Option<Promise> {
+pub fn create_new_lobby(lobby_settings: LobbySettings, session_token: String) -> Option<Promise> {
     let session_request = SessionNewLobby { lobby_settings };
 
     if let Ok(json) = serde_json::to_string(&session_request) {
@@ -101,6 +235,7 @@ pub fn create_new_lobby(lobby_settings: LobbySettings) -> Option<Promise> {
             .headers()
             .set("Content-Type", "application/json")
             .unwrap();
+        authorize(request, &session_token);
 
         Some(fetch(request))
     } else {
@@ -108,20 +243,54 @@ pub fn create_new_lobby(lobby_settings: LobbySettings) -> Option<Promise> {
     }
 }
 
-pub fn post_probe(url: String, session_id: String) -> Option<Promise> {
-    let session_request = SessionRequest { session_id };
+/// Authenticates a request with the session token as a `Bearer` token.
+fn authorize(request: &Request, session_token: &str) {
+    request
+        .headers()
+        .set("Authorization", &format!("Bearer {session_token}"))
+        .unwrap();
+}
 
-    if let Ok(json) = serde_json::to_string(&session_request) {
+pub fn post_probe(url: String, session_token: String) -> Option<Promise> {
+    let request = request_url("POST", &url);
+    authorize(&request, &session_token);
+
+    Some(fetch(&request))
+}
+
+pub fn send_ready(lobby_id: LobbyID, session_token: String) -> Option<Promise> {
+    post_probe(format!("{API_URL}/lobby/{lobby_id}/ready"), session_token)
+}
+
+pub fn send_rematch(lobby_id: LobbyID, session_token: String) -> Option<Promise> {
+    post_probe(format!("{API_URL}/lobby/{lobby_id}/rematch"), session_token)
+}
+
+pub fn send_leave(lobby_id: LobbyID, session_token: String) -> Option<Promise> {
+    post_probe(format!("{API_URL}/lobby/{lobby_id}/leave"), session_token)
+}
+
+pub fn send_rejoin(
+    lobby_id: LobbyID,
+    session_token: String,
+    rejoin_token: String,
+) -> Option<Promise> {
+    let session_rejoin = SessionRejoin { rejoin_token };
+
+    if let Ok(json) = serde_json::to_string(&session_rejoin) {
         let mut opts = RequestInit::new();
         opts.method("POST");
         opts.body(Some(&json.into()));
 
+        let url = format!("{API_URL}/lobby/{lobby_id}/rejoin");
+
         let request = &Request::new_with_str_and_init(&url, &opts).unwrap();
 
         request
             .headers()
             .set("Content-Type", "application/json")
             .unwrap();
+        authorize(request, &session_token);
 
         Some(fetch(request))
     } else {
@@ -129,21 +298,8 @@ pub fn post_probe(url: String, session_id: String) -> Option<Promise> {
     }
 }
 
-pub fn send_ready(lobby_id: LobbyID, session_id: String) -> Option<Promise> {
-    post_probe(format!("{API_URL}/lobby/{lobby_id}/ready"), session_id)
-}
-
-pub fn send_rematch(lobby_id: LobbyID, session_id: String) -> Option<Promise> {
-    post_probe(format!("{API_URL}/lobby/{lobby_id}/rematch"), session_id)
-}
-
-pub fn send_message(lobby_id: LobbyID, session_id: String, message: Message) -> Option<Promise> {
-    let session_message = SessionMessage {
-        session_id,
-        message,
-    };
-
-    if let Ok(json) = serde_json::to_string(&session_message) {
+pub fn send_message(lobby_id: LobbyID, session_token: String, message: Message) -> Option<Promise> {
+    if let Ok(json) = serde_json::to_string(&message) {
         let mut opts = RequestInit::new();
         opts.method("POST");
         opts.body(Some(&json.into()));
@@ -156,6 +312,7 @@ pub fn send_message(lobby_id: LobbyID, session_id: String, message: Message) ->
             .headers()
             .set("Content-Type", "application/json")
             .unwrap();
+        authorize(request, &session_token);
 
         Some(fetch(request))
     } else {
@@ -167,6 +324,42 @@ pub fn get_session_id() -> Option<String> {
     storage().and_then(|storage| storage.get_item("session_id").unwrap_or_default())
 }
 
+pub fn get_session_token() -> Option<String> {
+    storage().and_then(|storage| storage.get_item("session_token").unwrap_or_default())
+}
+
+/// Formats a lobby ID and rejoin token as a code, which can be entered on another device to rejoin the lobby.
+pub fn rejoin_code(lobby_id: LobbyID, rejoin_token: &str) -> String {
+    format!("{lobby_id}-{rejoin_token}")
+}
+
+/// Parses a code made by [`rejoin_code`] into its lobby ID and rejoin token.
+pub fn parse_rejoin_code(code: &str) -> Option<(LobbyID, String)> {
+    let (lobby_id, rejoin_token) = code.trim().split_once('-')?;
+
+    Some((lobby_id.parse().ok()?, rejoin_token.to_string()))
+        .filter(|(_, rejoin_token)| !rejoin_token.is_empty())
+}
+
+/// Remembers the rejoin token of the online game in progress, so it can be restored on startup.
+pub fn save_rejoin_token(lobby_id: LobbyID, rejoin_token: &str) {
+    storage().map(|storage| storage.set_item("rejoin", &rejoin_code(lobby_id, rejoin_token)));
+}
+
+/// Returns the lobby ID and rejoin token of the online game in progress, if there is one.
+pub fn load_rejoin_token() -> Option<(LobbyID, String)> {
+    storage()
+        .and_then(|storage| storage.get_item("rejoin").unwrap_or_default())
+        .and_then(|code| parse_rejoin_code(&code))
+}
+
+/// Forgets the rejoin token of a lobby, once its game is over or left.
+pub fn clear_rejoin_token(lobby_id: LobbyID) {
+    if load_rejoin_token().is_some_and(|(saved_id, _)| saved_id == lobby_id) {
+        storage().map(|storage| storage.remove_item("rejoin"));
+    }
+}
+
 pub fn client_timestamp() -> Duration {
     let since_the_epoch = window().performance().unwrap().now() as u64;
 